@@ -9,8 +9,8 @@ use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    f64_to_token_amount, get_ipc_provider, print_tx_result, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 /// The command to send funds to a subnet from parent
@@ -38,18 +38,19 @@ impl CommandLineHandler for Fund {
             None => None,
         };
 
-        println!(
-            "fund performed in epoch: {:?}",
-            provider
-                .fund(
-                    subnet,
-                    gateway_addr,
-                    from,
-                    to,
-                    f64_to_token_amount(arguments.amount)?,
-                )
-                .await?,
-        );
+        let result = provider
+            .fund(
+                subnet,
+                gateway_addr,
+                from,
+                to,
+                f64_to_token_amount(arguments.amount)?,
+            )
+            .await?;
+        print_tx_result(global, result, |outcome| {
+            println!("fund performed in epoch: {}", outcome.block_number);
+            println!("{outcome}");
+        })?;
 
         Ok(())
     }
@@ -89,14 +90,16 @@ impl CommandLineHandler for PreFund {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
-        provider
+        let result = provider
             .pre_fund(
                 subnet.clone(),
                 from,
                 f64_to_token_amount(arguments.initial_balance)?,
             )
             .await?;
-        log::info!("address pre-funded successfully");
+        print_tx_result(global, result, |_| {
+            log::info!("address pre-funded successfully")
+        })?;
 
         Ok(())
     }
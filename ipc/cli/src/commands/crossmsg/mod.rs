@@ -2,6 +2,8 @@
 // SPDX-License-Identifier: MIT
 use self::fund::{PreFund, PreFundArgs};
 use self::release::{PreRelease, PreReleaseArgs};
+use self::send::{SendCrossMsg, SendCrossMsgArgs};
+use self::status::{CrossMsgStatus, CrossMsgStatusArgs};
 use self::topdown_cross::{
     LatestParentFinality, LatestParentFinalityArgs, ListTopdownMsgs, ListTopdownMsgsArgs,
 };
@@ -18,6 +20,8 @@ use clap::{Args, Subcommand};
 pub mod fund;
 pub mod propagate;
 pub mod release;
+mod send;
+mod status;
 mod topdown_cross;
 
 #[derive(Debug, Args)]
@@ -38,6 +42,8 @@ impl CrossMsgsCommandsArgs {
             Commands::Propagate(args) => Propagate::handle(global, args).await,
             Commands::ListTopdownMsgs(args) => ListTopdownMsgs::handle(global, args).await,
             Commands::ParentFinality(args) => LatestParentFinality::handle(global, args).await,
+            Commands::Status(args) => CrossMsgStatus::handle(global, args).await,
+            Commands::Send(args) => SendCrossMsg::handle(global, args).await,
         }
     }
 }
@@ -51,4 +57,6 @@ pub(crate) enum Commands {
     Propagate(PropagateArgs),
     ListTopdownMsgs(ListTopdownMsgsArgs),
     ParentFinality(LatestParentFinalityArgs),
+    Status(CrossMsgStatusArgs),
+    Send(SendCrossMsgArgs),
 }
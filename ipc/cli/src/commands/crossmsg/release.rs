@@ -8,8 +8,8 @@ use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    f64_to_token_amount, get_ipc_provider, print_tx_result, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 /// The command to release funds from a child to a parent
@@ -41,19 +41,20 @@ impl CommandLineHandler for Release {
             None => None,
         };
 
-        println!(
-            "release performed in epoch: {:?}",
-            provider
-                .release(
-                    subnet,
-                    gateway_addr,
-                    from,
-                    to,
-                    f64_to_token_amount(arguments.amount)?,
-                    fee,
-                )
-                .await?,
-        );
+        let result = provider
+            .release(
+                subnet,
+                gateway_addr,
+                from,
+                to,
+                f64_to_token_amount(arguments.amount)?,
+                fee,
+            )
+            .await?;
+        print_tx_result(global, result, |outcome| {
+            println!("release performed in epoch: {}", outcome.block_number);
+            println!("{outcome}");
+        })?;
 
         Ok(())
     }
@@ -95,10 +96,12 @@ impl CommandLineHandler for PreRelease {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
-        provider
+        let result = provider
             .pre_release(subnet.clone(), from, f64_to_token_amount(arguments.amount)?)
             .await?;
-        log::info!("address pre-release successfully");
+        print_tx_result(global, result, |_| {
+            log::info!("address pre-release successfully")
+        })?;
 
         Ok(())
     }
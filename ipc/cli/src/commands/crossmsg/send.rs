@@ -0,0 +1,134 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Send cross-net message cli command handler.
+
+use async_trait::async_trait;
+use clap::Args;
+use fvm_ipld_encoding::RawBytes;
+use ipc_sdk::address::IPCAddress;
+use ipc_sdk::cross::CrossMsgBuilder;
+use ipc_sdk::subnet_id::SubnetID;
+use std::{fmt::Debug, str::FromStr};
+
+use crate::{
+    f64_to_token_amount, get_ipc_provider, print_tx_result, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
+};
+
+/// The command to send a cross-net message calling a contract in another subnet
+pub(crate) struct SendCrossMsg;
+
+#[async_trait]
+impl CommandLineHandler for SendCrossMsg {
+    type Arguments = SendCrossMsgArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("send cross-msg with args: {:?}", arguments);
+
+        let mut provider = get_ipc_provider(global)?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let from = match &arguments.from {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+        let from = provider.sender(&subnet, from)?;
+        let gateway_addr = match &arguments.gateway_address {
+            Some(address) => Some(require_fil_addr_from_str(address)?),
+            None => None,
+        };
+
+        let to = IPCAddress::from_str(&arguments.to)?;
+
+        // messages that go through other subnets need relayers and propagate calls along
+        // the way, so the route is shown before sending.
+        match provider
+            .plan_route(&IPCAddress::new(&subnet, &from)?, &to)
+            .await
+        {
+            Ok(plan) if plan.is_multi_hop() => println!("{plan}"),
+            Ok(_) => {}
+            Err(e) => log::warn!("cannot plan the route of the message: {e:#}"),
+        }
+
+        let mut msg = CrossMsgBuilder::new(to).wrapped(arguments.wrapped);
+        if let Some(signature) = &arguments.signature {
+            msg = msg.abi_call(signature, &arguments.args)?;
+        }
+        if let Some(method) = arguments.method {
+            msg = msg.method(method);
+        }
+        if let Some(params) = &arguments.params {
+            msg = msg.params(RawBytes::new(hex::decode(params.trim_start_matches("0x"))?));
+        }
+        if let Some(value) = arguments.value {
+            msg = msg.value(f64_to_token_amount(value)?);
+        }
+        if let Some(fee) = arguments.fee {
+            msg = msg.fee(f64_to_token_amount(fee)?);
+        }
+
+        let result = provider
+            .send_cross_message(subnet, gateway_addr, Some(from), msg)
+            .await?;
+        print_tx_result(global, result, |outcome| {
+            println!("cross-msg sent in epoch: {}", outcome.block_number);
+            println!("{outcome}");
+        })?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "send",
+    about = "Send a cross-net message calling a contract in another subnet"
+)]
+pub(crate) struct SendCrossMsgArgs {
+    #[arg(long, short, help = "The gateway address of the subnet")]
+    pub gateway_address: Option<String>,
+    #[arg(long, short, help = "The address that sends the message")]
+    pub from: Option<String>,
+    #[arg(long, short, help = "The subnet to send the message from")]
+    pub subnet: String,
+    #[arg(
+        long,
+        short,
+        help = "The IPC address of the destination, i.e. <subnet id>:<address>"
+    )]
+    pub to: String,
+    #[arg(
+        long,
+        help = "The signature of the Solidity function to call, e.g. \"transfer(address,uint256)\""
+    )]
+    pub signature: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "signature",
+        help = "The method number to call, if not calling a Solidity function"
+    )]
+    pub method: Option<u64>,
+    #[arg(
+        long,
+        conflicts_with = "signature",
+        help = "The hex encoded params of the method"
+    )]
+    pub params: Option<String>,
+    #[arg(long, help = "The value sent with the message, in whole FIL")]
+    pub value: Option<f64>,
+    #[arg(
+        long,
+        help = "The fee to pay for the cross-net message in whole FIL, defaults to the gateway fee"
+    )]
+    pub fee: Option<f64>,
+    #[arg(
+        long,
+        help = "Wrap the message so that the destination learns the original sender"
+    )]
+    pub wrapped: bool,
+    #[arg(
+        requires = "signature",
+        help = "The arguments of the Solidity function, in order"
+    )]
+    pub args: Vec<String>,
+}
@@ -0,0 +1,71 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Track the stage of a cross-net message cli command
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_provider::lifecycle::DEFAULT_POLL_INTERVAL;
+use ipc_sdk::subnet_id::SubnetID;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::commands::get_ipc_provider;
+use crate::{CommandLineHandler, GlobalArguments};
+
+/// The command to get the stage of a fund sent to a child subnet or of a release from it.
+pub(crate) struct CrossMsgStatus;
+
+#[async_trait]
+impl CommandLineHandler for CrossMsgStatus {
+    type Arguments = CrossMsgStatusArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("cross-msg status with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global)?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+        let tx_hash = hex::decode(arguments.tx.trim_start_matches("0x"))?;
+
+        match (arguments.release, arguments.wait) {
+            (false, false) => println!("{}", provider.fund_status(&subnet, &tx_hash).await?),
+            (false, true) => println!(
+                "{}",
+                provider
+                    .wait_fund_executed(&subnet, &tx_hash, DEFAULT_POLL_INTERVAL)
+                    .await?
+            ),
+            (true, false) => println!("{}", provider.release_status(&subnet, &tx_hash).await?),
+            (true, true) => println!(
+                "{}",
+                provider
+                    .wait_release_executed(&subnet, &tx_hash, DEFAULT_POLL_INTERVAL)
+                    .await?
+            ),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "status",
+    about = "Get the stage of a fund to a child subnet or of a release from it"
+)]
+pub(crate) struct CrossMsgStatusArgs {
+    #[arg(
+        long,
+        short,
+        help = "The subnet id of the child subnet that was funded or released from"
+    )]
+    pub subnet: String,
+    #[arg(
+        long,
+        help = "The hash of the fund transaction in the parent, or of the release in the child"
+    )]
+    pub tx: String,
+    #[arg(long, help = "The transaction is a release from the child subnet")]
+    pub release: bool,
+    #[arg(long, help = "Wait until the message is executed in the destination subnet")]
+    pub wait: bool,
+}
@@ -9,6 +9,7 @@ use std::str::FromStr;
 use async_trait::async_trait;
 use clap::Args;
 use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::cross::CrossMsg;
 use ipc_sdk::subnet_id::SubnetID;
 
 use crate::commands::get_ipc_provider;
@@ -27,6 +28,25 @@ impl CommandLineHandler for ListTopdownMsgs {
         let provider = get_ipc_provider(global)?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
 
+        if let (Some(from), Some(to)) = (arguments.from_epoch, arguments.to_epoch) {
+            let msgs = provider.get_top_down_msgs_range(&subnet, from, to).await?;
+            for msgs in msgs {
+                println!(
+                    "epoch: {}, block hash: {}, nonces: {:?}",
+                    msgs.height,
+                    hex::encode(&msgs.block_hash),
+                    msgs.nonces()
+                );
+                for msg in msgs.msgs {
+                    print_msg(&msg)?;
+                }
+            }
+            return Ok(());
+        }
+
+        let epoch = arguments
+            .epoch
+            .ok_or_else(|| anyhow!("either --epoch or --from-epoch and --to-epoch are required"))?;
         let hash = if let Some(hash) = &arguments.block_hash {
             hex::decode(hash)?
         } else {
@@ -37,33 +57,54 @@ impl CommandLineHandler for ListTopdownMsgs {
             let hash = provider.get_block_hash(&parent, epoch).await?;
             hash.block_hash
         };
-        let msgs = provider
-            .get_top_down_msgs(&subnet, arguments.epoch, &hash)
-            .await?;
+        let msgs = provider.get_top_down_msgs(&subnet, epoch, &hash).await?;
         for msg in msgs {
-            println!(
-                "from: {}, to: {}, value: {}, nonce: {}, fee: {} ",
-                msg.msg.from.to_string()?,
-                msg.msg.to.to_string()?,
-                msg.msg.value,
-                msg.msg.nonce,
-                msg.msg.fee
-            );
+            print_msg(&msg)?;
         }
 
         Ok(())
     }
 }
 
+fn print_msg(msg: &CrossMsg) -> anyhow::Result<()> {
+    println!(
+        "from: {}, to: {}, value: {}, nonce: {}, fee: {} ",
+        msg.msg.from.to_string()?,
+        msg.msg.to.to_string()?,
+        msg.msg.value,
+        msg.msg.nonce,
+        msg.msg.fee
+    );
+    Ok(())
+}
+
 #[derive(Debug, Args)]
-#[command(about = "List topdown cross messages for a specific epoch")]
+#[command(about = "List topdown cross messages for a specific epoch or range of epochs")]
 pub(crate) struct ListTopdownMsgsArgs {
     #[arg(long, short, help = "The subnet id of the topdown subnet")]
     pub subnet: String,
-    #[arg(long, short, help = "Include topdown messages of this epoch")]
-    pub epoch: ChainEpoch,
+    #[arg(
+        long,
+        short,
+        required_unless_present = "from_epoch",
+        help = "Include topdown messages of this epoch"
+    )]
+    pub epoch: Option<ChainEpoch>,
     #[arg(long, short, help = "The block hash to query until")]
     pub block_hash: Option<String>,
+    #[arg(
+        long,
+        requires = "to_epoch",
+        conflicts_with_all = ["epoch", "block_hash"],
+        help = "Include topdown messages from this epoch, checking the block hashes chain"
+    )]
+    pub from_epoch: Option<ChainEpoch>,
+    #[arg(
+        long,
+        requires = "from_epoch",
+        help = "Include topdown messages until this epoch, inclusive"
+    )]
+    pub to_epoch: Option<ChainEpoch>,
 }
 
 pub(crate) struct LatestParentFinality;
@@ -7,11 +7,13 @@ mod config;
 mod crossmsg;
 // mod daemon;
 mod subnet;
+mod tx;
 mod util;
 mod wallet;
 
 use crate::commands::checkpoint::CheckpointCommandsArgs;
 use crate::commands::crossmsg::CrossMsgsCommandsArgs;
+use crate::commands::tx::TxCommandsArgs;
 use crate::commands::util::UtilCommandsArgs;
 use crate::GlobalArguments;
 use anyhow::{anyhow, Context, Result};
@@ -22,6 +24,7 @@ use fvm_shared::econ::TokenAmount;
 use ipc_sdk::ethers_address_to_fil_address;
 
 use ipc_provider::config::{Config, Subnet};
+use ipc_provider::manager::{TxResult, UnsignedTransaction};
 use ipc_sdk::subnet_id::SubnetID;
 use std::fmt::Debug;
 use std::io;
@@ -45,6 +48,7 @@ enum Commands {
     Wallet(WalletCommandsArgs),
     CrossMsg(CrossMsgsCommandsArgs),
     Checkpoint(CheckpointCommandsArgs),
+    Tx(TxCommandsArgs),
     Util(UtilCommandsArgs),
 }
 
@@ -111,6 +115,7 @@ pub async fn cli() -> anyhow::Result<()> {
                 Commands::CrossMsg(args) => args.handle(global).await,
                 Commands::Wallet(args) => args.handle(global).await,
                 Commands::Checkpoint(args) => args.handle(global).await,
+                Commands::Tx(args) => args.handle(global).await,
                 Commands::Util(args) => args.handle(global).await,
             };
 
@@ -121,12 +126,41 @@ pub async fn cli() -> anyhow::Result<()> {
     }
 }
 
+fn write_unsigned_tx(global: &GlobalArguments, unsigned: &UnsignedTransaction) -> Result<()> {
+    let path = global
+        .unsigned_out()
+        .ok_or_else(|| anyhow!("no file to write the unsigned transaction to"))?;
+    std::fs::write(path, serde_json::to_string_pretty(unsigned)?)?;
+    println!("{unsigned}");
+    println!("unsigned transaction written to {path}, sign it with `wallet sign-tx`");
+    Ok(())
+}
+
+/// Prints the outcome of a state-changing operation with `on_sent`, or the report of its
+/// simulation in dry-run mode. With --unsigned-out the transaction is written to be signed
+/// offline instead.
+pub(crate) fn print_tx_result<T>(
+    global: &GlobalArguments,
+    result: TxResult<T>,
+    on_sent: impl FnOnce(T),
+) -> Result<()> {
+    match result {
+        TxResult::Sent(outcome) => on_sent(outcome),
+        TxResult::Simulated(report) => println!("{report}"),
+        TxResult::Unsigned(unsigned) => write_unsigned_tx(global, &unsigned)?,
+    }
+    Ok(())
+}
+
 fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut io::stdout());
 }
 
 pub(crate) fn get_ipc_provider(global: &GlobalArguments) -> Result<ipc_provider::IpcProvider> {
-    ipc_provider::IpcProvider::new_from_config(global.config_path())
+    let mut provider = ipc_provider::IpcProvider::new_from_config(global.config_path())?;
+    provider.with_dry_run(global.dry_run());
+    provider.with_offline_signing(global.unsigned_out().is_some());
+    Ok(provider)
 }
 
 pub(crate) fn f64_to_token_amount(f: f64) -> anyhow::Result<TokenAmount> {
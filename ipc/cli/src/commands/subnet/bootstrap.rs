@@ -7,7 +7,10 @@ use clap::Args;
 use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
-use crate::{get_ipc_provider, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
+use crate::{
+    get_ipc_provider, print_tx_result, require_fil_addr_from_str, CommandLineHandler,
+    GlobalArguments,
+};
 
 /// The command to add a bootstrap subnet
 pub struct AddBootstrap;
@@ -26,9 +29,14 @@ impl CommandLineHandler for AddBootstrap {
             None => None,
         };
 
-        provider
+        let result = provider
             .add_bootstrap(&subnet, from, arguments.endpoint.clone())
-            .await
+            .await?;
+        print_tx_result(global, result, |outcome| {
+            println!("bootstrap added in {outcome}")
+        })?;
+
+        Ok(())
     }
 }
 
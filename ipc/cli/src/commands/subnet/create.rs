@@ -4,12 +4,14 @@
 
 use async_trait::async_trait;
 use clap::Args;
+use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
+use ipc_provider::manager::{TxOutcome, TxResult};
 use ipc_sdk::subnet_id::SubnetID;
 use std::fmt::Debug;
 use std::str::FromStr;
 
-use crate::commands::get_ipc_provider;
+use crate::commands::{get_ipc_provider, print_tx_result};
 use crate::{f64_to_token_amount, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
 
 const DEFAULT_ACTIVE_VALIDATORS: u16 = 100;
@@ -21,7 +23,7 @@ impl CreateSubnet {
     pub async fn create(
         global: &GlobalArguments,
         arguments: &CreateSubnetArgs,
-    ) -> anyhow::Result<String> {
+    ) -> anyhow::Result<TxResult<(Address, TxOutcome)>> {
         let mut provider = get_ipc_provider(global)?;
         let parent = SubnetID::from_str(&arguments.parent)?;
 
@@ -30,7 +32,7 @@ impl CreateSubnet {
             None => None,
         };
 
-        let addr = provider
+        provider
             .create_subnet(
                 from,
                 parent,
@@ -42,9 +44,7 @@ impl CreateSubnet {
                     .unwrap_or(DEFAULT_ACTIVE_VALIDATORS),
                 f64_to_token_amount(arguments.min_cross_msg_fee)?,
             )
-            .await?;
-
-        Ok(addr.to_string())
+            .await
     }
 }
 
@@ -55,13 +55,15 @@ impl CommandLineHandler for CreateSubnet {
     async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
         log::debug!("create subnet with args: {:?}", arguments);
 
-        let address = CreateSubnet::create(global, arguments).await?;
-
-        log::info!(
-            "created subnet actor with id: {}/{}",
-            arguments.parent,
-            address
-        );
+        let result = CreateSubnet::create(global, arguments).await?;
+        print_tx_result(global, result, |(address, outcome)| {
+            println!("{outcome}");
+            log::info!(
+                "created subnet actor with id: {}/{}",
+                arguments.parent,
+                address
+            );
+        })?;
 
         Ok(())
     }
@@ -0,0 +1,41 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Get the subnet info cli command
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_sdk::subnet_id::SubnetID;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+/// The command to get the status and configuration of a subnet.
+pub(crate) struct SubnetInfo;
+
+#[async_trait]
+impl CommandLineHandler for SubnetInfo {
+    type Arguments = SubnetInfoArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("get subnet info with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global)?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+
+        let info = provider.get_subnet_info(&subnet).await?;
+        println!("{}", info);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "info",
+    about = "Get the status, stake, validators and configuration of a subnet"
+)]
+pub(crate) struct SubnetInfoArgs {
+    #[arg(long, short, help = "The subnet id to query")]
+    pub subnet: String,
+}
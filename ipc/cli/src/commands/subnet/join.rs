@@ -4,12 +4,13 @@
 
 use async_trait::async_trait;
 use clap::Args;
+use ipc_provider::manager::TxResult;
 use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    f64_to_token_amount, get_ipc_provider, print_tx_result, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 /// The command to join a subnet
@@ -31,11 +32,17 @@ impl CommandLineHandler for JoinSubnet {
         let public_key = hex::decode(&arguments.public_key)?;
         if let Some(initial_balance) = arguments.initial_balance {
             log::info!("pre-funding address with {initial_balance}");
-            provider
+            let result = provider
                 .pre_fund(subnet.clone(), from, f64_to_token_amount(initial_balance)?)
                 .await?;
+            // only the pre-funding is simulated or written to be signed, the join is left
+            // for once it has been sent.
+            if !matches!(result, TxResult::Sent(_)) {
+                return print_tx_result(global, result, |_| {});
+            }
+            log::info!("address pre-funded successfully");
         }
-        let epoch = provider
+        let result = provider
             .join_subnet(
                 subnet,
                 from,
@@ -43,7 +50,10 @@ impl CommandLineHandler for JoinSubnet {
                 public_key,
             )
             .await?;
-        println!("joined at epoch: {epoch}");
+        print_tx_result(global, result, |outcome| {
+            println!("joined at epoch: {}", outcome.block_number);
+            println!("{outcome}");
+        })?;
 
         Ok(())
     }
@@ -87,9 +97,12 @@ impl CommandLineHandler for StakeSubnet {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
-        provider
+        let result = provider
             .stake(subnet, from, f64_to_token_amount(arguments.collateral)?)
-            .await
+            .await?;
+        print_tx_result(global, result, |outcome| println!("staked in {outcome}"))?;
+
+        Ok(())
     }
 }
 
@@ -124,9 +137,12 @@ impl CommandLineHandler for UnstakeSubnet {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
-        provider
+        let result = provider
             .unstake(subnet, from, f64_to_token_amount(arguments.collateral)?)
-            .await
+            .await?;
+        print_tx_result(global, result, |outcome| println!("unstaked in {outcome}"))?;
+
+        Ok(())
     }
 }
 
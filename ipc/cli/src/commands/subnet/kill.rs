@@ -7,7 +7,10 @@ use clap::Args;
 use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
-use crate::{get_ipc_provider, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
+use crate::{
+    get_ipc_provider, print_tx_result, require_fil_addr_from_str, CommandLineHandler,
+    GlobalArguments,
+};
 
 /// The command to kill an existing subnet.
 pub struct KillSubnet;
@@ -26,7 +29,12 @@ impl CommandLineHandler for KillSubnet {
             None => None,
         };
 
-        provider.kill_subnet(subnet, from).await
+        let result = provider.kill_subnet(subnet, from).await?;
+        print_tx_result(global, result, |outcome| {
+            println!("killed subnet in {outcome}")
+        })?;
+
+        Ok(())
     }
 }
 
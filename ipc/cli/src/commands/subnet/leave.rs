@@ -7,7 +7,10 @@ use clap::Args;
 use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
-use crate::{get_ipc_provider, require_fil_addr_from_str, CommandLineHandler, GlobalArguments};
+use crate::{
+    get_ipc_provider, print_tx_result, require_fil_addr_from_str, CommandLineHandler,
+    GlobalArguments,
+};
 
 /// The command to leave a new subnet.
 pub struct LeaveSubnet;
@@ -25,7 +28,12 @@ impl CommandLineHandler for LeaveSubnet {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
-        provider.leave_subnet(subnet, from).await
+        let result = provider.leave_subnet(subnet, from).await?;
+        print_tx_result(global, result, |outcome| {
+            println!("left subnet in {outcome}")
+        })?;
+
+        Ok(())
     }
 }
 
@@ -54,11 +62,14 @@ impl CommandLineHandler for Claim {
             Some(address) => Some(require_fil_addr_from_str(address)?),
             None => None,
         };
-        if !&arguments.rewards {
-            provider.claim_collateral(subnet, from).await
+        let result = if !&arguments.rewards {
+            provider.claim_collateral(subnet, from).await?
         } else {
-            provider.claim_relayer_reward(subnet, from).await
-        }
+            provider.claim_relayer_reward(subnet, from).await?
+        };
+        print_tx_result(global, result, |outcome| println!("claimed in {outcome}"))?;
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,48 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! List the validators of a subnet cli command
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_sdk::subnet_id::SubnetID;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+/// The command to list the active and waiting validators of a subnet.
+pub(crate) struct ListValidators;
+
+#[async_trait]
+impl CommandLineHandler for ListValidators {
+    type Arguments = ListValidatorsArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("list validators with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global)?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+
+        let mut validators = provider.list_validators(&subnet).await?;
+        if arguments.ascending {
+            validators.reverse();
+        }
+        for validator in validators {
+            println!("{}", validator);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "list-validators",
+    about = "List the active and waiting validators of a subnet, sorted by collateral"
+)]
+pub(crate) struct ListValidatorsArgs {
+    #[arg(long, short, help = "The subnet id to list the validators of")]
+    pub subnet: String,
+    #[arg(long, help = "Sort from the lowest collateral instead of the highest")]
+    pub ascending: bool,
+}
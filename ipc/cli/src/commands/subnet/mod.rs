@@ -3,10 +3,12 @@
 
 pub use crate::commands::subnet::create::{CreateSubnet, CreateSubnetArgs};
 use crate::commands::subnet::genesis_epoch::{GenesisEpoch, GenesisEpochArgs};
+use crate::commands::subnet::info::{SubnetInfo, SubnetInfoArgs};
 pub use crate::commands::subnet::join::{JoinSubnet, JoinSubnetArgs};
 pub use crate::commands::subnet::kill::{KillSubnet, KillSubnetArgs};
 pub use crate::commands::subnet::leave::{LeaveSubnet, LeaveSubnetArgs};
 use crate::commands::subnet::list_subnets::{ListSubnets, ListSubnetsArgs};
+use crate::commands::subnet::list_validators::{ListValidators, ListValidatorsArgs};
 use crate::commands::subnet::rpc::{RPCSubnet, RPCSubnetArgs};
 use crate::commands::subnet::send_value::{SendValue, SendValueArgs};
 use crate::commands::subnet::validator::{ValidatorInfo, ValidatorInfoArgs};
@@ -21,10 +23,12 @@ use self::rpc::{ChainIdSubnet, ChainIdSubnetArgs};
 pub mod bootstrap;
 pub mod create;
 mod genesis_epoch;
+mod info;
 pub mod join;
 pub mod kill;
 pub mod leave;
 pub mod list_subnets;
+mod list_validators;
 pub mod rpc;
 pub mod send_value;
 mod validator;
@@ -58,6 +62,8 @@ impl SubnetCommandsArgs {
             Commands::ListBootstraps(args) => ListBootstraps::handle(global, args).await,
             Commands::GenesisEpoch(args) => GenesisEpoch::handle(global, args).await,
             Commands::GetValidator(args) => ValidatorInfo::handle(global, args).await,
+            Commands::Info(args) => SubnetInfo::handle(global, args).await,
+            Commands::ListValidators(args) => ListValidators::handle(global, args).await,
         }
     }
 }
@@ -79,4 +85,6 @@ pub(crate) enum Commands {
     ListBootstraps(ListBootstrapsArgs),
     GenesisEpoch(GenesisEpochArgs),
     GetValidator(ValidatorInfoArgs),
+    Info(SubnetInfoArgs),
+    ListValidators(ListValidatorsArgs),
 }
@@ -22,7 +22,7 @@ impl CommandLineHandler for RPCSubnet {
 
         let provider = get_ipc_provider(global)?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
-        let conn = match provider.connection(&subnet) {
+        let conn = match provider.connection(&subnet)? {
             None => return Err(anyhow::anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -52,7 +52,7 @@ impl CommandLineHandler for ChainIdSubnet {
 
         let provider = get_ipc_provider(global)?;
         let subnet = SubnetID::from_str(&arguments.subnet)?;
-        let conn = match provider.connection(&subnet) {
+        let conn = match provider.connection(&subnet)? {
             None => return Err(anyhow::anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
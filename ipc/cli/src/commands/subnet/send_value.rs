@@ -8,8 +8,8 @@ use ipc_sdk::subnet_id::SubnetID;
 use std::{fmt::Debug, str::FromStr};
 
 use crate::{
-    f64_to_token_amount, get_ipc_provider, require_fil_addr_from_str, CommandLineHandler,
-    GlobalArguments,
+    f64_to_token_amount, get_ipc_provider, print_tx_result, require_fil_addr_from_str,
+    CommandLineHandler, GlobalArguments,
 };
 
 pub(crate) struct SendValue;
@@ -28,14 +28,19 @@ impl CommandLineHandler for SendValue {
             None => None,
         };
 
-        provider
+        let result = provider
             .send_value(
                 &subnet,
                 from,
                 require_fil_addr_from_str(&arguments.to)?,
                 f64_to_token_amount(arguments.amount)?,
             )
-            .await
+            .await?;
+        print_tx_result(global, result, |outcome| {
+            println!("value sent in {outcome}")
+        })?;
+
+        Ok(())
     }
 }
 
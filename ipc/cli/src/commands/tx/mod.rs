@@ -0,0 +1,30 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use crate::{CommandLineHandler, GlobalArguments};
+
+use clap::{Args, Subcommand};
+
+use self::send::{SendTx, SendTxArgs};
+
+mod send;
+
+#[derive(Debug, Args)]
+#[command(name = "tx", about = "transaction related commands")]
+#[command(args_conflicts_with_subcommands = true)]
+pub(crate) struct TxCommandsArgs {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+impl TxCommandsArgs {
+    pub async fn handle(&self, global: &GlobalArguments) -> anyhow::Result<()> {
+        match &self.command {
+            Commands::Send(args) => SendTx::handle(global, args).await,
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Commands {
+    Send(SendTxArgs),
+}
@@ -0,0 +1,59 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Send signed transaction cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_provider::manager::SignedTransaction;
+use ipc_sdk::subnet_id::SubnetID;
+use std::{fmt::Debug, str::FromStr};
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+/// The command to broadcast a transaction signed offline with `wallet sign-tx`.
+pub(crate) struct SendTx;
+
+#[async_trait]
+impl CommandLineHandler for SendTx {
+    type Arguments = SendTxArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("send signed transaction with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global)?;
+        let subnet = SubnetID::from_str(&arguments.subnet)?;
+
+        let signed: SignedTransaction =
+            serde_json::from_str(&std::fs::read_to_string(&arguments.input)?)?;
+        log::info!(
+            "sending {} from {:?} in tx {:?}",
+            signed.method,
+            signed.from,
+            signed.tx_hash
+        );
+
+        let outcome = provider.send_signed_transaction(&subnet, &signed).await?;
+        println!(
+            "{} performed in epoch: {}",
+            signed.method, outcome.block_number
+        );
+        println!("{outcome}");
+
+        // the address of the actor deployed by a `subnet create` transaction is only known
+        // from its `SubnetDeployed` event.
+        if let Ok(address) = outcome.deployed_subnet() {
+            log::info!("created subnet actor with id: {subnet}/{address}");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(about = "Broadcast a transaction signed with wallet sign-tx")]
+pub(crate) struct SendTxArgs {
+    #[arg(long, short, help = "The subnet the transaction is sent to")]
+    pub subnet: String,
+    #[arg(long, short, help = "The file with the signed transaction")]
+    pub input: String,
+}
@@ -12,6 +12,7 @@ use self::default::{
 use self::export::{WalletExport, WalletExportArgs, WalletPublicKey, WalletPublicKeyArgs};
 use self::import::{WalletImport, WalletImportArgs};
 use self::remove::{WalletRemove, WalletRemoveArgs};
+use self::sign_tx::{WalletSignTx, WalletSignTxArgs};
 
 mod balances;
 mod default;
@@ -19,6 +20,7 @@ mod export;
 mod import;
 mod new;
 mod remove;
+mod sign_tx;
 
 #[derive(Debug, Args)]
 #[command(name = "wallet", about = "wallet related commands")]
@@ -39,6 +41,7 @@ impl WalletCommandsArgs {
             Commands::SetDefault(args) => WalletSetDefault::handle(global, args).await,
             Commands::GetDefault(args) => WalletGetDefault::handle(global, args).await,
             Commands::PubKey(args) => WalletPublicKey::handle(global, args).await,
+            Commands::SignTx(args) => WalletSignTx::handle(global, args).await,
         }
     }
 }
@@ -53,4 +56,5 @@ pub(crate) enum Commands {
     SetDefault(WalletSetDefaultArgs),
     GetDefault(WalletGetDefaultArgs),
     PubKey(WalletPublicKeyArgs),
+    SignTx(WalletSignTxArgs),
 }
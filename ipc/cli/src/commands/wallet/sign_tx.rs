@@ -0,0 +1,50 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Wallet sign-tx cli handler
+
+use async_trait::async_trait;
+use clap::Args;
+use ipc_provider::manager::UnsignedTransaction;
+use std::fmt::Debug;
+
+use crate::{get_ipc_provider, CommandLineHandler, GlobalArguments};
+
+/// The command to sign a transaction built with `--unsigned-out`, e.g. in a machine with no
+/// network access holding the keys.
+pub(crate) struct WalletSignTx;
+
+#[async_trait]
+impl CommandLineHandler for WalletSignTx {
+    type Arguments = WalletSignTxArgs;
+
+    async fn handle(global: &GlobalArguments, arguments: &Self::Arguments) -> anyhow::Result<()> {
+        log::debug!("sign transaction with args: {:?}", arguments);
+
+        let provider = get_ipc_provider(global)?;
+
+        let unsigned: UnsignedTransaction =
+            serde_json::from_str(&std::fs::read_to_string(&arguments.input)?)?;
+        println!("{unsigned}");
+
+        let signed = provider.sign_transaction(&unsigned)?;
+        std::fs::write(&arguments.output, serde_json::to_string_pretty(&signed)?)?;
+        println!(
+            "signed transaction {:?} written to {}",
+            signed.tx_hash, arguments.output
+        );
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(
+    name = "sign-tx",
+    about = "Sign a transaction built with --unsigned-out with a key of the evm keystore"
+)]
+pub(crate) struct WalletSignTxArgs {
+    #[arg(long, short, help = "The file with the unsigned transaction")]
+    pub input: String,
+    #[arg(long, short, help = "The file to write the signed transaction to")]
+    pub output: String,
+}
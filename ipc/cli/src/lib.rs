@@ -36,6 +36,16 @@ pub struct GlobalArguments {
         help = "The toml config file path for IPC Agent, default to ${HOME}/.ipc-agent/config.toml"
     )]
     config_path: Option<String>,
+    #[arg(
+        long,
+        help = "Simulate state-changing operations and report the expected outcome instead of sending them"
+    )]
+    dry_run: bool,
+    #[arg(
+        long,
+        help = "Build state-changing operations to be signed offline and write them to this file instead of sending them"
+    )]
+    unsigned_out: Option<String>,
 }
 
 impl GlobalArguments {
@@ -45,6 +55,14 @@ impl GlobalArguments {
             .unwrap_or_else(ipc_provider::default_config_path)
     }
 
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn unsigned_out(&self) -> Option<&str> {
+        self.unsigned_out.as_deref()
+    }
+
     pub fn config(&self) -> Result<Config> {
         let config_path = self.config_path();
         Config::from_file(config_path)
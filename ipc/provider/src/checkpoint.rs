@@ -2,8 +2,8 @@
 // SPDX-License-Identifier: MIT
 //! Bottom up checkpoint manager
 
-use crate::config::Subnet;
-use crate::manager::{BottomUpCheckpointRelayer, EthSubnetManager};
+use crate::config::{SignerConfig, Subnet};
+use crate::manager::{BottomUpCheckpointRelayer, EthSubnetManager, TxResult};
 use anyhow::{anyhow, Result};
 use fvm_shared::address::Address;
 use fvm_shared::clock::ChainEpoch;
@@ -66,11 +66,14 @@ impl BottomUpCheckpointManager<EthSubnetManager> {
         parent: Subnet,
         child: Subnet,
         keystore: Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>,
+        signers: Vec<SignerConfig>,
     ) -> Result<Self> {
         let parent_handler =
-            EthSubnetManager::from_subnet_with_wallet_store(&parent, Some(keystore.clone()))?;
+            EthSubnetManager::from_subnet_with_wallet_store(&parent, Some(keystore.clone()))?
+                .with_signers(signers.clone());
         let child_handler =
-            EthSubnetManager::from_subnet_with_wallet_store(&child, Some(keystore))?;
+            EthSubnetManager::from_subnet_with_wallet_store(&child, Some(keystore))?
+                .with_signers(signers);
         Self::new(parent, child, parent_handler, child_handler).await
     }
 }
@@ -156,15 +159,16 @@ impl<T: BottomUpCheckpointRelayer + Send + Sync + 'static> BottomUpCheckpointMan
         let bundle = self.child_handler.checkpoint_bundle_at(height).await?;
         log::debug!("bottom up bundle: {bundle:?}");
 
-        let epoch = self
+        let outcome = self
             .parent_handler
             .submit_checkpoint(submitter, bundle)
             .await
+            .and_then(TxResult::sent)
             .map_err(|e| anyhow!("cannot submit bottom up checkpoint due to: {e:}"))?;
         log::info!(
             "submitted bottom up checkpoint({}) in parent at height {}",
             height,
-            epoch
+            outcome.block_number
         );
 
         Ok(())
@@ -201,16 +205,17 @@ impl<T: BottomUpCheckpointRelayer + Send + Sync + 'static> BottomUpCheckpointMan
                     .await?;
                 log::debug!("bottom up bundle: {bundle:?}");
 
-                let epoch = self
+                let outcome = self
                     .parent_handler
                     .submit_checkpoint(submitter, bundle)
                     .await
+                    .and_then(TxResult::sent)
                     .map_err(|e| anyhow!("cannot submit bottom up checkpoint due to: {e:}"))?;
 
                 log::info!(
                     "submitted bottom up checkpoint({}) in parent at height {}",
                     event.height,
-                    epoch
+                    outcome.block_number
                 );
             }
         }
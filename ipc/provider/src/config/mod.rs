@@ -7,6 +7,7 @@
 
 pub mod deserialize;
 mod reload;
+pub mod signer;
 pub mod subnet;
 
 pub mod serialize;
@@ -23,6 +24,7 @@ use ipc_sdk::subnet_id::SubnetID;
 pub use reload::ReloadableConfig;
 use serde::{Deserialize, Serialize};
 use serialize::serialize_subnets_to_str;
+pub use signer::SignerConfig;
 pub use subnet::Subnet;
 
 pub const JSON_RPC_VERSION: &str = "2.0";
@@ -58,8 +60,20 @@ registry_addr = "0x74539671a1d2f1c8f200826baba665179f53a1b7"
 # [subnets.config]
 # network_type = "fevm"
 # provider_http = "https://<RPC_ADDR>/"
+# fallback_providers_http = [
+#     "https://<FALLBACK_RPC_ADDR>/",
+#     { url = "https://<AUTHENTICATED_FALLBACK_RPC_ADDR>/", auth_token = "<TOKEN>" },
+# ]
+# provider_ws = "wss://<RPC_ADDR>/"
 # gateway_addr = "0x77aa40b105843728088c0132e43fc44348881da8"
 # registry_addr = "0x74539671a1d2f1c8f200826baba665179f53a1b7"
+
+# Remote signer template - uncomment to sign the transactions of an address with a
+# Web3Signer service instead of the keystore
+# [[signers]]
+# type = "web3signer"
+# address = "0x<ADDRESS>"
+# url = "http://<SIGNER_ADDR>/"
 "#;
 
 /// The top-level struct representing the config. Calls to [`Config::from_file`] deserialize into
@@ -71,6 +85,9 @@ pub struct Config {
     #[serde(deserialize_with = "deserialize_subnets_from_vec", default)]
     #[serde(serialize_with = "serialize_subnets_to_str")]
     pub subnets: HashMap<SubnetID, Subnet>,
+    /// The signers of the addresses whose transactions are not signed with the keystore.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signers: Vec<SignerConfig>,
 }
 
 impl Config {
@@ -79,12 +96,16 @@ impl Config {
         Config {
             keystore_path: None,
             subnets: Default::default(),
+            signers: vec![],
         }
     }
 
     /// Reads a TOML configuration in the `s` string and returns a [`Config`] struct.
     pub fn from_toml_str(s: &str) -> Result<Self> {
-        let config = toml::from_str(s)?;
+        let config: Config = toml::from_str(s)?;
+        for subnet in config.subnets.values() {
+            subnet.validate()?;
+        }
         Ok(config)
     }
 
@@ -121,6 +121,7 @@ mod tests {
         let mut config = Config {
             keystore_path: Some(String::from("~/.ipc")),
             subnets: Default::default(),
+            signers: vec![],
         };
 
         let eth_addr1 = EthAddress::from_str("0x6BE1Ccf648c74800380d0520D797a170c808b624").unwrap();
@@ -129,8 +130,8 @@ mod tests {
             config: SubnetConfig::Fevm(EVMSubnet {
                 gateway_addr: Address::from(eth_addr1),
                 provider_http: "http://127.0.0.1:3030/rpc/v1".parse().unwrap(),
-                auth_token: None,
                 registry_addr: Address::from(eth_addr1),
+                ..Default::default()
             }),
         };
         config.add_subnet(subnet2);
@@ -0,0 +1,42 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+use fvm_shared::address::Address;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config::deserialize::deserialize_eth_address_from_str;
+use crate::config::serialize::serialize_eth_address_to_str;
+
+/// Selects how the transactions of an address are signed. Addresses without a signer in
+/// the config are signed with their key in the keystore.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Signs with the private key of the address in the keystore.
+    Keystore {
+        #[serde(deserialize_with = "deserialize_eth_address_from_str")]
+        #[serde(serialize_with = "serialize_eth_address_to_str")]
+        address: Address,
+    },
+    /// Signs with a remote service implementing the `eth_signTransaction` method of the
+    /// Web3Signer JSON-RPC API, so that the private key is never held by the client.
+    #[serde(rename = "web3signer")]
+    Web3Signer {
+        #[serde(deserialize_with = "deserialize_eth_address_from_str")]
+        #[serde(serialize_with = "serialize_eth_address_to_str")]
+        address: Address,
+        url: Url,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        auth_token: Option<String>,
+    },
+}
+
+impl SignerConfig {
+    /// The address whose transactions are signed.
+    pub fn address(&self) -> &Address {
+        match self {
+            SignerConfig::Keystore { address } => address,
+            SignerConfig::Web3Signer { address, .. } => address,
+        }
+    }
+}
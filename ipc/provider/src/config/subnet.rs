@@ -1,5 +1,9 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
 use fvm_shared::address::Address;
 use ipc_sdk::subnet_id::SubnetID;
 use serde::{Deserialize, Serialize};
@@ -12,6 +16,18 @@ use crate::config::serialize::{
     serialize_address_to_str, serialize_eth_address_to_str, serialize_subnet_id_to_str,
 };
 
+/// Default polling interval used to check for pending transactions and events. Ethers
+/// defaults to 7 seconds, for our child subnets we can reduce it to the block time
+/// (or potentially less).
+pub const DEFAULT_POLLING_INTERVAL_SECS: u64 = 1;
+/// Default number of blocks a transaction needs to be included for to be considered final.
+pub const DEFAULT_REQUIRED_CONFIRMATIONS: usize = 1;
+/// Default maximum time to wait for a transaction to be confirmed. It accommodates fast
+/// subnets with slow roots (like Calibration and mainnet).
+pub const DEFAULT_MAX_WAIT_SECS: u64 = 200;
+/// Default timeout of a request to an RPC endpoint, after which the next endpoint is tried.
+pub const DEFAULT_RPC_TIMEOUT_SECS: u64 = 60;
+
 /// Represents a subnet declaration in the config.
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct Subnet {
@@ -24,6 +40,8 @@ pub struct Subnet {
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 #[serde(tag = "network_type")]
 pub enum SubnetConfig {
+    #[serde(rename = "fvm")]
+    Fvm(FVMSubnet),
     #[serde(rename = "fevm")]
     Fevm(EVMSubnet),
 }
@@ -31,36 +49,72 @@ pub enum SubnetConfig {
 /// A helper enum to differentiate the different network types
 #[derive(PartialEq, Eq)]
 pub enum NetworkType {
+    Fvm,
     Fevm,
 }
 
 impl Subnet {
     pub fn network_type(&self) -> NetworkType {
         match &self.config {
+            SubnetConfig::Fvm(_) => NetworkType::Fvm,
             SubnetConfig::Fevm(_) => NetworkType::Fevm,
         }
     }
 
     pub fn auth_token(&self) -> Option<String> {
         match &self.config {
+            SubnetConfig::Fvm(s) => s.auth_token.clone(),
             SubnetConfig::Fevm(s) => s.auth_token.clone(),
         }
     }
 
     pub fn rpc_http(&self) -> &Url {
         match &self.config {
+            SubnetConfig::Fvm(s) => &s.jsonrpc_api_http,
             SubnetConfig::Fevm(s) => &s.provider_http,
         }
     }
 
     pub fn gateway_addr(&self) -> Address {
         match &self.config {
+            SubnetConfig::Fvm(s) => s.gateway_addr,
             SubnetConfig::Fevm(s) => s.gateway_addr,
         }
     }
+
+    /// How the requests to the RPC endpoints of the subnet are retried and rate limited.
+    pub fn retry_config(&self) -> RetryConfig {
+        let retry = match &self.config {
+            SubnetConfig::Fvm(s) => &s.retry,
+            SubnetConfig::Fevm(s) => &s.retry,
+        };
+        retry.clone().unwrap_or_default()
+    }
+
+    /// Whether the subnet supports `op`. The native actors of fvm subnets support none of
+    /// these operations, see [`FVMSubnet`].
+    pub fn supports(&self, _op: SubnetOperation) -> bool {
+        self.network_type() == NetworkType::Fevm
+    }
+
+    /// Checks the parameters of the subnet that cannot be checked while deserializing it.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let SubnetConfig::Fevm(EVMSubnet { gas: Some(gas), .. }) = &self.config {
+            gas.validate()
+                .with_context(|| format!("invalid gas config of subnet {}", self.id))?;
+        }
+        Ok(())
+    }
 }
 
-/// The FVM subnet config parameters
+/// The FVM subnet config parameters.
+///
+/// The native IPC actors of fvm subnets only keep a validator set and checkpoint votes,
+/// so these subnets do not support pre-funding and pre-releasing, unstaking, claiming
+/// collateral or relayer rewards, bootstrap nodes, top-down message and validator change
+/// queries, tracking the status of `fund` and `release` transactions, nor relaying
+/// bottom-up checkpoints. The provider refuses to connect to an fvm subnet for any of
+/// these operations, see [`SubnetOperation`].
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct FVMSubnet {
     #[serde(deserialize_with = "deserialize_address_from_str")]
@@ -68,12 +122,68 @@ pub struct FVMSubnet {
     pub gateway_addr: Address,
     pub jsonrpc_api_http: Url,
     pub auth_token: Option<String>,
+    /// How requests to `jsonrpc_api_http` are retried and rate limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+}
+
+/// The operations that are not supported by every type of subnet, see [`Subnet::supports`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubnetOperation {
+    PreFund,
+    PreRelease,
+    Unstake,
+    ClaimCollateral,
+    ClaimRelayerReward,
+    AddBootstrap,
+    /// Querying the top-down messages committed for a child subnet at a given height.
+    TopDownMessages,
+    /// Querying the validator changes committed for a child subnet at a given height.
+    ValidatorChanges,
+    FundStatus,
+    ReleaseStatus,
+    /// Building, submitting and querying the bottom-up checkpoints of a child subnet.
+    CheckpointRelaying,
+}
+
+impl Display for SubnetOperation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            SubnetOperation::PreFund => "pre-funding",
+            SubnetOperation::PreRelease => "pre-releasing",
+            SubnetOperation::Unstake => "unstaking",
+            SubnetOperation::ClaimCollateral => "claiming collateral",
+            SubnetOperation::ClaimRelayerReward => "claiming relayer rewards",
+            SubnetOperation::AddBootstrap => "adding bootstrap nodes",
+            SubnetOperation::TopDownMessages => "querying top-down messages",
+            SubnetOperation::ValidatorChanges => "querying validator changes",
+            SubnetOperation::FundStatus => "tracking fund transactions",
+            SubnetOperation::ReleaseStatus => "tracking release transactions",
+            SubnetOperation::CheckpointRelaying => "relaying bottom-up checkpoints",
+        };
+        f.write_str(op)
+    }
+}
+
+/// An operation requested from an fvm subnet, which does not support it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{op} is not supported by fvm subnets")]
+pub struct UnsupportedOperationError {
+    pub op: SubnetOperation,
 }
 
 /// The EVM subnet config parameters
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
 pub struct EVMSubnet {
     pub provider_http: Url,
+    /// Endpoints to fail over to when `provider_http` is not available, in order of
+    /// preference. The `auth_token` is only sent to `provider_http`, fallback endpoints
+    /// that need one set their own.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_providers_http: Vec<FallbackProvider>,
+    /// The websocket endpoint used to subscribe to the events of the subnet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_ws: Option<Url>,
     pub auth_token: Option<String>,
     #[serde(deserialize_with = "deserialize_eth_address_from_str")]
     #[serde(serialize_with = "serialize_eth_address_to_str")]
@@ -81,4 +191,200 @@ pub struct EVMSubnet {
     #[serde(deserialize_with = "deserialize_eth_address_from_str")]
     #[serde(serialize_with = "serialize_eth_address_to_str")]
     pub gateway_addr: Address,
+    /// The interval in seconds to poll for pending transactions and events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polling_interval_secs: Option<u64>,
+    /// The number of confirmations to wait for before considering a transaction final.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_confirmations: Option<usize>,
+    /// The maximum time in seconds to wait for a transaction to be confirmed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wait_secs: Option<u64>,
+    /// How the fees of the transactions sent to the subnet are priced.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas: Option<GasConfig>,
+    /// Forces the type of the transactions sent to the subnet, detected from the chain
+    /// by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_type: Option<TxType>,
+    /// The timeout in seconds of a request to an RPC endpoint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_timeout_secs: Option<u64>,
+    /// The number of endpoints that need to return the same result for a read request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpc_read_quorum: Option<usize>,
+    /// How requests to the RPC endpoints are retried and rate limited.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryConfig>,
+}
+
+/// A subnet served by a local node, with every optional parameter left to its default. The
+/// contract addresses are expected to be set by the caller.
+impl Default for EVMSubnet {
+    fn default() -> Self {
+        Self {
+            provider_http: Url::parse("http://127.0.0.1:8545").unwrap(),
+            fallback_providers_http: vec![],
+            provider_ws: None,
+            auth_token: None,
+            registry_addr: Address::new_id(0),
+            gateway_addr: Address::new_id(0),
+            polling_interval_secs: None,
+            required_confirmations: None,
+            max_wait_secs: None,
+            gas: None,
+            tx_type: None,
+            rpc_timeout_secs: None,
+            rpc_read_quorum: None,
+            retry: None,
+        }
+    }
+}
+
+impl EVMSubnet {
+    pub fn polling_interval(&self) -> Duration {
+        Duration::from_secs(
+            self.polling_interval_secs
+                .unwrap_or(DEFAULT_POLLING_INTERVAL_SECS),
+        )
+    }
+
+    pub fn required_confirmations(&self) -> usize {
+        self.required_confirmations
+            .unwrap_or(DEFAULT_REQUIRED_CONFIRMATIONS)
+    }
+
+    pub fn max_wait(&self) -> Duration {
+        Duration::from_secs(self.max_wait_secs.unwrap_or(DEFAULT_MAX_WAIT_SECS))
+    }
+
+    pub fn tx_type(&self) -> TxType {
+        self.tx_type.unwrap_or_default()
+    }
+
+    pub fn rpc_timeout(&self) -> Duration {
+        Duration::from_secs(self.rpc_timeout_secs.unwrap_or(DEFAULT_RPC_TIMEOUT_SECS))
+    }
+
+    pub fn rpc_read_quorum(&self) -> usize {
+        self.rpc_read_quorum.unwrap_or(1)
+    }
+}
+
+/// A fallback RPC endpoint of an EVM subnet, either a url or a table with the url and the
+/// auth token of the endpoint.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FallbackProvider {
+    Url(Url),
+    WithAuth { url: Url, auth_token: String },
+}
+
+impl FallbackProvider {
+    pub fn url(&self) -> &Url {
+        match self {
+            FallbackProvider::Url(url) => url,
+            FallbackProvider::WithAuth { url, .. } => url,
+        }
+    }
+
+    pub fn auth_token(&self) -> Option<&str> {
+        match self {
+            FallbackProvider::Url(_) => None,
+            FallbackProvider::WithAuth { auth_token, .. } => Some(auth_token),
+        }
+    }
+}
+
+/// The type of the transactions sent to an EVM subnet.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TxType {
+    /// Sends EIP-1559 transactions if the chain supports them, legacy ones otherwise.
+    #[default]
+    Auto,
+    Eip1559,
+    /// Pre EIP-1559 transactions, paying a `gasPrice`.
+    Legacy,
+}
+
+/// The gas pricing config of an EVM subnet. All fees are in attoFIL per unit of gas.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct GasConfig {
+    #[serde(default)]
+    pub strategy: GasStrategyConfig,
+    /// Caps the max fee per gas computed by the strategy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas_cap: Option<u64>,
+    /// Refuses to send transactions if the max fee per gas computed by the strategy
+    /// (after applying the cap) is above this value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee_per_gas_ceiling: Option<u64>,
+}
+
+impl GasConfig {
+    /// Checks that the strategy can query the fee history of the chain.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let GasStrategyConfig::FeeHistory { percentile, blocks } = self.strategy {
+            if percentile > 100 {
+                return Err(anyhow!("fee history percentile {percentile} is above 100"));
+            }
+            if blocks == 0 {
+                return Err(anyhow!("fee history needs at least one block"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The strategy used to compute the fees of the transactions.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GasStrategyConfig {
+    /// Estimates the fees from the base fee and the rewards of the past blocks.
+    #[default]
+    Estimator,
+    /// Always uses the same fees.
+    Fixed {
+        max_priority_fee_per_gas: u64,
+        max_fee_per_gas: u64,
+    },
+    /// Uses the median of the given reward percentile over the last `blocks` blocks as
+    /// the premium.
+    FeeHistory { percentile: u8, blocks: u64 },
+    /// Sets the max fee to `multiplier_percent`% of the base fee plus a fixed premium.
+    BaseFeeMultiplier {
+        multiplier_percent: u64,
+        max_priority_fee_per_gas: u64,
+    },
+}
+
+/// The retry and rate limiting config of the RPC requests to a subnet.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// The number of times a request failing with a transient error is retried.
+    pub max_retries: u32,
+    /// The maximum backoff before the first retry, doubled on every retry.
+    pub initial_backoff_ms: u64,
+    /// The maximum backoff between retries.
+    pub max_backoff_ms: u64,
+    /// The maximum number of requests per second, unlimited if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_sec: Option<u32>,
+    /// The maximum number of requests sent at once, defaults to `requests_per_sec`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 10_000,
+            requests_per_sec: None,
+            burst: None,
+        }
+    }
 }
@@ -3,6 +3,7 @@
 use std::io::Write;
 use std::str::FromStr;
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
 
 use fvm_shared::address::Address;
 use indoc::formatdoc;
@@ -11,14 +12,23 @@ use primitives::EthAddress;
 use tempfile::NamedTempFile;
 use url::Url;
 
-use crate::config::{Config, ReloadableConfig};
+use crate::config::subnet::{
+    FallbackProvider, GasConfig, GasStrategyConfig, NetworkType, RetryConfig, SubnetConfig,
+    SubnetOperation, TxType,
+};
+use crate::config::{Config, ReloadableConfig, SignerConfig};
 
 // Arguments for the config's fields
 const REPO_PATH: &str = "~/.ipc";
 const CHILD_ID: &str = "/r123/f0100";
 const CHILD_AUTH_TOKEN: &str = "CHILD_AUTH_TOKEN";
 const PROVIDER_HTTP: &str = "http://127.0.0.1:3030/rpc/v1";
+const FALLBACK_PROVIDER_HTTP: &str = "http://127.0.0.1:3031/rpc/v1";
+const PROVIDER_WS: &str = "ws://127.0.0.1:3030/rpc/v1";
 const ETH_ADDRESS: &str = "0x6be1ccf648c74800380d0520d797a170c808b624";
+const SIGNER_URL: &str = "http://127.0.0.1:9000/";
+const FVM_CHILD_ID: &str = "/r123/f0101";
+const FVM_GATEWAY_ADDR: &str = "f064";
 
 #[tokio::test]
 async fn reload_works() {
@@ -100,6 +110,195 @@ fn check_subnets_config() {
     assert_eq!(child.auth_token().as_ref().unwrap(), CHILD_AUTH_TOKEN);
 }
 
+#[test]
+fn check_evm_subnet_tx_config() {
+    let config = read_config().subnets;
+
+    let child = &config[&SubnetID::from_str(CHILD_ID).unwrap()];
+    let SubnetConfig::Fevm(evm) = &child.config else {
+        panic!("expected fevm subnet");
+    };
+    assert_eq!(evm.polling_interval(), Duration::from_secs(1));
+    assert_eq!(evm.required_confirmations(), 2);
+    assert_eq!(evm.max_wait(), Duration::from_secs(60));
+}
+
+#[test]
+fn check_evm_subnet_optional_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [[subnets]]
+            id = "{CHILD_ID}"
+
+            [subnets.config]
+            network_type = "fevm"
+            provider_http = "{PROVIDER_HTTP}"
+            registry_addr = "{ETH_ADDRESS}"
+            gateway_addr = "{ETH_ADDRESS}"
+            tx_type = "legacy"
+            fallback_providers_http = [
+                "{FALLBACK_PROVIDER_HTTP}",
+                {{ url = "{FALLBACK_PROVIDER_HTTP}", auth_token = "{CHILD_AUTH_TOKEN}" }},
+            ]
+            provider_ws = "{PROVIDER_WS}"
+            rpc_read_quorum = 2
+
+            [subnets.config.gas]
+            max_fee_per_gas_ceiling = 1000000
+
+            [subnets.config.gas.strategy]
+            type = "base_fee_multiplier"
+            multiplier_percent = 150
+            max_priority_fee_per_gas = 100
+
+            [subnets.config.retry]
+            max_retries = 5
+            requests_per_sec = 10
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let child = &config.subnets[&SubnetID::from_str(CHILD_ID).unwrap()];
+    let SubnetConfig::Fevm(evm) = &child.config else {
+        panic!("expected fevm subnet");
+    };
+    assert_eq!(
+        evm.gas,
+        Some(GasConfig {
+            strategy: GasStrategyConfig::BaseFeeMultiplier {
+                multiplier_percent: 150,
+                max_priority_fee_per_gas: 100,
+            },
+            max_fee_per_gas_cap: None,
+            max_fee_per_gas_ceiling: Some(1_000_000),
+        })
+    );
+    assert_eq!(evm.tx_type(), TxType::Legacy);
+    assert_eq!(
+        evm.fallback_providers_http,
+        vec![
+            FallbackProvider::Url(Url::from_str(FALLBACK_PROVIDER_HTTP).unwrap()),
+            FallbackProvider::WithAuth {
+                url: Url::from_str(FALLBACK_PROVIDER_HTTP).unwrap(),
+                auth_token: CHILD_AUTH_TOKEN.to_string(),
+            },
+        ]
+    );
+    assert_eq!(evm.provider_ws, Some(Url::from_str(PROVIDER_WS).unwrap()));
+    assert_eq!(evm.rpc_read_quorum(), 2);
+    assert_eq!(
+        child.retry_config(),
+        RetryConfig {
+            max_retries: 5,
+            requests_per_sec: Some(10),
+            ..Default::default()
+        }
+    );
+
+    // subnets without gas config use the default estimator
+    let config = read_config().subnets;
+    let child = &config[&SubnetID::from_str(CHILD_ID).unwrap()];
+    let SubnetConfig::Fevm(evm) = &child.config else {
+        panic!("expected fevm subnet");
+    };
+    assert!(evm.gas.is_none());
+    assert_eq!(evm.tx_type(), TxType::Auto);
+    assert!(evm.fallback_providers_http.is_empty());
+    assert!(evm.provider_ws.is_none());
+    assert_eq!(evm.rpc_read_quorum(), 1);
+    assert_eq!(child.retry_config(), RetryConfig::default());
+}
+
+#[test]
+fn check_invalid_gas_config() {
+    let config_with_strategy = |percentile: u8, blocks: u64| {
+        formatdoc!(
+            r#"
+            [[subnets]]
+            id = "{CHILD_ID}"
+
+            [subnets.config]
+            network_type = "fevm"
+            provider_http = "{PROVIDER_HTTP}"
+            registry_addr = "{ETH_ADDRESS}"
+            gateway_addr = "{ETH_ADDRESS}"
+
+            [subnets.config.gas.strategy]
+            type = "fee_history"
+            percentile = {percentile}
+            blocks = {blocks}
+            "#
+        )
+    };
+
+    assert!(Config::from_toml_str(&config_with_strategy(50, 10)).is_ok());
+    assert!(Config::from_toml_str(&config_with_strategy(101, 10)).is_err());
+    assert!(Config::from_toml_str(&config_with_strategy(50, 0)).is_err());
+}
+
+#[test]
+fn check_signers_config() {
+    let config = Config::from_toml_str(
+        formatdoc!(
+            r#"
+            [[signers]]
+            type = "web3signer"
+            address = "{ETH_ADDRESS}"
+            url = "{SIGNER_URL}"
+            auth_token = "{CHILD_AUTH_TOKEN}"
+
+            [[signers]]
+            type = "keystore"
+            address = "{ETH_ADDRESS}"
+            "#
+        )
+        .as_str(),
+    )
+    .unwrap();
+
+    let address = Address::from(EthAddress::from_str(ETH_ADDRESS).unwrap());
+    assert_eq!(
+        config.signers,
+        vec![
+            SignerConfig::Web3Signer {
+                address,
+                url: Url::from_str(SIGNER_URL).unwrap(),
+                auth_token: Some(CHILD_AUTH_TOKEN.to_string()),
+            },
+            SignerConfig::Keystore { address },
+        ]
+    );
+    assert_eq!(config.signers[0].address(), &address);
+
+    let r = toml::to_string(&config).unwrap();
+    assert_eq!(Config::from_toml_str(&r).unwrap(), config);
+
+    // addresses are signed with the keystore by default
+    assert!(read_config().signers.is_empty());
+}
+
+#[test]
+fn check_fvm_subnet_config() {
+    let config = read_config().subnets;
+
+    let child_id = SubnetID::from_str(FVM_CHILD_ID).unwrap();
+    let child = &config[&child_id];
+    assert!(child.network_type() == NetworkType::Fvm);
+    assert_eq!(
+        child.gateway_addr(),
+        Address::from_str(FVM_GATEWAY_ADDR).unwrap()
+    );
+    assert_eq!(*child.rpc_http(), Url::from_str(PROVIDER_HTTP).unwrap(),);
+    assert!(child.auth_token().is_none());
+    assert!(!child.supports(SubnetOperation::PreFund));
+
+    let evm_child = &config[&SubnetID::from_str(CHILD_ID).unwrap()];
+    assert!(evm_child.supports(SubnetOperation::PreFund));
+}
+
 fn config_str() -> String {
     formatdoc!(
         r#"
@@ -114,6 +313,16 @@ fn config_str() -> String {
         provider_http = "{PROVIDER_HTTP}"
         registry_addr = "{ETH_ADDRESS}"
         gateway_addr = "{ETH_ADDRESS}"
+        required_confirmations = 2
+        max_wait_secs = 60
+
+        [[subnets]]
+        id = "{FVM_CHILD_ID}"
+
+        [subnets.config]
+        network_type = "fvm"
+        jsonrpc_api_http = "{PROVIDER_HTTP}"
+        gateway_addr = "{FVM_GATEWAY_ADDR}"
         "#
     )
 }
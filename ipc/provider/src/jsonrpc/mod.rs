@@ -1,32 +1,32 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 use anyhow::{anyhow, Result};
-use async_channel::{Receiver, Sender};
 use async_trait::async_trait;
-use futures_util::{SinkExt, StreamExt};
-use reqwest::header::HeaderValue;
 use reqwest::Client;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use serde_json::json;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
-use tokio::net::TcpStream;
-use tokio::spawn;
-use tokio_tungstenite::tungstenite::client::IntoClientRequest;
-use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::MaybeTlsStream;
-use tokio_tungstenite::{connect_async, WebSocketStream};
 use url::Url;
 
+use crate::retry::{is_retryable_status, RetryPolicy, TransientError};
+
+mod subscription;
 #[cfg(test)]
 mod tests;
 
+pub use subscription::Subscription;
+use subscription::SubscriptionRequest;
+
 const DEFAULT_JSON_RPC_VERSION: &str = "2.0";
-const DEFAULT_JSON_RPC_ID: u8 = 1;
 /// Request timeout of the RPC client. It should be enough to accommodate
 /// the time required to validate transaction on-chain.
 const DEFAULT_REQ_TIMEOUT: Duration = Duration::from_secs(250);
+/// Interval of the pings sent to keep websocket subscriptions alive.
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
 
 /// A convenience constant that represents empty params in a JSON-RPC request.
 pub const NO_PARAMS: Value = json!([]);
@@ -39,10 +39,18 @@ pub trait JsonRpcClient {
     /// Sends a JSON-RPC request with `method` and `params` via HTTP/HTTPS.
     async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T>;
 
-    /// Subscribes to notifications via a Websocket. This returns a [`Receiver`]
-    /// channel that is used to receive the messages sent by the server.
-    /// TODO: https://github.com/consensus-shipyard/ipc-agent/issues/7.
-    async fn subscribe(&self, method: &str) -> Result<Receiver<Value>>;
+    /// Sends the `requests`, pairs of method and params, in a single JSON-RPC batch via
+    /// HTTP/HTTPS. Returns the result of every request in the same order as `requests`,
+    /// failing only if the batch as a whole fails.
+    async fn batch_request<T: DeserializeOwned>(
+        &self,
+        requests: Vec<(&str, Value)>,
+    ) -> Result<Vec<Result<T>>>;
+
+    /// Subscribes to notifications via a Websocket, sending a JSON-RPC request with `method`
+    /// and `params`. This returns a [`Subscription`], a stream of the messages pushed by the
+    /// server that re-subscribes if the connection drops.
+    async fn subscribe(&self, method: &str, params: Value) -> Result<Subscription>;
 }
 
 /// The implementation of [`JsonRpcClient`].
@@ -50,6 +58,11 @@ pub struct JsonRpcClientImpl {
     http_client: Client,
     url: Url,
     bearer_token: Option<String>,
+    retry_policy: RetryPolicy,
+    /// The id of the next request, so that responses can be matched to their requests.
+    next_id: AtomicU64,
+    /// The interval of the pings keeping websocket subscriptions alive.
+    heartbeat: Duration,
 }
 
 impl JsonRpcClientImpl {
@@ -59,64 +72,143 @@ impl JsonRpcClientImpl {
             http_client: Client::default(),
             url,
             bearer_token: bearer_token.map(String::from),
+            retry_policy: RetryPolicy::default(),
+            next_id: AtomicU64::new(1),
+            heartbeat: DEFAULT_HEARTBEAT_INTERVAL,
         }
     }
-}
 
-#[async_trait]
-impl JsonRpcClient for JsonRpcClientImpl {
-    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
-        let request_body = build_jsonrpc_request(method, params)?;
-        let mut builder = self.http_client.post(self.url.as_str()).json(&request_body);
-        builder = builder.timeout(DEFAULT_REQ_TIMEOUT);
+    /// Sets how requests failing with transient errors are retried and rate limited.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
 
-        // Add the authorization bearer token if present
-        if self.bearer_token.is_some() {
-            builder = builder.bearer_auth(self.bearer_token.as_ref().unwrap());
-        }
+    /// Sets the interval of the pings sent to keep websocket subscriptions alive. A
+    /// subscription that receives no message in two intervals is re-established.
+    pub fn with_heartbeat(mut self, heartbeat: Duration) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
 
-        let response = builder.send().await?;
+    fn next_id(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed)
+    }
 
-        let response_body = response.text().await?;
-        log::debug!("received raw response body: {:?}", response_body);
+    async fn send_request<T: DeserializeOwned>(&self, method: &str, params: &Value) -> Result<T> {
+        let id = self.next_id();
+        let request_body = build_jsonrpc_request(id, method, params.clone())?;
+        let value = self.post::<JsonRpcResponse<T>>(&request_body).await?;
 
-        let value =
-            serde_json::from_str::<JsonRpcResponse<T>>(response_body.as_ref()).map_err(|e| {
-                log::error!("cannot parse json rpc client response: {:?}", response_body);
-                anyhow!(
-                    "cannot parse json rpc response: {:} due to {:}",
-                    response_body,
-                    e.to_string()
-                )
-            })?;
-
-        if value.id != DEFAULT_JSON_RPC_ID || value.jsonrpc != DEFAULT_JSON_RPC_VERSION {
+        if value.id != Some(id) || value.jsonrpc != DEFAULT_JSON_RPC_VERSION {
             return Err(anyhow!("json_rpc id or version not matching."));
         }
 
         Result::from(value)
     }
 
-    async fn subscribe(&self, method: &str) -> Result<Receiver<Value>> {
-        let mut request = self.url.as_str().into_client_request()?;
+    async fn send_batch<T: DeserializeOwned>(
+        &self,
+        requests: &[(&str, Value)],
+    ) -> Result<Vec<Result<T>>> {
+        let mut ids = Vec::with_capacity(requests.len());
+        let mut batch = Vec::with_capacity(requests.len());
+        for (method, params) in requests {
+            let id = self.next_id();
+            batch.push(build_jsonrpc_request(id, method, params.clone())?);
+            ids.push(id);
+        }
+
+        // servers that do not support batches, or reject the batch as a whole, reply with
+        // a single error response.
+        let responses = match self.post::<BatchResponse>(&Value::Array(batch)).await? {
+            BatchResponse::Batch(responses) => responses,
+            BatchResponse::Single(response) => {
+                return Err(anyhow!(
+                    "json_rpc batch rejected: {:}",
+                    response.error.unwrap_or_default()
+                ))
+            }
+        };
+
+        let mut responses = responses
+            .into_iter()
+            .filter(|r| r.jsonrpc == DEFAULT_JSON_RPC_VERSION)
+            .filter_map(|r| Some((r.id?, r)))
+            .collect::<HashMap<_, _>>();
+
+        Ok(ids
+            .into_iter()
+            .map(|id| {
+                let response = responses
+                    .remove(&id)
+                    .ok_or_else(|| anyhow!("no json_rpc response for request {id}"))?;
+                let value = Result::<Value>::from(response)?;
+                Ok(serde_json::from_value(value)?)
+            })
+            .collect())
+    }
+
+    /// Posts `body` and parses the response as `R`.
+    async fn post<R: DeserializeOwned>(&self, body: &Value) -> Result<R> {
+        let mut builder = self.http_client.post(self.url.as_str()).json(body);
+        builder = builder.timeout(DEFAULT_REQ_TIMEOUT);
 
         // Add the authorization bearer token if present
         if self.bearer_token.is_some() {
-            let token_string = format!("Bearer {}", self.bearer_token.as_ref().unwrap());
-            let header_value = HeaderValue::from_str(token_string.as_str())?;
-            request.headers_mut().insert("Authorization", header_value);
+            builder = builder.bearer_auth(self.bearer_token.as_ref().unwrap());
         }
 
-        let (mut ws_stream, _) = connect_async(request).await?;
-        let request_body = build_jsonrpc_request(method, NO_PARAMS)?;
-        ws_stream
-            .send(Message::text(request_body.to_string()))
-            .await?;
+        let response = builder.send().await?;
+        let status = response.status();
+
+        let response_body = response.text().await?;
+        log::debug!("received raw response body: {:?}", response_body);
+
+        serde_json::from_str::<R>(response_body.as_ref()).map_err(|e| {
+            if is_retryable_status(status) {
+                return anyhow::Error::new(TransientError(format!("{status}: {response_body}")));
+            }
+            log::error!("cannot parse json rpc client response: {:?}", response_body);
+            anyhow!(
+                "cannot parse json rpc response: {:} due to {:}",
+                response_body,
+                e.to_string()
+            )
+        })
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for JsonRpcClientImpl {
+    async fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        self.retry_policy
+            .run(method, || self.send_request(method, &params))
+            .await
+    }
 
-        let (send_chan, recv_chan) = async_channel::unbounded::<Value>();
-        spawn(handle_stream(ws_stream, send_chan));
+    async fn batch_request<T: DeserializeOwned>(
+        &self,
+        requests: Vec<(&str, Value)>,
+    ) -> Result<Vec<Result<T>>> {
+        if requests.is_empty() {
+            return Ok(vec![]);
+        }
+        self.retry_policy
+            .run("batch", || self.send_batch(&requests))
+            .await
+    }
 
-        Ok(recv_chan)
+    async fn subscribe(&self, method: &str, params: Value) -> Result<Subscription> {
+        SubscriptionRequest {
+            url: self.url.clone(),
+            bearer_token: self.bearer_token.clone(),
+            request: build_jsonrpc_request(self.next_id(), method, params)?,
+            retry_policy: self.retry_policy.clone(),
+            heartbeat: self.heartbeat,
+        }
+        .subscribe()
+        .await
     }
 }
 
@@ -125,7 +217,8 @@ impl JsonRpcClient for JsonRpcClientImpl {
 /// them into Result.
 #[derive(Debug, Deserialize)]
 struct JsonRpcResponse<T> {
-    id: u8,
+    /// The id of the request, null if the request could not be parsed.
+    id: Option<u64>,
     jsonrpc: String,
 
     result: Option<T>,
@@ -148,37 +241,16 @@ impl<T: DeserializeOwned> From<JsonRpcResponse<T>> for Result<T> {
     }
 }
 
-// Processes a websocket stream by reading messages from the stream `ws_stream` and sending
-// them to an output channel `chan`.
-async fn handle_stream(
-    mut ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
-    chan: Sender<Value>,
-) {
-    loop {
-        match ws_stream.next().await {
-            None => {
-                log::trace!("No message in websocket stream. The stream was closed.");
-                break;
-            }
-            Some(result) => match result {
-                Ok(msg) => {
-                    println!("{}", msg);
-                    log::trace!("Read message from websocket stream: {}", msg);
-                    let value = serde_json::from_str(msg.to_text().unwrap()).unwrap();
-                    chan.send(value).await.unwrap();
-                }
-                Err(err) => {
-                    log::error!("Error reading message from websocket stream: {:?}", err);
-                    break;
-                }
-            },
-        };
-    }
-    chan.close();
+/// The response to a batch request, either one response per request or a single error.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum BatchResponse {
+    Batch(Vec<JsonRpcResponse<Value>>),
+    Single(JsonRpcResponse<Value>),
 }
 
 // A convenience function to build a JSON-RPC request.
-fn build_jsonrpc_request(method: &str, params: Value) -> Result<Value> {
+fn build_jsonrpc_request(id: u64, method: &str, params: Value) -> Result<Value> {
     let has_params = if params.is_array() {
         let array_params = params.as_array().unwrap();
         !array_params.is_empty()
@@ -194,14 +266,14 @@ fn build_jsonrpc_request(method: &str, params: Value) -> Result<Value> {
     let request_value = if has_params {
         json!({
             "jsonrpc": DEFAULT_JSON_RPC_VERSION,
-            "id": DEFAULT_JSON_RPC_ID,
+            "id": id,
             "method": method,
             "params": params,
         })
     } else {
         json!({
             "jsonrpc": DEFAULT_JSON_RPC_VERSION,
-            "id": DEFAULT_JSON_RPC_ID,
+            "id": id,
             "method": method,
         })
     };
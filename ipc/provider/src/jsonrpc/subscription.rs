@@ -0,0 +1,231 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Websocket subscriptions of the [`JsonRpcClientImpl`](super::JsonRpcClientImpl), kept alive
+//! with pings and re-established when the connection drops.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use async_channel::{Receiver, Sender};
+use futures_util::{SinkExt, Stream, StreamExt};
+use reqwest::header::HeaderValue;
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Request;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use url::Url;
+
+use crate::retry::RetryPolicy;
+
+/// A subscription to the notifications pushed by the server over a websocket.
+///
+/// The subscription is a [`Stream`] of the notifications. The connection is kept alive with
+/// pings and, if it drops, it is re-established and the subscription request sent again,
+/// waiting between attempts as in the retry policy of the client. Notifications pushed
+/// while disconnected are lost.
+///
+/// Dropping the subscription, or calling [`Subscription::unsubscribe`], closes the
+/// connection, which cancels the subscription in the server.
+#[derive(Debug)]
+pub struct Subscription {
+    notifications: Receiver<Value>,
+    cancel: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl Subscription {
+    /// Closes the subscription and waits for its connection to be closed.
+    pub async fn unsubscribe(self) {
+        // the task may have stopped already if the stream was closed.
+        let _ = self.cancel.send(());
+        if let Err(e) = self.task.await {
+            log::error!("websocket subscription task failed: {e}");
+        }
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Value;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().notifications.poll_next_unpin(cx)
+    }
+}
+
+/// The parameters of a websocket subscription, used to re-subscribe after reconnecting.
+pub(crate) struct SubscriptionRequest {
+    pub url: Url,
+    pub bearer_token: Option<String>,
+    /// The JSON-RPC request sent after connecting.
+    pub request: Value,
+    pub retry_policy: RetryPolicy,
+    pub heartbeat: Duration,
+}
+
+/// How a connection of a subscription ended.
+enum Closed {
+    /// The subscription was closed by the client.
+    Unsubscribed,
+    /// The connection was closed by the server.
+    Disconnected,
+}
+
+impl SubscriptionRequest {
+    /// Connects and sends the subscription request, returning once the first connection is
+    /// established so that invalid urls or credentials are reported to the caller.
+    pub async fn subscribe(self) -> Result<Subscription> {
+        let ws_stream = self.connect().await?;
+
+        let (send_chan, recv_chan) = async_channel::unbounded::<Value>();
+        let (cancel, cancelled) = oneshot::channel();
+        let task = tokio::spawn(self.run(ws_stream, send_chan, cancelled));
+
+        Ok(Subscription {
+            notifications: recv_chan,
+            cancel,
+            task,
+        })
+    }
+
+    fn ws_request(&self) -> Result<Request> {
+        let mut request = self.url.as_str().into_client_request()?;
+
+        // Add the authorization bearer token if present
+        if let Some(token) = &self.bearer_token {
+            let header_value = HeaderValue::from_str(&format!("Bearer {token}"))?;
+            request.headers_mut().insert("Authorization", header_value);
+        }
+        Ok(request)
+    }
+
+    async fn connect(&self) -> Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+        let (mut ws_stream, _) = connect_async(self.ws_request()?).await?;
+        ws_stream
+            .send(Message::text(self.request.to_string()))
+            .await?;
+        Ok(ws_stream)
+    }
+
+    /// Forwards the notifications, reconnecting until the subscription is closed.
+    async fn run(
+        self,
+        ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        chan: Sender<Value>,
+        mut cancelled: oneshot::Receiver<()>,
+    ) {
+        let mut ws_stream = Some(ws_stream);
+        let mut attempt = 0;
+        loop {
+            let result = match ws_stream.take() {
+                Some(ws_stream) => Ok(ws_stream),
+                None => self.connect().await,
+            };
+            let result = match result {
+                Ok(ws_stream) => {
+                    self.handle_stream(ws_stream, &chan, &mut cancelled, &mut attempt)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(Closed::Unsubscribed) => break,
+                Ok(Closed::Disconnected) => log::warn!("websocket {} closed", self.url),
+                Err(e) => log::warn!("websocket subscription to {} failed: {e:#}", self.url),
+            }
+
+            attempt += 1;
+            let backoff = self.retry_policy.max_backoff(attempt);
+            log::info!(
+                "re-subscribing to {} in {}ms (attempt {attempt})",
+                self.url,
+                backoff.as_millis()
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(backoff) => {}
+                _ = &mut cancelled => break,
+            }
+        }
+        chan.close();
+    }
+
+    /// Reads the messages of `ws_stream` and sends the notifications to `chan`, pinging the
+    /// server every heartbeat interval until the connection drops or the subscription is
+    /// closed.
+    async fn handle_stream(
+        &self,
+        mut ws_stream: WebSocketStream<MaybeTlsStream<TcpStream>>,
+        chan: &Sender<Value>,
+        cancelled: &mut oneshot::Receiver<()>,
+        attempt: &mut u32,
+    ) -> Result<Closed> {
+        let request_id = self.request.get("id");
+        let mut heartbeat = tokio::time::interval(self.heartbeat);
+        let mut last_seen = Instant::now();
+
+        loop {
+            tokio::select! {
+                _ = &mut *cancelled => {
+                    let _ = ws_stream.close(None).await;
+                    return Ok(Closed::Unsubscribed);
+                }
+                _ = heartbeat.tick() => {
+                    if chan.is_closed() {
+                        let _ = ws_stream.close(None).await;
+                        return Ok(Closed::Unsubscribed);
+                    }
+                    // any message, including the pong to the previous ping, proves that the
+                    // connection is alive.
+                    if last_seen.elapsed() > self.heartbeat * 2 {
+                        let secs = last_seen.elapsed().as_secs();
+                        return Err(anyhow!("no message received in {secs}s"));
+                    }
+                    ws_stream.send(Message::Ping(vec![])).await?;
+                }
+                msg = ws_stream.next() => {
+                    let msg = match msg {
+                        None => return Ok(Closed::Disconnected),
+                        Some(msg) => msg?,
+                    };
+                    last_seen = Instant::now();
+                    log::trace!("Read message from websocket stream: {}", msg);
+
+                    let value = match &msg {
+                        Message::Text(text) => serde_json::from_str::<Value>(text),
+                        Message::Binary(bytes) => serde_json::from_slice::<Value>(bytes),
+                        Message::Close(_) => return Ok(Closed::Disconnected),
+                        // pongs are only used to keep track of the connection, and pings are
+                        // answered by the websocket stream.
+                        _ => continue,
+                    };
+                    let value = match value {
+                        Ok(value) => value,
+                        Err(e) => {
+                            log::warn!("cannot parse websocket message {msg}: {e}");
+                            continue;
+                        }
+                    };
+
+                    // the response to the subscription request
+                    if value.get("id").is_some() && value.get("id") == request_id {
+                        if let Some(error) = value.get("error") {
+                            return Err(anyhow!("subscription rejected: {error}"));
+                        }
+                        *attempt = 0;
+                        continue;
+                    }
+
+                    if chan.send(value).await.is_err() {
+                        let _ = ws_stream.close(None).await;
+                        return Ok(Closed::Unsubscribed);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,18 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
-use futures_util::StreamExt;
-use serde_json::json;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
 use url::Url;
 
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl, NO_PARAMS};
+use crate::retry::RetryPolicy;
+use crate::testing::rpc_endpoint;
 
 /// The default endpoints for public lotus node. If the urls fail in running tests, need to
 /// check these endpoints again.
@@ -63,8 +71,110 @@ async fn test_request_with_params_error() {
 async fn test_subscribe() {
     let url = Url::parse(WS_ENDPOINT).unwrap();
     let client = JsonRpcClientImpl::new(url, None);
-    let mut chan = client.subscribe("Filecoin.ChainNotify").await.unwrap();
+    let mut chan = client
+        .subscribe("Filecoin.ChainNotify", NO_PARAMS)
+        .await
+        .unwrap();
     for _ in 1..=3 {
         chan.next().await.unwrap();
     }
 }
+
+/// Starts a stand-in JSON-RPC endpoint that answers every request with its method name,
+/// or an error for the `fail` method. Batch responses are sent in reverse order.
+async fn echo_endpoint() -> Url {
+    rpc_endpoint(|method, _| match method {
+        "fail" => json!({"error": {"code": 1, "message": "failed"}}),
+        method => json!({ "result": method }),
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_request_ids() {
+    let client = JsonRpcClientImpl::new(echo_endpoint().await, None);
+    for method in ["first", "second"] {
+        let response = client.request::<String>(method, NO_PARAMS).await.unwrap();
+        assert_eq!(response, method);
+    }
+    assert_eq!(client.next_id(), 3);
+}
+
+#[tokio::test]
+async fn test_batch_request() {
+    let client = JsonRpcClientImpl::new(echo_endpoint().await, None);
+    let responses = client
+        .batch_request::<String>(vec![
+            ("first", NO_PARAMS),
+            ("fail", NO_PARAMS),
+            ("third", json!([1])),
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(responses.len(), 3);
+    assert_eq!(responses[0].as_ref().unwrap(), "first");
+    assert!(responses[1].is_err());
+    assert_eq!(responses[2].as_ref().unwrap(), "third");
+
+    let responses = client.batch_request::<String>(vec![]).await.unwrap();
+    assert!(responses.is_empty());
+}
+
+/// Starts a stand-in websocket endpoint that answers the subscription request, pushes a
+/// notification with the number of the connection and closes the connection.
+async fn flaky_ws_endpoint() -> Url {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("ws://{}", listener.local_addr().unwrap())).unwrap();
+    let connections = Arc::new(AtomicU64::new(0));
+
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let connection = connections.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let mut ws_stream = tokio_tungstenite::accept_async(stream).await.unwrap();
+                let request = match ws_stream.next().await {
+                    Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text).unwrap(),
+                    _ => return,
+                };
+                assert_eq!(request["params"], json!(["newHeads"]));
+
+                let response = json!({"jsonrpc": "2.0", "id": request["id"], "result": "0x1"});
+                let notification =
+                    json!({"jsonrpc": "2.0", "method": "eth_subscription", "params": connection});
+                for msg in [response, notification] {
+                    ws_stream
+                        .send(Message::text(msg.to_string()))
+                        .await
+                        .unwrap();
+                }
+                let _ = ws_stream.close(None).await;
+            });
+        }
+    });
+
+    url
+}
+
+#[tokio::test]
+async fn test_subscribe_reconnects() {
+    let client = JsonRpcClientImpl::new(flaky_ws_endpoint().await, None).with_retry_policy(
+        RetryPolicy::new(3, Duration::from_millis(10), Duration::from_millis(100)),
+    );
+    let mut subscription = client
+        .subscribe("eth_subscribe", json!(["newHeads"]))
+        .await
+        .unwrap();
+
+    // the response to the subscription request is not forwarded, and every connection
+    // re-subscribes after the previous one is closed.
+    for connection in 0..3 {
+        let notification = subscription.next().await.unwrap();
+        assert_eq!(notification["params"], json!(connection));
+    }
+
+    tokio::time::timeout(Duration::from_secs(5), subscription.unsubscribe())
+        .await
+        .unwrap();
+}
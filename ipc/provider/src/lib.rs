@@ -2,9 +2,10 @@
 // SPDX-License-Identifier: MIT
 //! Ipc agent sdk, contains the json rpc client to interact with the IPC agent rpc server.
 
-use crate::manager::{GetBlockHashResult, TopDownQueryPayload};
+use crate::manager::{GetBlockHashResult, TopDownMsgs, TopDownQueryPayload, ValidatorChanges};
 use anyhow::anyhow;
 use base64::Engine;
+use config::subnet::{SubnetOperation, UnsupportedOperationError};
 use config::Config;
 use fvm_shared::{
     address::{set_current_network, Address, Network},
@@ -16,15 +17,23 @@ use ipc_identity::{
     EthKeyAddress, EvmKeyStore, KeyStore, KeyStoreConfig, PersistentKeyStore, Wallet,
 };
 use ipc_sdk::checkpoint::{BottomUpCheckpointBundle, QuorumReachedEvent};
-use ipc_sdk::staking::{StakingChangeRequest, ValidatorInfo};
+use ipc_sdk::staking::{StakingChangeRequest, SubnetValidator, ValidatorInfo};
 use ipc_sdk::{
-    cross::CrossMsg,
+    address::IPCAddress,
+    cross::{cross_msg_route, CrossMsg, CrossMsgBuilder, IPCMsgType},
     subnet::{ConsensusType, ConstructParams},
     subnet_id::SubnetID,
 };
+use lifecycle::{FundStatus, FundTracker, ReleaseStatus, ReleaseTracker};
 use lotus::message::wallet::WalletKeyType;
-use manager::{EthSubnetManager, SubnetGenesisInfo, SubnetInfo, SubnetManager};
+use manager::{
+    EthSubnetManager, EventSubscriber, EventSubscription, IpcEventFilter, IpcEventStream, IpcLog,
+    IpcLogStream, LotusSubnetManager, MockHierarchy, NonceManager, SignedTransaction,
+    SubnetDetails, SubnetGenesisInfo, SubnetInfo, SubnetManager, TxOutcome, TxResult,
+    UnsignedTransaction,
+};
 use num_traits::FromPrimitive;
+use route::{RouteHop, RoutePlan};
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Borrow,
@@ -32,14 +41,20 @@ use std::{
     path::{Path, PathBuf},
     str::FromStr,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 use zeroize::Zeroize;
 
 pub mod checkpoint;
 pub mod config;
 pub mod jsonrpc;
+pub mod lifecycle;
 pub mod lotus;
 pub mod manager;
+pub mod retry;
+pub mod route;
+#[cfg(test)]
+mod testing;
 
 const DEFAULT_REPO_PATH: &str = ".ipc";
 const DEFAULT_CONFIG_NAME: &str = "config.toml";
@@ -78,6 +93,17 @@ pub struct IpcProvider {
     config: Arc<Config>,
     fvm_wallet: Option<Arc<RwLock<Wallet>>>,
     evm_keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
+    /// When set, connections are served by the in-memory mock hierarchy
+    /// instead of the networks in the config.
+    mock_hierarchy: Option<MockHierarchy>,
+    /// When set, state-changing operations on fevm subnets are simulated instead of sent.
+    dry_run: bool,
+    /// When set, state-changing operations on fevm subnets are built to be signed offline
+    /// instead of sent.
+    offline_signing: bool,
+    /// Shared by all the fevm connections, so that nonces are allocated consistently
+    /// across operations.
+    nonce_manager: NonceManager,
 }
 
 impl IpcProvider {
@@ -91,6 +117,10 @@ impl IpcProvider {
             config,
             fvm_wallet: Some(fvm_wallet),
             evm_keystore: Some(evm_keystore),
+            mock_hierarchy: None,
+            dry_run: false,
+            offline_signing: false,
+            nonce_manager: NonceManager::new(),
         }
     }
 
@@ -127,18 +157,47 @@ impl IpcProvider {
                 config,
                 fvm_wallet: None,
                 evm_keystore: None,
+                mock_hierarchy: None,
+                dry_run: false,
+                offline_signing: false,
+                nonce_manager: NonceManager::new(),
             })
         }
     }
 
+    /// Initializes an `IpcProvider` backed by an in-memory mock hierarchy. All the
+    /// subnets in the config are served by the mock, which allows to exercise the
+    /// provider without a running network.
+    pub fn new_with_mock_hierarchy(config: Config, hierarchy: MockHierarchy) -> Self {
+        Self {
+            sender: None,
+            config: Arc::new(config),
+            fvm_wallet: None,
+            evm_keystore: None,
+            mock_hierarchy: Some(hierarchy),
+            dry_run: false,
+            offline_signing: false,
+            nonce_manager: NonceManager::new(),
+        }
+    }
+
     /// Initialized an `IpcProvider` using the default config path.
     pub fn new_default() -> anyhow::Result<Self> {
         Self::new_from_config(default_config_path())
     }
 
-    /// Get the connection instance for the subnet.
-    pub fn connection(&self, subnet: &SubnetID) -> Option<Connection> {
+    /// Get the connection instance for the subnet, or `None` if the subnet is not in the
+    /// config. Fails if the subnet does not support the dry-run or offline signing modes
+    /// of the provider.
+    pub fn connection(&self, subnet: &SubnetID) -> anyhow::Result<Option<Connection>> {
         let subnets = &self.config.subnets;
+        if let (Some(hierarchy), Some(subnet)) = (&self.mock_hierarchy, subnets.get(subnet)) {
+            return Ok(Some(Connection {
+                manager: Box::new(hierarchy.manager(subnet)),
+                subnet: subnet.clone(),
+            }));
+        }
+
         match subnets.get(subnet) {
             Some(subnet) => match &subnet.config {
                 config::subnet::SubnetConfig::Fevm(_) => {
@@ -151,20 +210,59 @@ impl IpcProvider {
                     };
                     let manager =
                         match EthSubnetManager::from_subnet_with_wallet_store(subnet, wallet) {
-                            Ok(w) => Some(w),
+                            Ok(w) => Some(
+                                w.with_dry_run(self.dry_run)
+                                    .with_offline_signing(self.offline_signing)
+                                    .with_signers(self.config.signers.clone())
+                                    .with_nonce_manager(self.nonce_manager.clone()),
+                            ),
                             Err(e) => {
                                 log::warn!("error initializing evm wallet: {e}");
-                                return None;
+                                return Ok(None);
                             }
                         };
-                    Some(Connection {
+                    Ok(Some(Connection {
                         manager: Box::new(manager.unwrap()),
                         subnet: subnet.clone(),
-                    })
+                    }))
+                }
+                config::subnet::SubnetConfig::Fvm(_) => {
+                    if self.dry_run {
+                        return Err(anyhow!("dry-run is not supported for fvm subnets"));
+                    }
+                    if self.offline_signing {
+                        return Err(anyhow!("offline signing is not supported for fvm subnets"));
+                    }
+                    let manager = match self.fvm_wallet() {
+                        Ok(w) => LotusSubnetManager::from_subnet_with_wallet_store(subnet, w),
+                        Err(e) => {
+                            log::warn!("error initializing fvm wallet: {e}");
+                            LotusSubnetManager::from_subnet(subnet)
+                        }
+                    };
+                    Ok(Some(Connection {
+                        manager: Box::new(manager),
+                        subnet: subnet.clone(),
+                    }))
                 }
             },
-            None => None,
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `connection`, but fails if the subnet does not support `op`.
+    pub fn connection_for(
+        &self,
+        subnet: &SubnetID,
+        op: SubnetOperation,
+    ) -> anyhow::Result<Option<Connection>> {
+        let conn = self.connection(subnet)?;
+        if let Some(conn) = &conn {
+            if !conn.subnet().supports(op) {
+                return Err(anyhow::Error::new(UnsupportedOperationError { op }));
+            }
         }
+        Ok(conn)
     }
 
     /// Set the default account for the provider
@@ -172,6 +270,21 @@ impl IpcProvider {
         self.sender = Some(from);
     }
 
+    /// Simulate state-changing operations instead of sending them. Operations then return
+    /// a `TxResult::Simulated` report describing the expected outcome of the transaction.
+    /// Only supported for fevm subnets.
+    pub fn with_dry_run(&mut self, dry_run: bool) {
+        self.dry_run = dry_run;
+    }
+
+    /// Build state-changing operations without signing them, so that they can be signed
+    /// offline. Operations then return a `TxResult::Unsigned` transaction that is signed
+    /// with `sign_transaction` and broadcast with `send_signed_transaction`. Only supported
+    /// for fevm subnets.
+    pub fn with_offline_signing(&mut self, offline_signing: bool) {
+        self.offline_signing = offline_signing;
+    }
+
     /// Returns the evm wallet if it is configured, and throws an error if no wallet configured.
     ///
     /// This method should be used when we want the wallet retrieval to throw an error
@@ -223,6 +336,14 @@ impl IpcProvider {
                     return Ok(addr);
                 }
             }
+            config::subnet::SubnetConfig::Fvm(_) => {
+                if self.sender.is_none() {
+                    let wallet = self.fvm_wallet()?;
+                    let addr = wallet.read().unwrap().get_default()?;
+                    self.sender = Some(addr);
+                    return Ok(addr);
+                }
+            }
         };
 
         Err(anyhow!("error fetching a valid sender"))
@@ -253,8 +374,8 @@ impl IpcProvider {
         bottomup_check_period: ChainEpoch,
         active_validators_limit: u16,
         min_cross_msg_fee: TokenAmount,
-    ) -> anyhow::Result<Address> {
-        let conn = match self.connection(&parent) {
+    ) -> anyhow::Result<TxResult<(Address, TxOutcome)>> {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -284,9 +405,9 @@ impl IpcProvider {
         from: Option<Address>,
         collateral: TokenAmount,
         public_key: Vec<u8>,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -304,9 +425,9 @@ impl IpcProvider {
         subnet: SubnetID,
         from: Option<Address>,
         balance: TokenAmount,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::PreFund)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -322,9 +443,9 @@ impl IpcProvider {
         subnet: SubnetID,
         from: Option<Address>,
         amount: TokenAmount,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::PreRelease)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -340,9 +461,9 @@ impl IpcProvider {
         subnet: SubnetID,
         from: Option<Address>,
         collateral: TokenAmount,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -358,9 +479,9 @@ impl IpcProvider {
         subnet: SubnetID,
         from: Option<Address>,
         collateral: TokenAmount,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::Unstake)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -375,9 +496,9 @@ impl IpcProvider {
         &mut self,
         subnet: SubnetID,
         from: Option<Address>,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -392,9 +513,9 @@ impl IpcProvider {
         &mut self,
         subnet: SubnetID,
         from: Option<Address>,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::ClaimCollateral)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -409,9 +530,9 @@ impl IpcProvider {
         &mut self,
         subnet: SubnetID,
         from: Option<Address>,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::ClaimRelayerReward)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -426,9 +547,9 @@ impl IpcProvider {
         &mut self,
         subnet: SubnetID,
         from: Option<Address>,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -444,7 +565,7 @@ impl IpcProvider {
         gateway_addr: Option<Address>,
         subnet: &SubnetID,
     ) -> anyhow::Result<HashMap<SubnetID, SubnetInfo>> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -468,9 +589,9 @@ impl IpcProvider {
         from: Option<Address>,
         to: Option<Address>,
         amount: TokenAmount,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -498,8 +619,8 @@ impl IpcProvider {
         to: Option<Address>,
         amount: TokenAmount,
         fee: Option<TokenAmount>,
-    ) -> anyhow::Result<ChainEpoch> {
-        let conn = match self.connection(&subnet) {
+    ) -> anyhow::Result<TxResult> {
+        let conn = match self.connection(&subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -526,17 +647,41 @@ impl IpcProvider {
         _gateway_addr: Address,
         _from: Address,
         _postbox_msg_key: Vec<u8>,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         todo!()
     }
 
+    /// Sends the cross-net message built by `msg` from `subnet`, see [`CrossMsgBuilder`].
     pub async fn send_cross_message(
-        &self,
-        _gateway_addr: Address,
-        _from: Address,
-        _cross_msg: CrossMsg,
-    ) -> anyhow::Result<()> {
-        todo!()
+        &mut self,
+        subnet: SubnetID,
+        gateway_addr: Option<Address>,
+        from: Option<Address>,
+        msg: CrossMsgBuilder,
+    ) -> anyhow::Result<TxResult> {
+        let conn = match self.connection(&subnet)? {
+            None => return Err(anyhow!("target subnet not found")),
+            Some(conn) => conn,
+        };
+
+        let subnet_config = conn.subnet();
+        let sender = self.check_sender(subnet_config, from)?;
+
+        let gateway_addr = match gateway_addr {
+            None => subnet_config.gateway_addr(),
+            Some(addr) => addr,
+        };
+
+        // the message pays the minimum fee of the gateway unless set explicitly.
+        let msg = if msg.has_fee() {
+            msg
+        } else {
+            msg.fee(conn.manager().cross_msg_fee().await?)
+        };
+        let cross_msg = msg.build(IPCAddress::new(&subnet, &sender)?)?;
+        conn.manager()
+            .send_cross_message(gateway_addr, sender, cross_msg)
+            .await
     }
 
     /// Send value between two addresses in a subnet
@@ -546,8 +691,8 @@ impl IpcProvider {
         from: Option<Address>,
         to: Address,
         amount: TokenAmount,
-    ) -> anyhow::Result<()> {
-        let conn = match self.connection(subnet) {
+    ) -> anyhow::Result<TxResult> {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -577,7 +722,7 @@ impl IpcProvider {
         subnet: &SubnetID,
         address: &Address,
     ) -> anyhow::Result<TokenAmount> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -586,7 +731,7 @@ impl IpcProvider {
     }
 
     pub async fn chain_head(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -597,7 +742,7 @@ impl IpcProvider {
     /// Obtain the genesis epoch of the input subnet.
     pub async fn genesis_epoch(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("parent subnet config not found")),
             Some(conn) => conn,
         };
@@ -611,7 +756,7 @@ impl IpcProvider {
         validator: &Address,
     ) -> anyhow::Result<ValidatorInfo> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target subnet parent not found")),
             Some(conn) => conn,
         };
@@ -626,7 +771,7 @@ impl IpcProvider {
         epoch: ChainEpoch,
     ) -> anyhow::Result<TopDownQueryPayload<Vec<StakingChangeRequest>>> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::ValidatorChanges)? {
             None => return Err(anyhow!("target subnet parent not found")),
             Some(conn) => conn,
         };
@@ -634,17 +779,57 @@ impl IpcProvider {
         conn.manager().get_validator_changeset(subnet, epoch).await
     }
 
+    /// Returns the validator changes of a child subnet committed in its parent from `from`
+    /// to `to`, both inclusive, grouped by height.
+    pub async fn get_validator_changeset_range(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> anyhow::Result<Vec<ValidatorChanges>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection_for(&parent, SubnetOperation::ValidatorChanges)? {
+            None => return Err(anyhow!("target subnet parent not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager()
+            .get_validator_changeset_range(subnet, from, to)
+            .await
+    }
+
     /// Get genesis info for a child subnet. This can be used to deterministically
     /// generate the genesis of the subnet
     pub async fn get_genesis_info(&self, subnet: &SubnetID) -> anyhow::Result<SubnetGenesisInfo> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("parent subnet config not found")),
             Some(conn) => conn,
         };
         conn.manager().get_genesis_info(subnet).await
     }
 
+    /// Get the status and configuration of a child subnet from its parent.
+    pub async fn get_subnet_info(&self, subnet: &SubnetID) -> anyhow::Result<SubnetDetails> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent)? {
+            None => return Err(anyhow!("parent subnet config not found")),
+            Some(conn) => conn,
+        };
+        conn.manager().get_subnet_info(subnet).await
+    }
+
+    /// Lists the active and waiting validators of a child subnet, ranked by confirmed
+    /// collateral.
+    pub async fn list_validators(&self, subnet: &SubnetID) -> anyhow::Result<Vec<SubnetValidator>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection(&parent)? {
+            None => return Err(anyhow!("parent subnet config not found")),
+            Some(conn) => conn,
+        };
+        conn.manager().list_validators(subnet).await
+    }
+
     pub async fn get_top_down_msgs(
         &self,
         subnet: &SubnetID,
@@ -652,7 +837,7 @@ impl IpcProvider {
         block_hash: &[u8],
     ) -> anyhow::Result<Vec<CrossMsg>> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::TopDownMessages)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -662,12 +847,115 @@ impl IpcProvider {
             .await
     }
 
+    /// Returns the top down messages of a child subnet committed in its parent from
+    /// `from` to `to`, both inclusive, grouped by height.
+    pub async fn get_top_down_msgs_range(
+        &self,
+        subnet: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> anyhow::Result<Vec<TopDownMsgs>> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let conn = match self.connection_for(&parent, SubnetOperation::TopDownMessages)? {
+            None => return Err(anyhow!("target parent subnet not found")),
+            Some(conn) => conn,
+        };
+
+        conn.manager()
+            .get_top_down_msgs_range(subnet, from, to)
+            .await
+    }
+
+    /// Subscribes to the IPC events emitted in `subnet` through its `provider_ws`
+    /// endpoint. Top-down messages and staking changes of a child subnet are emitted in
+    /// its parent, so that is the subnet to subscribe to.
+    pub fn subscribe(
+        &self,
+        subnet: &SubnetID,
+        subscription: EventSubscription,
+    ) -> anyhow::Result<IpcEventStream> {
+        let subnet = self
+            .config
+            .subnets
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet {subnet} not found in config"))?;
+        EventSubscriber::from_subnet(subnet)?.subscribe(subscription)
+    }
+
+    /// Returns a filter for the `QuorumReached` events of the gateway and the
+    /// `SubnetDeployed` events of the registry of `subnet`. The staking changes of its
+    /// child subnets can be added with `IpcEventFilter::staking_changes`.
+    pub fn ipc_event_filter(&self, subnet: &SubnetID) -> anyhow::Result<IpcEventFilter> {
+        let manager = self.evm_manager(subnet)?;
+        Ok(IpcEventFilter::new()
+            .quorum_reached(manager.gateway_addr())
+            .subnet_deployed(manager.registry_addr()))
+    }
+
+    /// Returns the IPC events matching `filter` emitted in `subnet` between `from` and `to`,
+    /// both inclusive.
+    pub async fn query_events(
+        &self,
+        subnet: &SubnetID,
+        filter: IpcEventFilter,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> anyhow::Result<Vec<IpcLog>> {
+        self.evm_manager(subnet)?
+            .events(filter)
+            .range(from, to)
+            .await
+    }
+
+    /// Returns the IPC events matching `filter` emitted in `subnet` from `from` onwards,
+    /// followed by the new ones. Subscribes through `provider_ws` if the subnet has one,
+    /// polls `provider_http` otherwise.
+    pub fn tail_events(
+        &self,
+        subnet: &SubnetID,
+        filter: IpcEventFilter,
+        from: ChainEpoch,
+    ) -> anyhow::Result<IpcLogStream> {
+        let config = self
+            .config
+            .subnets
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet {subnet} not found in config"))?;
+        match &config.config {
+            config::subnet::SubnetConfig::Fevm(evm) if evm.provider_ws.is_some() => {
+                Ok(EventSubscriber::from_subnet(config)?.subscribe_logs(&filter, Some(from)))
+            }
+            _ => Ok(self.evm_manager(subnet)?.events(filter).tail(from)),
+        }
+    }
+
+    fn evm_manager(&self, subnet: &SubnetID) -> anyhow::Result<EthSubnetManager> {
+        let subnet = self
+            .config
+            .subnets
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet {subnet} not found in config"))?;
+        EthSubnetManager::from_subnet_with_wallet_store(subnet, None)
+    }
+
+    /// Broadcasts a transaction signed offline to `subnet` and waits for its receipt.
+    pub async fn send_signed_transaction(
+        &self,
+        subnet: &SubnetID,
+        signed: &SignedTransaction,
+    ) -> anyhow::Result<TxOutcome> {
+        self.evm_manager(subnet)?
+            .with_nonce_manager(self.nonce_manager.clone())
+            .send_raw_transaction(signed)
+            .await
+    }
+
     pub async fn get_block_hash(
         &self,
         subnet: &SubnetID,
         height: ChainEpoch,
     ) -> anyhow::Result<GetBlockHashResult> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -676,7 +964,7 @@ impl IpcProvider {
     }
 
     pub async fn get_chain_id(&self, subnet: &SubnetID) -> anyhow::Result<String> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -685,7 +973,7 @@ impl IpcProvider {
     }
 
     pub async fn get_chain_head_height(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -698,7 +986,7 @@ impl IpcProvider {
         subnet: &SubnetID,
         height: ChainEpoch,
     ) -> anyhow::Result<BottomUpCheckpointBundle> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection_for(subnet, SubnetOperation::CheckpointRelaying)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -712,7 +1000,7 @@ impl IpcProvider {
         addr: &Address,
     ) -> anyhow::Result<bool> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::CheckpointRelaying)? {
             None => return Err(anyhow!("parent subnet not found")),
             Some(conn) => conn,
         };
@@ -727,7 +1015,7 @@ impl IpcProvider {
         subnet: &SubnetID,
     ) -> anyhow::Result<ChainEpoch> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("parent subnet not found")),
             Some(conn) => conn,
         };
@@ -742,7 +1030,7 @@ impl IpcProvider {
         subnet: &SubnetID,
         height: ChainEpoch,
     ) -> anyhow::Result<Vec<QuorumReachedEvent>> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection_for(subnet, SubnetOperation::CheckpointRelaying)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
@@ -756,9 +1044,9 @@ impl IpcProvider {
         subnet: &SubnetID,
         from: Option<Address>,
         endpoint: String,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<TxResult> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection_for(&parent, SubnetOperation::AddBootstrap)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -774,7 +1062,7 @@ impl IpcProvider {
     /// Lists the bootstrap nodes of a subnet
     pub async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> anyhow::Result<Vec<String>> {
         let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
-        let conn = match self.connection(&parent) {
+        let conn = match self.connection(&parent)? {
             None => return Err(anyhow!("target parent subnet not found")),
             Some(conn) => conn,
         };
@@ -784,13 +1072,137 @@ impl IpcProvider {
 
     /// Returns the latest finality from the parent committed in a child subnet.
     pub async fn latest_parent_finality(&self, subnet: &SubnetID) -> anyhow::Result<ChainEpoch> {
-        let conn = match self.connection(subnet) {
+        let conn = match self.connection(subnet)? {
             None => return Err(anyhow!("target subnet not found")),
             Some(conn) => conn,
         };
 
         conn.manager().latest_parent_finality().await
     }
+
+    /// Returns the stage of a `fund` transaction sent to the parent of `subnet`.
+    pub async fn fund_status(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+    ) -> anyhow::Result<FundStatus> {
+        let (parent, child) =
+            self.parent_and_child_connections(subnet, SubnetOperation::FundStatus)?;
+        FundTracker::new(subnet, parent.manager(), child.manager())
+            .status(tx_hash)
+            .await
+    }
+
+    /// Waits until the top-down message of a `fund` transaction sent to the parent of
+    /// `subnet` is executed in `subnet`, polling every `poll_interval`.
+    pub async fn wait_fund_executed(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+        poll_interval: Duration,
+    ) -> anyhow::Result<FundStatus> {
+        let (parent, child) =
+            self.parent_and_child_connections(subnet, SubnetOperation::FundStatus)?;
+        FundTracker::new(subnet, parent.manager(), child.manager())
+            .wait(tx_hash, poll_interval)
+            .await
+    }
+
+    /// Returns the stage of a `release` transaction with hash `tx_hash` sent in `subnet`,
+    /// from its inclusion in a checkpoint to its execution in the parent.
+    pub async fn release_status(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+    ) -> anyhow::Result<ReleaseStatus> {
+        let (parent, child) =
+            self.parent_and_child_connections(subnet, SubnetOperation::ReleaseStatus)?;
+        ReleaseTracker::new(subnet, parent.manager(), child.manager())
+            .status(tx_hash)
+            .await
+    }
+
+    /// Waits until the bottom-up message of a `release` transaction sent in `subnet` is
+    /// executed in its parent, polling every `poll_interval`.
+    pub async fn wait_release_executed(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+        poll_interval: Duration,
+    ) -> anyhow::Result<ReleaseStatus> {
+        let (parent, child) =
+            self.parent_and_child_connections(subnet, SubnetOperation::ReleaseStatus)?;
+        ReleaseTracker::new(subnet, parent.manager(), child.manager())
+            .wait(tx_hash, poll_interval)
+            .await
+    }
+
+    /// Plans the route of a cross-net message from `from` to `to` through the hierarchy,
+    /// querying the gateway of each subnet along the route for its fee. All the subnets
+    /// along the route must be in the config.
+    pub async fn plan_route(
+        &self,
+        from: &IPCAddress,
+        to: &IPCAddress,
+    ) -> anyhow::Result<RoutePlan> {
+        let mut hops = vec![];
+        for (i, hop) in cross_msg_route(&from.subnet()?, &to.subnet()?)?
+            .into_iter()
+            .enumerate()
+        {
+            let conn = self
+                .connection(&hop.from)?
+                .ok_or_else(|| anyhow!("subnet config not found for {}", hop.from))?;
+            let msg_type = hop.msg_type();
+            let checkpoint_period = match msg_type {
+                IPCMsgType::BottomUp => {
+                    let parent = self
+                        .connection(&hop.to)?
+                        .ok_or_else(|| anyhow!("subnet config not found for {}", hop.to))?;
+                    Some(parent.manager().checkpoint_period(&hop.from).await?)
+                }
+                IPCMsgType::TopDown => None,
+            };
+
+            hops.push(RouteHop {
+                gateway: conn.subnet().gateway_addr(),
+                min_fee: conn.manager().cross_msg_fee().await?,
+                propagate: i > 0,
+                checkpoint_period,
+                msg_type,
+                from: hop.from,
+                to: hop.to,
+            });
+        }
+        Ok(RoutePlan {
+            from: from.clone(),
+            to: to.clone(),
+            hops,
+        })
+    }
+
+    /// Returns `from` or the default sender of the provider in `subnet`.
+    pub fn sender(&mut self, subnet: &SubnetID, from: Option<Address>) -> anyhow::Result<Address> {
+        let conn = self
+            .connection(subnet)?
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+        self.check_sender(conn.subnet(), from)
+    }
+
+    fn parent_and_child_connections(
+        &self,
+        subnet: &SubnetID,
+        op: SubnetOperation,
+    ) -> anyhow::Result<(Connection, Connection)> {
+        let parent = subnet.parent().ok_or_else(|| anyhow!("no parent found"))?;
+        let parent = self
+            .connection_for(&parent, op)?
+            .ok_or_else(|| anyhow!("parent subnet config not found"))?;
+        let child = self
+            .connection_for(subnet, op)?
+            .ok_or_else(|| anyhow!("target subnet not found"))?;
+        Ok((parent, child))
+    }
 }
 
 /// Lotus JSON keytype format
@@ -875,6 +1287,22 @@ impl IpcProvider {
         keystore.put(ipc_identity::EvmKeyInfo::new(private_key))
     }
 
+    /// Signs a transaction built in offline-signing mode with the key of its sender in the
+    /// evm keystore.
+    pub fn sign_transaction(
+        &self,
+        unsigned: &UnsignedTransaction,
+    ) -> anyhow::Result<SignedTransaction> {
+        let from = unsigned.from()?;
+        let keystore = self.evm_wallet()?;
+        let private_key =
+            keystore.read().unwrap().get(&from.into())?.ok_or_else(|| {
+                anyhow!("address {from:?} does not have private key in key store")
+            })?;
+        let wallet = ethers::signers::LocalWallet::from_bytes(private_key.private_key())?;
+        unsigned.sign(&wallet)
+    }
+
     pub fn import_evm_key_from_json(&self, keyinfo: String) -> anyhow::Result<EthKeyAddress> {
         let persisted: ipc_identity::PersistentKeyInfo = serde_json::from_str(&keyinfo)?;
         self.import_evm_key_from_privkey(persisted.private_key().parse()?)
@@ -0,0 +1,277 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Tracking of the cross-net messages sent between a parent and a child subnet through the
+//! stages they go through until they are executed in the destination subnet.
+
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use anyhow::Result;
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::subnet_id::SubnetID;
+
+use crate::manager::{CheckpointedBottomUpMsg, CommittedTopDownMsg, SubnetManager};
+
+/// The default interval between two queries when waiting for a message to be executed.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The stages of a `fund` from a parent to a child subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FundStage {
+    /// The transaction is not included in the parent yet, or the child has not committed
+    /// the parent finality of the height that included it.
+    Pending,
+    /// The child committed the parent finality of the height that included the transaction,
+    /// but did not execute its top-down message yet.
+    FinalizedInParent,
+    /// The top-down message was executed in the child.
+    ExecutedInChild,
+}
+
+impl Display for FundStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FundStage::Pending => write!(f, "pending"),
+            FundStage::FinalizedInParent => write!(f, "finalized in parent"),
+            FundStage::ExecutedInChild => write!(f, "executed in child"),
+        }
+    }
+}
+
+/// The stage of a `fund`, with its top-down message once committed in the parent.
+#[derive(Debug, Clone)]
+pub struct FundStatus {
+    pub stage: FundStage,
+    pub msg: Option<CommittedTopDownMsg>,
+}
+
+impl Display for FundStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.msg {
+            Some(msg) => write!(
+                f,
+                "{} (nonce: {}, parent height: {}, value: {})",
+                self.stage, msg.msg.msg.nonce, msg.height, msg.msg.msg.value
+            ),
+            None => write!(f, "{} (not included in the parent yet)", self.stage),
+        }
+    }
+}
+
+/// Tracks the `fund` transactions to a child subnet, querying the parent for the top-down
+/// message of the transaction and the child for its parent finality and applied nonce.
+pub struct FundTracker<'a> {
+    subnet: &'a SubnetID,
+    parent: &'a dyn SubnetManager,
+    child: &'a dyn SubnetManager,
+}
+
+impl<'a> FundTracker<'a> {
+    pub fn new(
+        subnet: &'a SubnetID,
+        parent: &'a dyn SubnetManager,
+        child: &'a dyn SubnetManager,
+    ) -> Self {
+        Self {
+            subnet,
+            parent,
+            child,
+        }
+    }
+
+    /// Returns the current stage of the `fund` transaction with hash `tx_hash`.
+    pub async fn status(&self, tx_hash: &[u8]) -> Result<FundStatus> {
+        let Some(msg) = self.parent.get_fund_msg(self.subnet, tx_hash).await? else {
+            return Ok(FundStatus {
+                stage: FundStage::Pending,
+                msg: None,
+            });
+        };
+
+        let stage = if self.child.applied_top_down_nonce().await? > msg.msg.msg.nonce {
+            FundStage::ExecutedInChild
+        } else if self.child.latest_parent_finality().await? >= msg.height {
+            FundStage::FinalizedInParent
+        } else {
+            FundStage::Pending
+        };
+        Ok(FundStatus {
+            stage,
+            msg: Some(msg),
+        })
+    }
+
+    /// Polls the stage of the transaction every `poll_interval` until its top-down message
+    /// is executed in the child.
+    pub async fn wait(&self, tx_hash: &[u8], poll_interval: Duration) -> Result<FundStatus> {
+        let mut stage = None;
+        loop {
+            let status = self.status(tx_hash).await?;
+            if stage != Some(status.stage) {
+                log::info!("fund 0x{} is {status}", hex::encode(tx_hash));
+                stage = Some(status.stage);
+            }
+            if status.stage == FundStage::ExecutedInChild {
+                return Ok(status);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+/// The stages of a `release` from a child to its parent subnet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReleaseStage {
+    /// The transaction is not included in the child yet.
+    Pending,
+    /// The bottom-up message is in the checkpoint of the child, which has not reached the
+    /// quorum of signatures of its validators yet.
+    InCheckpoint,
+    /// The checkpoint reached its quorum, but was not submitted to the parent yet.
+    QuorumReached,
+    /// The checkpoint was submitted to the parent, but its message was not executed yet.
+    SubmittedToParent,
+    /// The bottom-up message was executed in the parent.
+    ExecutedInParent,
+}
+
+impl Display for ReleaseStage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReleaseStage::Pending => write!(f, "pending"),
+            ReleaseStage::InCheckpoint => write!(f, "in checkpoint"),
+            ReleaseStage::QuorumReached => write!(f, "quorum reached"),
+            ReleaseStage::SubmittedToParent => write!(f, "submitted to parent"),
+            ReleaseStage::ExecutedInParent => write!(f, "executed in parent"),
+        }
+    }
+}
+
+/// The stage of a `release`, with its bottom-up message once included in the child.
+#[derive(Debug, Clone)]
+pub struct ReleaseStatus {
+    pub stage: ReleaseStage,
+    pub msg: Option<CheckpointedBottomUpMsg>,
+}
+
+impl Display for ReleaseStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.msg {
+            Some(msg) => write!(
+                f,
+                "{} (nonce: {}, child height: {}, checkpoint height: {}, value: {})",
+                self.stage, msg.msg.msg.nonce, msg.height, msg.checkpoint_height, msg.msg.msg.value
+            ),
+            None => write!(f, "{} (not included in the child yet)", self.stage),
+        }
+    }
+}
+
+/// Tracks the `release` transactions from a child subnet, querying the child for the
+/// bottom-up message of the transaction and the quorum of its checkpoint, and the parent
+/// for the last checkpoint submitted and the applied nonce.
+pub struct ReleaseTracker<'a> {
+    subnet: &'a SubnetID,
+    parent: &'a dyn SubnetManager,
+    child: &'a dyn SubnetManager,
+}
+
+impl<'a> ReleaseTracker<'a> {
+    pub fn new(
+        subnet: &'a SubnetID,
+        parent: &'a dyn SubnetManager,
+        child: &'a dyn SubnetManager,
+    ) -> Self {
+        Self {
+            subnet,
+            parent,
+            child,
+        }
+    }
+
+    /// Returns the current stage of the `release` transaction with hash `tx_hash`.
+    pub async fn status(&self, tx_hash: &[u8]) -> Result<ReleaseStatus> {
+        match self.release_msg(tx_hash).await? {
+            Some(msg) => self.msg_status(msg).await,
+            None => Ok(ReleaseStatus {
+                stage: ReleaseStage::Pending,
+                msg: None,
+            }),
+        }
+    }
+
+    /// The bottom-up message of the `release` transaction with hash `tx_hash`, `None` if the
+    /// transaction is not included in the child yet. Resolving the message scans the blocks
+    /// of the child since the previous checkpoint, and it does not change once found.
+    async fn release_msg(&self, tx_hash: &[u8]) -> Result<Option<CheckpointedBottomUpMsg>> {
+        let period = self.parent.checkpoint_period(self.subnet).await?;
+        self.child.get_release_msg(tx_hash, period).await
+    }
+
+    /// The stage of the bottom-up message `msg` of a `release`.
+    async fn msg_status(&self, msg: CheckpointedBottomUpMsg) -> Result<ReleaseStatus> {
+        let checkpoint_height = msg.checkpoint_height;
+        let stage = if self.parent.applied_bottom_up_nonce(self.subnet).await? > msg.msg.msg.nonce {
+            ReleaseStage::ExecutedInParent
+        } else if self
+            .parent
+            .last_bottom_up_checkpoint_height(self.subnet)
+            .await?
+            >= checkpoint_height
+        {
+            ReleaseStage::SubmittedToParent
+        } else if self.quorum_reached(checkpoint_height).await? {
+            ReleaseStage::QuorumReached
+        } else if self.child.current_epoch().await? >= checkpoint_height {
+            ReleaseStage::InCheckpoint
+        } else {
+            ReleaseStage::Pending
+        };
+        Ok(ReleaseStatus {
+            stage,
+            msg: Some(msg),
+        })
+    }
+
+    /// Whether the checkpoint of the child at `checkpoint_height` reached its quorum, which
+    /// happens at any height after the checkpoint is cut.
+    async fn quorum_reached(&self, checkpoint_height: ChainEpoch) -> Result<bool> {
+        let head = self.child.current_epoch().await?;
+        if head < checkpoint_height {
+            return Ok(false);
+        }
+        let events = self
+            .child
+            .quorum_reached_events_range(checkpoint_height, head)
+            .await?;
+        Ok(events.iter().any(|e| e.height == checkpoint_height))
+    }
+
+    /// Polls the stage of the transaction every `poll_interval` until its bottom-up message
+    /// is executed in the parent. The message is only resolved until it is found, then only
+    /// its stage is polled.
+    pub async fn wait(&self, tx_hash: &[u8], poll_interval: Duration) -> Result<ReleaseStatus> {
+        let mut stage = None;
+        let mut msg = None;
+        loop {
+            if msg.is_none() {
+                msg = self.release_msg(tx_hash).await?;
+            }
+            let status = match &msg {
+                Some(msg) => self.msg_status(msg.clone()).await?,
+                None => ReleaseStatus {
+                    stage: ReleaseStage::Pending,
+                    msg: None,
+                },
+            };
+            if stage != Some(status.stage) {
+                log::info!("release 0x{} is {status}", hex::encode(tx_hash));
+                stage = Some(status.stage);
+            }
+            if status.stage == ReleaseStage::ExecutedInParent {
+                return Ok(status);
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
@@ -24,6 +24,7 @@ use serde_json::json;
 
 use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl, NO_PARAMS};
 use crate::lotus::message::chain::{ChainHeadResponse, GetTipSetByHeightResponse};
+use crate::lotus::message::ipc::SubnetInfo;
 use crate::lotus::message::mpool::{
     EstimateGasResponse, MpoolPushMessage, MpoolPushMessageResponse, MpoolPushMessageResponseInner,
 };
@@ -31,6 +32,7 @@ use crate::lotus::message::state::{ReadStateResponse, StateWaitMsgResponse};
 use crate::lotus::message::wallet::{WalletKeyType, WalletListResponse};
 use crate::lotus::message::CIDMap;
 use crate::lotus::{LotusClient, NetworkVersion};
+use crate::retry::subnet_retry_policy;
 
 pub type DefaultLotusJsonRPCClient = LotusJsonRPCClient<JsonRpcClientImpl>;
 
@@ -51,6 +53,7 @@ mod methods {
     pub const CHAIN_HEAD: &str = "Filecoin.ChainHead";
     pub const GET_TIPSET_BY_HEIGHT: &str = "Filecoin.ChainGetTipSetByHeight";
     pub const ESTIMATE_MESSAGE_GAS: &str = "Filecoin.GasEstimateMessageGas";
+    pub const IPC_LIST_CHILD_SUBNETS: &str = "Filecoin.IPCListChildSubnets";
 }
 
 /// The default state wait confidence value
@@ -348,6 +351,42 @@ impl<T: JsonRpcClient + Send + Sync> LotusClient for LotusJsonRPCClient<T> {
         log::debug!("received get_tipset_by_height response: {r:?}");
         Ok(r)
     }
+
+    async fn get_tipsets_by_height(
+        &self,
+        epochs: &[ChainEpoch],
+        tip_set: Cid,
+    ) -> Result<Vec<GetTipSetByHeightResponse>> {
+        let requests = epochs
+            .iter()
+            .map(|epoch| {
+                (
+                    methods::GET_TIPSET_BY_HEIGHT,
+                    json!([epoch, [CIDMap::from(tip_set)]]),
+                )
+            })
+            .collect();
+        let r = self
+            .client
+            .batch_request::<GetTipSetByHeightResponse>(requests)
+            .await?
+            .into_iter()
+            .collect::<Result<Vec<_>>>()?;
+        log::debug!("received get_tipsets_by_height response: {r:?}");
+        Ok(r)
+    }
+
+    async fn ipc_list_child_subnets(&self, gateway_addr: Address) -> Result<Vec<SubnetInfo>> {
+        let r = self
+            .client
+            .request::<Vec<SubnetInfo>>(
+                methods::IPC_LIST_CHILD_SUBNETS,
+                json!([gateway_addr.to_string()]),
+            )
+            .await?;
+        log::debug!("received ipc_list_child_subnets response: {r:?}");
+        Ok(r)
+    }
 }
 
 impl<T: JsonRpcClient + Send + Sync> LotusJsonRPCClient<T> {
@@ -449,7 +488,8 @@ impl LotusJsonRPCClient<JsonRpcClientImpl> {
     pub fn from_subnet(subnet: &crate::config::Subnet) -> Self {
         let url = subnet.rpc_http().clone();
         let auth_token = subnet.auth_token();
-        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref());
+        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref())
+            .with_retry_policy(subnet_retry_policy(subnet));
         LotusJsonRPCClient::new(jsonrpc_client, subnet.id.clone())
     }
 
@@ -459,7 +499,8 @@ impl LotusJsonRPCClient<JsonRpcClientImpl> {
     ) -> Self {
         let url = subnet.rpc_http().clone();
         let auth_token = subnet.auth_token();
-        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref());
+        let jsonrpc_client = JsonRpcClientImpl::new(url, auth_token.as_deref())
+            .with_retry_policy(subnet_retry_policy(subnet));
         LotusJsonRPCClient::new_with_wallet_store(jsonrpc_client, subnet.id.clone(), wallet_store)
     }
 }
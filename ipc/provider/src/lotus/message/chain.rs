@@ -1,6 +1,7 @@
 use cid::Cid;
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use fvm_shared::clock::ChainEpoch;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -11,6 +12,7 @@ use crate::lotus::message::CIDMap;
 #[serde(rename_all = "PascalCase")]
 pub struct Block {
     parent_state_root: CIDMap,
+    parents: Vec<CIDMap>,
 }
 
 /// A simplified struct representing a `ChainGetTipSetByHeight` response that does not fully
@@ -20,6 +22,8 @@ pub struct Block {
 pub struct GetTipSetByHeightResponse {
     pub cids: Vec<CIDMap>,
     blocks: Vec<Block>,
+    /// The height of the tipset, lower than the one requested if it was a null round.
+    pub height: ChainEpoch,
 }
 
 impl GetTipSetByHeightResponse {
@@ -41,6 +45,14 @@ impl GetTipSetByHeightResponse {
             .map(|b| Cid::try_from(&b.parent_state_root))
             .collect()
     }
+
+    /// The cids of the parent tipset, shared by all the blocks of the tipset.
+    pub fn parent_cids(&self) -> anyhow::Result<Vec<Cid>> {
+        match self.blocks.first() {
+            Some(block) => block.parents.iter().map(Cid::try_from).collect(),
+            None => Err(anyhow::anyhow!("tipset has no blocks")),
+        }
+    }
 }
 
 /// A simplified struct representing a `ChainHead` response that does not decode the `blocks` field.
@@ -34,6 +34,9 @@ pub struct IPCReadGatewayStateResponse {
     pub bottom_up_check_period: ChainEpoch,
     pub top_down_check_period: ChainEpoch,
     pub applied_topdown_nonce: u64,
+    pub applied_bottomup_nonce: u64,
+    #[serde(deserialize_with = "deserialize_token_amount_from_str")]
+    pub cross_msg_fee: TokenAmount,
     pub top_down_checkpoint_voting: Voting,
     pub initialized: bool,
 }
@@ -44,6 +47,9 @@ pub struct IPCReadGatewayStateResponse {
 #[serde(rename_all = "PascalCase")]
 pub struct IPCReadSubnetActorStateResponse {
     pub bottom_up_check_period: ChainEpoch,
+    pub genesis_epoch: ChainEpoch,
+    #[serde(deserialize_with = "deserialize_token_amount_from_str")]
+    pub min_validator_stake: TokenAmount,
     pub validator_set: ValidatorSet,
     pub min_validators: u64,
     pub bottom_up_checkpoint_voting: Voting,
@@ -56,6 +62,26 @@ pub struct IPCReadSubnetActorStateResponse {
 pub struct Voting {
     pub genesis_epoch: i64,
     pub last_voting_executed: i64,
+    /// The share of the voting power needed to execute a checkpoint.
+    pub ratio: Ratio,
+}
+
+/// A fraction as encoded by the native actors.
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+pub struct Ratio {
+    pub num: u64,
+    pub denom: u64,
+}
+
+impl Ratio {
+    /// The ratio as a percentage, rounded down.
+    pub fn percentage(&self) -> u8 {
+        if self.denom == 0 {
+            return 0;
+        }
+        (self.num * 100 / self.denom).min(100) as u8
+    }
 }
 
 /// SubnetInfo is an auxiliary struct that collects relevant information about the state of a subnet
@@ -138,6 +164,8 @@ pub struct StorableMsgsWrapper {
 
 #[cfg(test)]
 mod tests {
+    use fvm_shared::econ::TokenAmount;
+
     use crate::lotus::message::ipc::IPCReadSubnetActorStateResponse;
 
     #[test]
@@ -146,7 +174,8 @@ mod tests {
         {"Name":"test2","ParentID":{"Parent":"/r31415926","Actor":"t00"},"IPCGatewayAddr":"f064","Consensus":3,"MinValidatorStake":"1000000000000000000","TotalStake":"10000000000000000000","Stake":{"/":"bafy2bzacebentzoqaapingrxwknlxqcusl23rqaa7cwb42u76fgvb25nxpmhq"},"Status":1,"Genesis":null,"BottomUpCheckPeriod":10,"TopDownCheckPeriod":10,"GenesisEpoch":0,"CommittedCheckpoints":{"/":"bafy2bzaceamp42wmmgr2g2ymg46euououzfyck7szknvfacqscohrvaikwfay"},"ValidatorSet":{"validators":[{"addr":"t1cp4q4lqsdhob23ysywffg2tvbmar5cshia4rweq","net_addr":"test","weight":"10000000000000000000"}],"configuration_number":1},"MinValidators":1,"PreviousExecutedCheckpoint":{"/":"bafy2bzacedkoa623kvi5gfis2yks7xxjl73vg7xwbojz4tpq63dd5jpfz757i"},"BottomUpCheckpointVoting":{"GenesisEpoch":0,"SubmissionPeriod":10,"LastVotingExecuted":0,"ExecutableEpochQueue":null,"EpochVoteSubmission":{"/":"bafy2bzaceamp42wmmgr2g2ymg46euououzfyck7szknvfacqscohrvaikwfay"},"Ratio":{"Num":2,"Denom":3}}}
         "#;
 
-        let r = serde_json::from_str::<IPCReadSubnetActorStateResponse>(raw);
-        assert!(r.is_ok());
+        let r = serde_json::from_str::<IPCReadSubnetActorStateResponse>(raw).unwrap();
+        assert_eq!(r.min_validator_stake, TokenAmount::from_whole(1));
+        assert_eq!(r.bottom_up_checkpoint_voting.ratio.percentage(), 66);
     }
 }
@@ -12,12 +12,9 @@ use crate::lotus::message::CIDMap;
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct StateWaitMsgResponse {
-    #[allow(dead_code)]
-    message: CIDMap,
-    #[allow(dead_code)]
+    pub(crate) message: CIDMap,
     pub(crate) receipt: Receipt,
-    #[allow(dead_code)]
-    tip_set: Vec<CIDMap>,
+    pub(crate) tip_set: Vec<CIDMap>,
     pub height: u64,
 }
 
@@ -35,12 +32,10 @@ pub struct ReadStateResponse<State> {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Receipt {
-    #[allow(dead_code)]
-    exit_code: u32,
+    pub(crate) exit_code: u32,
     #[serde(rename = "Return")]
     pub result: Option<String>,
-    #[allow(dead_code)]
-    gas_used: u64,
+    pub(crate) gas_used: u64,
 }
 
 impl Receipt {
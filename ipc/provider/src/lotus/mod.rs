@@ -13,6 +13,7 @@ use fvm_shared::econ::TokenAmount;
 use serde::de::DeserializeOwned;
 
 use crate::lotus::message::chain::GetTipSetByHeightResponse;
+use crate::lotus::message::ipc::SubnetInfo;
 use message::chain::ChainHeadResponse;
 use message::mpool::{MpoolPushMessage, MpoolPushMessageResponseInner};
 use message::state::{ReadStateResponse, StateWaitMsgResponse};
@@ -89,4 +90,14 @@ pub trait LotusClient {
         epoch: ChainEpoch,
         tip_set: Cid,
     ) -> Result<GetTipSetByHeightResponse>;
+
+    /// Gets the tipsets at `epochs` in a single batch request, in the same order.
+    async fn get_tipsets_by_height(
+        &self,
+        epochs: &[ChainEpoch],
+        tip_set: Cid,
+    ) -> Result<Vec<GetTipSetByHeightResponse>>;
+
+    /// Lists the child subnets registered in the gateway actor at `gateway_addr`.
+    async fn ipc_list_child_subnets(&self, gateway_addr: Address) -> Result<Vec<SubnetInfo>>;
 }
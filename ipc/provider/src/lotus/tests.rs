@@ -1,5 +1,6 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
+use cid::Cid;
 use url::Url;
 
 use crate::jsonrpc::JsonRpcClientImpl;
@@ -49,3 +50,19 @@ async fn chain_head() {
     assert!(!head.blocks.is_empty());
     assert_eq!(head.cids.len(), head.blocks.len());
 }
+
+#[tokio::test]
+#[ignore]
+async fn get_tipsets_by_height() {
+    let client = get_lotus_client();
+    let head = client.chain_head().await.unwrap();
+    let tipsets = client
+        .get_tipsets_by_height(
+            &[head.height as i64 - 2, head.height as i64 - 1],
+            Cid::try_from(&head.cids[0]).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(tipsets.len(), 2);
+    assert!(tipsets.iter().all(|t| !t.cids.is_empty()));
+}
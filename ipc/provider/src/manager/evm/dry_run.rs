@@ -0,0 +1,166 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Simulation of state-changing calls without broadcasting them to the network.
+
+use std::fmt::{Display, Formatter};
+
+use anyhow::{anyhow, Result};
+use ethers::prelude::Middleware;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::U256;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::eth_to_fil_amount;
+
+use super::error::{revert_data_from_middleware_error, IpcContractError};
+use super::gas::{GasCeilingExceeded, GasFees};
+use super::manager::DefaultSignerMiddleware;
+
+/// The result of simulating a state-changing call with `eth_call` and `eth_estimateGas`
+/// instead of sending it, returned as a `TxResult::Simulated` by the mutating operations
+/// when the manager runs in dry-run mode.
+#[derive(Debug, Clone)]
+pub struct DryRunReport {
+    /// The contract method (or plain transfer) that was simulated.
+    pub method: String,
+    /// The gas the transaction is expected to consume, `None` if it would revert.
+    pub gas: Option<U256>,
+    /// The gas premium computed by the premium estimation.
+    pub max_priority_fee_per_gas: U256,
+    /// The fee cap computed by the premium estimation.
+    pub max_fee_per_gas: U256,
+    /// The decoded revert reason if the transaction would fail.
+    pub revert_reason: Option<String>,
+    /// Set if the fees are above the `max_fee_per_gas_ceiling` of the subnet, in which
+    /// case the transaction would not be sent.
+    pub gas_ceiling_exceeded: Option<GasCeilingExceeded>,
+}
+
+impl DryRunReport {
+    /// Returns true if the simulated transaction would be sent and executed successfully.
+    pub fn would_succeed(&self) -> bool {
+        self.revert_reason.is_none() && self.gas_ceiling_exceeded.is_none()
+    }
+
+    /// The upper bound of the fee paid for the transaction, i.e. `gas * max_fee_per_gas`.
+    pub fn max_fee(&self) -> Option<TokenAmount> {
+        let gas = self.gas?;
+        eth_to_fil_amount(&gas.checked_mul(self.max_fee_per_gas)?).ok()
+    }
+}
+
+impl Display for DryRunReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "dry run of `{}`:", self.method)?;
+        match &self.revert_reason {
+            None => writeln!(f, "  status: would succeed")?,
+            Some(reason) => writeln!(f, "  status: would revert: {reason}")?,
+        }
+        match self.gas {
+            Some(gas) => writeln!(f, "  gas: {gas}")?,
+            None => writeln!(f, "  gas: n/a")?,
+        }
+        writeln!(
+            f,
+            "  max priority fee per gas: {}",
+            self.max_priority_fee_per_gas
+        )?;
+        writeln!(f, "  max fee per gas: {}", self.max_fee_per_gas)?;
+        if let Some(exceeded) = &self.gas_ceiling_exceeded {
+            writeln!(f, "  would not be sent: {exceeded}")?;
+        }
+        match self.max_fee() {
+            Some(fee) => write!(f, "  max fee: {fee} FIL"),
+            None => write!(f, "  max fee: n/a"),
+        }
+    }
+}
+
+/// Runs `tx` through `eth_call` and `eth_estimateGas` on the signer's provider and reports
+/// the expected gas together with the estimated fees.
+///
+/// Reverts are captured in the report, any other error (e.g. connectivity) is returned.
+pub(crate) async fn simulate_transaction(
+    signer: &DefaultSignerMiddleware,
+    method: &str,
+    tx: &TypedTransaction,
+    fees: &GasFees,
+) -> Result<DryRunReport> {
+    let mut report = DryRunReport {
+        method: method.to_string(),
+        gas: None,
+        max_priority_fee_per_gas: fees.max_priority_fee_per_gas,
+        max_fee_per_gas: fees.max_fee_per_gas,
+        revert_reason: None,
+        gas_ceiling_exceeded: None,
+    };
+
+    if let Err(e) = signer.call(tx, None).await {
+        let Some(data) = revert_data_from_middleware_error(&e) else {
+            return Err(anyhow!("cannot simulate {method}: {e}"));
+        };
+
+        report.revert_reason = Some(if data.is_empty() {
+            e.to_string()
+        } else {
+            IpcContractError::from_revert_data(&data).to_string()
+        });
+        return Ok(report);
+    }
+
+    let gas = signer
+        .estimate_gas(tx, None)
+        .await
+        .map_err(|e| anyhow!("cannot estimate gas for {method}: {e}"))?;
+    report.gas = Some(gas);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+    use fvm_shared::econ::TokenAmount;
+
+    use super::DryRunReport;
+    use crate::manager::evm::gas::{GasCeilingExceeded, GasFees};
+
+    fn report(gas: Option<u64>, revert_reason: Option<&str>) -> DryRunReport {
+        DryRunReport {
+            method: "join".to_string(),
+            gas: gas.map(U256::from),
+            max_priority_fee_per_gas: U256::from(100),
+            max_fee_per_gas: U256::from(1_000),
+            revert_reason: revert_reason.map(String::from),
+            gas_ceiling_exceeded: None,
+        }
+    }
+
+    #[test]
+    fn test_max_fee() {
+        let r = report(Some(21_000), None);
+        assert!(r.would_succeed());
+        assert_eq!(r.max_fee(), Some(TokenAmount::from_atto(21_000_000)));
+    }
+
+    #[test]
+    fn test_reverted_report() {
+        let r = report(None, Some("NotEnoughCollateral"));
+        assert!(!r.would_succeed());
+        assert_eq!(r.max_fee(), None);
+        assert!(r.to_string().contains("would revert: NotEnoughCollateral"));
+    }
+
+    #[test]
+    fn test_report_above_gas_ceiling() {
+        let mut r = report(Some(21_000), None);
+        r.gas_ceiling_exceeded = Some(GasCeilingExceeded {
+            fees: GasFees {
+                max_priority_fee_per_gas: U256::from(100),
+                max_fee_per_gas: U256::from(1_000),
+            },
+            ceiling: U256::from(500),
+        });
+        assert!(!r.would_succeed());
+        assert!(r.to_string().contains("would not be sent"));
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Decoding of the errors raised by the IPC contracts.
+
+use std::time::Duration;
+
+use ethers::abi::{Abi, AbiError, Token};
+use ethers::providers::{Middleware, MiddlewareError};
+use ethers::types::{H256, U256};
+use ethers_contract::{ContractError, EthError};
+use fvm_shared::address::Address;
+use ipc_actors_abis::{
+    gateway_getter_facet, gateway_manager_facet, gateway_messenger_facet, gateway_router_facet,
+    subnet_actor_getter_facet, subnet_actor_manager_facet, subnet_registry,
+};
+use ipc_sdk::ethers_address_to_fil_address;
+
+/// Selector of the solidity `Panic(uint256)` error.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// The error of a reverted call to the IPC contracts, decoded from the revert data
+/// against the errors declared in the contract ABIs.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IpcContractError {
+    #[error("subnet is already registered")]
+    AlreadyRegisteredSubnet,
+    #[error("cannot release zero funds")]
+    CannotReleaseZero,
+    #[error("cannot send a cross-net message to the same subnet")]
+    CannotSendCrossMsgToItself,
+    #[error("collateral is zero")]
+    CollateralIsZero,
+    #[error("invalid checkpoint epoch")]
+    InvalidCheckpointEpoch,
+    #[error("invalid collateral")]
+    InvalidCollateral,
+    #[error("invalid destination subnet for cross-net message")]
+    InvalidCrossMsgDstSubnet,
+    #[error("invalid value for cross-net message")]
+    InvalidCrossMsgValue,
+    #[error("invalid signature: error code {0}")]
+    InvalidSignatureErr(u8),
+    #[error("method not allowed: {0}")]
+    MethodNotAllowed(String),
+    #[error("{0} is not authorized")]
+    NotAuthorized(Address),
+    #[error("not enough balance")]
+    NotEnoughBalance,
+    #[error("not enough collateral")]
+    NotEnoughCollateral,
+    #[error("not enough fee for the cross-net message")]
+    NotEnoughFee,
+    #[error("not enough funds")]
+    NotEnoughFunds,
+    #[error("not enough funds to release")]
+    NotEnoughFundsToRelease,
+    #[error("not enough circulating supply in the subnet")]
+    NotEnoughSubnetCircSupply,
+    #[error("caller is not the gateway")]
+    NotGateway,
+    #[error("subnet is not registered")]
+    NotRegisteredSubnet,
+    #[error("{0} is not a validator")]
+    NotValidator(Address),
+    #[error("subnet is already killed")]
+    SubnetAlreadyKilled,
+    #[error("subnet is already bootstrapped")]
+    SubnetAlreadyBootstrapped,
+    #[error("subnet is not active")]
+    SubnetNotActive,
+    #[error("subnet not found")]
+    SubnetNotFound,
+    #[error("withdrawal exceeds the collateral")]
+    WithdrawExceedingCollateral,
+    /// Any other error declared in the IPC contracts.
+    #[error("{name}({})", args.join(", "))]
+    Other { name: String, args: Vec<String> },
+    /// A `require`/`revert` with a reason string.
+    #[error("reverted: {0}")]
+    Revert(String),
+    /// A solidity panic, e.g. an arithmetic overflow.
+    #[error("panic with code {0:#x}")]
+    Panic(U256),
+    /// Revert data that does not match any known error.
+    #[error("reverted with unknown data: 0x{}", hex::encode(.0))]
+    Unknown(Vec<u8>),
+}
+
+impl IpcContractError {
+    /// Decodes the revert data of a failed call to the IPC contracts.
+    pub fn from_revert_data(data: &[u8]) -> Self {
+        if let Some(reason) = String::decode_with_selector(data) {
+            return Self::Revert(reason);
+        }
+        if let Some(code) = data.strip_prefix(&PANIC_SELECTOR) {
+            if let Ok(code) = ethers::abi::decode(&[ethers::abi::ParamType::Uint(256)], code) {
+                if let Some(code) = code.into_iter().next().and_then(Token::into_uint) {
+                    return Self::Panic(code);
+                }
+            }
+        }
+
+        if data.len() < 4 {
+            return Self::Unknown(data.to_vec());
+        }
+        let (selector, args) = data.split_at(4);
+        for error in ipc_contract_abis().flat_map(Abi::errors) {
+            if error.signature()[..4] != *selector {
+                continue;
+            }
+            if let Ok(tokens) = error.decode(args) {
+                return Self::from_tokens(error, tokens);
+            }
+        }
+
+        Self::Unknown(data.to_vec())
+    }
+
+    fn from_tokens(error: &AbiError, tokens: Vec<Token>) -> Self {
+        let typed = match (error.name.as_str(), tokens.as_slice()) {
+            ("AlreadyRegisteredSubnet", []) => Some(Self::AlreadyRegisteredSubnet),
+            ("CannotReleaseZero", []) => Some(Self::CannotReleaseZero),
+            ("CannotSendCrossMsgToItself", []) => Some(Self::CannotSendCrossMsgToItself),
+            ("CollateralIsZero", []) => Some(Self::CollateralIsZero),
+            ("InvalidCheckpointEpoch", []) => Some(Self::InvalidCheckpointEpoch),
+            ("InvalidCollateral", []) => Some(Self::InvalidCollateral),
+            ("InvalidCrossMsgDstSubnet", []) => Some(Self::InvalidCrossMsgDstSubnet),
+            ("InvalidCrossMsgValue", []) => Some(Self::InvalidCrossMsgValue),
+            ("InvalidSignatureErr", [Token::Uint(code)]) => {
+                u8::try_from(*code).ok().map(Self::InvalidSignatureErr)
+            }
+            ("MethodNotAllowed", [Token::String(reason)]) => {
+                Some(Self::MethodNotAllowed(reason.clone()))
+            }
+            ("NotAuthorized", [Token::Address(addr)]) => ethers_address_to_fil_address(addr)
+                .ok()
+                .map(Self::NotAuthorized),
+            ("NotEnoughBalance", []) => Some(Self::NotEnoughBalance),
+            ("NotEnoughCollateral", []) => Some(Self::NotEnoughCollateral),
+            ("NotEnoughFee", []) => Some(Self::NotEnoughFee),
+            ("NotEnoughFunds", []) => Some(Self::NotEnoughFunds),
+            ("NotEnoughFundsToRelease", []) => Some(Self::NotEnoughFundsToRelease),
+            ("NotEnoughSubnetCircSupply", []) => Some(Self::NotEnoughSubnetCircSupply),
+            ("NotGateway", []) => Some(Self::NotGateway),
+            ("NotRegisteredSubnet", []) => Some(Self::NotRegisteredSubnet),
+            ("NotValidator", [Token::Address(addr)]) => ethers_address_to_fil_address(addr)
+                .ok()
+                .map(Self::NotValidator),
+            ("SubnetAlreadyKilled", []) => Some(Self::SubnetAlreadyKilled),
+            ("SubnetAlreadyBootstrapped", []) => Some(Self::SubnetAlreadyBootstrapped),
+            ("SubnetNotActive", []) => Some(Self::SubnetNotActive),
+            ("SubnetNotFound", []) => Some(Self::SubnetNotFound),
+            ("WithdrawExceedingCollateral", []) => Some(Self::WithdrawExceedingCollateral),
+            _ => None,
+        };
+
+        typed.unwrap_or_else(|| Self::Other {
+            name: error.name.clone(),
+            args: tokens.iter().map(Token::to_string).collect(),
+        })
+    }
+}
+
+/// The ABIs of the contracts the subnet manager interacts with.
+fn ipc_contract_abis() -> impl Iterator<Item = &'static Abi> {
+    [
+        &*gateway_getter_facet::GATEWAYGETTERFACET_ABI,
+        &*gateway_manager_facet::GATEWAYMANAGERFACET_ABI,
+        &*gateway_messenger_facet::GATEWAYMESSENGERFACET_ABI,
+        &*gateway_router_facet::GATEWAYROUTERFACET_ABI,
+        &*subnet_actor_getter_facet::SUBNETACTORGETTERFACET_ABI,
+        &*subnet_actor_manager_facet::SUBNETACTORMANAGERFACET_ABI,
+        &*subnet_registry::SUBNETREGISTRY_ABI,
+    ]
+    .into_iter()
+}
+
+/// A transaction that was sent to the network but not confirmed within the maximum wait
+/// time configured for the subnet. The transaction may still be included later, so callers
+/// can keep track of it with the hash.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("transaction {tx_hash:?} not confirmed after {}s, it may still be pending", .waited.as_secs())]
+pub struct TxTimeoutError {
+    pub tx_hash: H256,
+    pub waited: Duration,
+}
+
+/// A transaction that was included in a block but reverted. The reason is decoded by
+/// replaying the transaction at its block, if the node still serves the state of the block.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "transaction {tx_hash:?} reverted in block {block_number}{}",
+    .reason.as_ref().map(|r| format!(": {r}")).unwrap_or_default()
+)]
+pub struct TxRevertedError {
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub reason: Option<IpcContractError>,
+}
+
+/// Extracts the revert data from a middleware error, if the error is a revert.
+pub(crate) fn revert_data_from_middleware_error<E: MiddlewareError>(e: &E) -> Option<Vec<u8>> {
+    let response = e.as_error_response()?;
+    let data = response.as_revert_data()?;
+    Some(data.to_vec())
+}
+
+/// Converts the error of a contract call into an `anyhow::Error`, decoding reverts into
+/// an [`IpcContractError`] so that the actual cause is surfaced to the caller.
+pub(crate) fn contract_error<M: Middleware + 'static>(e: ContractError<M>) -> anyhow::Error {
+    let data = match &e {
+        ContractError::Revert(data) => Some(data.to_vec()),
+        ContractError::MiddlewareError { e } => revert_data_from_middleware_error(e),
+        ContractError::ProviderError { e } => revert_data_from_middleware_error(e),
+        _ => None,
+    };
+
+    match data {
+        Some(data) if !data.is_empty() => {
+            anyhow::Error::new(IpcContractError::from_revert_data(&data))
+        }
+        _ => anyhow::Error::new(e),
+    }
+}
+
+/// Same as [`contract_error`] for errors of raw transactions sent through a middleware.
+pub(crate) fn middleware_error<E: MiddlewareError + 'static>(e: E) -> anyhow::Error {
+    match revert_data_from_middleware_error(&e) {
+        Some(data) if !data.is_empty() => {
+            anyhow::Error::new(IpcContractError::from_revert_data(&data))
+        }
+        _ => anyhow::Error::new(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::abi::{encode, Token};
+    use ethers::types::U256;
+    use ethers::utils::id;
+
+    use super::IpcContractError;
+
+    #[test]
+    fn test_decode_custom_error() {
+        let data = id("NotEnoughCollateral()").to_vec();
+        assert_eq!(
+            IpcContractError::from_revert_data(&data),
+            IpcContractError::NotEnoughCollateral
+        );
+    }
+
+    #[test]
+    fn test_decode_revert_reason() {
+        let mut data = id("Error(string)").to_vec();
+        data.extend(encode(&[Token::String("not allowed".to_string())]));
+        assert_eq!(
+            IpcContractError::from_revert_data(&data).to_string(),
+            "reverted: not allowed"
+        );
+    }
+
+    #[test]
+    fn test_decode_panic_and_unknown() {
+        let mut data = id("Panic(uint256)").to_vec();
+        data.extend(encode(&[Token::Uint(U256::from(0x11))]));
+        assert_eq!(
+            IpcContractError::from_revert_data(&data),
+            IpcContractError::Panic(U256::from(0x11))
+        );
+
+        let data = vec![0xde, 0xad, 0xbe, 0xef];
+        assert_eq!(
+            IpcContractError::from_revert_data(&data),
+            IpcContractError::Unknown(data)
+        );
+    }
+}
@@ -0,0 +1,321 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Decoding of the logs emitted by the IPC contracts into [`IpcEvent`]s, with range queries
+//! and live tailing by polling `eth_getLogs`.
+
+use std::borrow::Borrow;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_channel::Sender;
+use ethers::contract::{ContractError, EthEvent, EthLogDecode, LogMeta};
+use ethers::providers::Middleware;
+use ethers::types::{Address, Filter, Log, H256};
+use futures_util::Stream;
+use fvm_shared::clock::ChainEpoch;
+use ipc_actors_abis::{gateway_router_facet, lib_staking_change_log, subnet_registry};
+use ipc_sdk::subnet_id::SubnetID;
+
+use crate::manager::evm::manager::{contract_address_from_subnet, ipc_event_from_log};
+use crate::manager::subnet::IpcEvent;
+
+/// The default number of blocks queried in a single `eth_getLogs` request.
+pub const DEFAULT_LOGS_PAGE_SIZE: u64 = 1000;
+
+/// An IPC event with the metadata of the log it was decoded from.
+#[derive(Debug, Clone)]
+pub struct IpcLog {
+    pub event: IpcEvent,
+    pub meta: LogMeta,
+}
+
+/// A stream of IPC events with their log metadata, either polled or subscribed to.
+pub type IpcLogStream = Pin<Box<dyn Stream<Item = Result<IpcLog>> + Send>>;
+
+/// Selects the IPC events to query or subscribe to.
+///
+/// The events are matched by emitter address and event signature independently, i.e. a
+/// filter for the `QuorumReached` events of the gateway and the `SubnetDeployed` events of
+/// the registry also matches any `SubnetDeployed` event emitted by the gateway. The IPC
+/// contracts do not emit events of each other, so this is harmless in practice.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IpcEventFilter {
+    addresses: Vec<Address>,
+    topics: Vec<H256>,
+}
+
+impl IpcEventFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The `QuorumReached` events of the gateway at `gateway_addr`.
+    pub fn quorum_reached(self, gateway_addr: Address) -> Self {
+        self.with(
+            gateway_addr,
+            gateway_router_facet::QuorumReachedFilter::signature(),
+        )
+    }
+
+    /// The `NewStakingChangeRequest` events of the subnet actor of `subnet_id`.
+    pub fn staking_changes(self, subnet_id: &SubnetID) -> Result<Self> {
+        Ok(self.with(
+            contract_address_from_subnet(subnet_id)?,
+            lib_staking_change_log::NewStakingChangeRequestFilter::signature(),
+        ))
+    }
+
+    /// The `SubnetDeployed` events of the registry at `registry_addr`.
+    pub fn subnet_deployed(self, registry_addr: Address) -> Self {
+        self.with(
+            registry_addr,
+            subnet_registry::SubnetDeployedFilter::signature(),
+        )
+    }
+
+    fn with(mut self, address: Address, topic: H256) -> Self {
+        if !self.addresses.contains(&address) {
+            self.addresses.push(address);
+        }
+        if !self.topics.contains(&topic) {
+            self.topics.push(topic);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// The `eth_getLogs` and `eth_subscribe` filter, without block range.
+    pub fn filter(&self) -> Filter {
+        Filter::new()
+            .address(self.addresses.clone())
+            .topic0(self.topics.clone())
+    }
+}
+
+/// Decodes the IPC events in `logs`, skipping the logs removed by a reorg, the pending ones
+/// and those that are not IPC events.
+pub fn decode_logs(logs: impl IntoIterator<Item = Log>) -> Result<Vec<IpcLog>> {
+    let mut events = vec![];
+    for log in logs {
+        if let Some(event) = decode_log(log)? {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// Decodes the IPC event in `log`, see [`decode_logs`].
+pub fn decode_log(log: Log) -> Result<Option<IpcLog>> {
+    if log.removed.unwrap_or_default() || log.block_number.is_none() || log.block_hash.is_none() {
+        return Ok(None);
+    }
+    let meta = LogMeta::from(&log);
+    Ok(ipc_event_from_log(log)?.map(|event| IpcLog { event, meta }))
+}
+
+/// Queries the IPC events matching a filter in a range of blocks, or tails them by polling
+/// the chain head.
+#[derive(Debug)]
+pub struct IpcEventQuery<M> {
+    client: Arc<M>,
+    filter: IpcEventFilter,
+    page_size: u64,
+    poll_interval: Duration,
+}
+
+impl<M: Middleware + 'static> IpcEventQuery<M> {
+    pub fn new(client: Arc<M>, filter: IpcEventFilter, poll_interval: Duration) -> Self {
+        Self {
+            client,
+            filter,
+            page_size: DEFAULT_LOGS_PAGE_SIZE,
+            poll_interval,
+        }
+    }
+
+    /// Sets the maximum number of blocks queried in a single `eth_getLogs` request, as
+    /// most endpoints limit the range of the queries.
+    pub fn with_page_size(mut self, page_size: u64) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Returns the events emitted between `from` and `to`, both inclusive, in the order
+    /// they were emitted.
+    pub async fn range(&self, from: ChainEpoch, to: ChainEpoch) -> Result<Vec<IpcLog>> {
+        if from < 0 || to < from {
+            return Err(anyhow!("invalid block range {from}..={to}"));
+        }
+        if self.filter.is_empty() {
+            return Err(anyhow!("no events selected in the filter"));
+        }
+
+        let filter = self.filter.filter();
+        let mut events = vec![];
+        let mut start = from as u64;
+        while start <= to as u64 {
+            let end = (start + self.page_size - 1).min(to as u64);
+            let logs = self
+                .client
+                .get_logs(&filter.clone().from_block(start).to_block(end))
+                .await
+                .map_err(|e| anyhow!("cannot get logs in {start}..={end}: {e}"))?;
+            events.extend(decode_logs(logs)?);
+            start = end + 1;
+        }
+        Ok(events)
+    }
+
+    /// Returns the events emitted from `from` onwards, followed by the new ones as the
+    /// chain advances. The query runs in the background until the stream is dropped,
+    /// errors are yielded in the stream and the failed range is retried in the next poll.
+    pub fn tail(self, from: ChainEpoch) -> IpcLogStream
+    where
+        M: Send + Sync,
+    {
+        let (tx, rx) = async_channel::bounded(DEFAULT_LOGS_PAGE_SIZE as usize);
+        tokio::spawn(async move { self.poll(from, tx).await });
+        Box::pin(rx)
+    }
+
+    async fn poll(self, mut next: ChainEpoch, tx: Sender<Result<IpcLog>>) {
+        while !tx.is_closed() {
+            match self.poll_once(next, &tx).await {
+                Ok(Some(height)) => next = height + 1,
+                Ok(None) => {}
+                Err(e) => {
+                    if tx.send(Err(e)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Sends the events from `next` up to the chain head, returns the last height sent.
+    async fn poll_once(
+        &self,
+        next: ChainEpoch,
+        tx: &Sender<Result<IpcLog>>,
+    ) -> Result<Option<ChainEpoch>> {
+        let head = self
+            .client
+            .get_block_number()
+            .await
+            .map_err(|e| anyhow!("cannot get block number: {e}"))?;
+        let head = head.as_u64() as ChainEpoch;
+        if head < next {
+            return Ok(None);
+        }
+
+        for event in self.range(next, head).await? {
+            if tx.send(Ok(event)).await.is_err() {
+                break;
+            }
+        }
+        Ok(Some(head))
+    }
+}
+
+/// This is a replacement for `Event::query_with_meta` in `ethers-contract`
+/// because in that one we don't get access to the `reverted` field, which
+/// we need to filteron in the currently deployed `1.25-rc4` version of Lotus.
+pub async fn query_with_meta<B, M, D>(
+    event: ethers::contract::Event<B, M, D>,
+    client: B,
+) -> Result<Vec<(D, LogMeta)>, ContractError<M>>
+where
+    B: Borrow<M>,
+    M: Middleware,
+    D: EthLogDecode,
+{
+    let logs = client
+        .borrow()
+        .get_logs(&event.filter)
+        .await
+        .map_err(ContractError::from_middleware_error)?;
+
+    let events = logs
+        .into_iter()
+        .filter(|l| !l.removed.unwrap_or_default())
+        .map(|log| {
+            let meta = LogMeta::from(&log);
+            let event = ethers::contract::parse_log::<D>(log)?;
+            Ok((event, meta))
+        })
+        .collect::<Result<_, ContractError<M>>>()?;
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::contract::EthEvent;
+    use ethers::types::{Address, Log, ValueOrArray, H256, U256, U64};
+    use ipc_actors_abis::{gateway_router_facet, subnet_registry};
+
+    use super::{decode_logs, IpcEventFilter};
+    use crate::manager::subnet::IpcEvent;
+
+    #[test]
+    fn test_event_filter() {
+        let gateway = Address::from_low_u64_be(1);
+        let registry = Address::from_low_u64_be(2);
+        let filter = IpcEventFilter::new()
+            .quorum_reached(gateway)
+            .subnet_deployed(registry)
+            .quorum_reached(gateway)
+            .filter();
+
+        assert_eq!(
+            filter.address,
+            Some(ValueOrArray::Array(vec![gateway, registry]))
+        );
+        assert_eq!(
+            filter.topics[0],
+            Some(ValueOrArray::Array(vec![
+                Some(gateway_router_facet::QuorumReachedFilter::signature()),
+                Some(subnet_registry::SubnetDeployedFilter::signature()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_decode_logs() {
+        let subnet_addr = Address::from_low_u64_be(100);
+        let deployed = Log {
+            address: Address::from_low_u64_be(2),
+            topics: vec![subnet_registry::SubnetDeployedFilter::signature()],
+            data: ethers::abi::encode(&[ethers::abi::Token::Address(subnet_addr)]).into(),
+            block_number: Some(U64::from(10)),
+            block_hash: Some(H256::repeat_byte(1)),
+            log_index: Some(U256::from(3)),
+            ..Default::default()
+        };
+        let pending = Log {
+            block_number: None,
+            block_hash: None,
+            ..deployed.clone()
+        };
+        let removed = Log {
+            removed: Some(true),
+            ..deployed.clone()
+        };
+        let unknown = Log {
+            topics: vec![H256::repeat_byte(2)],
+            ..deployed.clone()
+        };
+
+        let events = decode_logs(vec![deployed, pending, removed, unknown]).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].event, IpcEvent::SubnetDeployed { .. }));
+        assert_eq!(events[0].meta.block_number, U64::from(10));
+        assert_eq!(events[0].meta.log_index, U256::from(3));
+    }
+}
@@ -0,0 +1,524 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Gas pricing strategies for the transactions sent by the evm subnet manager.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::providers::{Middleware, Provider};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{BlockNumber, I256, U256};
+
+use super::transport::EthTransport;
+use crate::config::subnet::{GasConfig, GasStrategyConfig};
+
+/// The fees of an EIP-1559 transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasFees {
+    pub max_priority_fee_per_gas: U256,
+    pub max_fee_per_gas: U256,
+}
+
+impl GasFees {
+    /// The fees of a legacy transaction, which pays `gas_price` for every unit of gas.
+    pub fn legacy(gas_price: U256) -> Self {
+        Self {
+            max_priority_fee_per_gas: gas_price,
+            max_fee_per_gas: gas_price,
+        }
+    }
+
+    /// Sets the fees in the transaction, `max_fee_per_gas` is used as the gas price of
+    /// legacy transactions.
+    pub fn apply(&self, tx: &mut TypedTransaction) {
+        match tx.as_eip1559_mut() {
+            Some(tx) => {
+                tx.max_priority_fee_per_gas = Some(self.max_priority_fee_per_gas);
+                tx.max_fee_per_gas = Some(self.max_fee_per_gas);
+            }
+            None => {
+                tx.set_gas_price(self.max_fee_per_gas);
+            }
+        }
+    }
+}
+
+/// Computes the fees of the transactions sent to a subnet.
+#[async_trait]
+pub trait GasStrategy: Send + Sync {
+    /// Computes the fees of an EIP-1559 transaction.
+    async fn estimate_fees(&self, provider: &Provider<EthTransport>) -> Result<GasFees>;
+
+    /// Computes the gas price of a legacy transaction, for chains without EIP-1559.
+    /// Defaults to the gas price suggested by the node.
+    async fn estimate_gas_price(&self, provider: &Provider<EthTransport>) -> Result<U256> {
+        Ok(provider.get_gas_price().await?)
+    }
+}
+
+/// Returned by [`MaxFeeCeiling`] when the estimated max fee per gas is above the ceiling,
+/// so that the transaction is not sent.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("estimated max fee per gas {} exceeds the ceiling of {ceiling}", .fees.max_fee_per_gas)]
+pub struct GasCeilingExceeded {
+    /// The fees estimated by the strategy, which are above the ceiling.
+    pub fees: GasFees,
+    pub ceiling: U256,
+}
+
+/// Builds the strategy described by the subnet config.
+pub fn gas_strategy_from_config(config: &GasConfig) -> Arc<dyn GasStrategy> {
+    let mut strategy: Arc<dyn GasStrategy> = match &config.strategy {
+        GasStrategyConfig::Estimator => Arc::new(Eip1559Estimator),
+        GasStrategyConfig::Fixed {
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+        } => Arc::new(FixedGas(GasFees {
+            max_priority_fee_per_gas: U256::from(*max_priority_fee_per_gas),
+            max_fee_per_gas: U256::from(*max_fee_per_gas),
+        })),
+        GasStrategyConfig::FeeHistory { percentile, blocks } => Arc::new(FeeHistoryPercentile {
+            percentile: *percentile as f64,
+            blocks: *blocks,
+        }),
+        GasStrategyConfig::BaseFeeMultiplier {
+            multiplier_percent,
+            max_priority_fee_per_gas,
+        } => Arc::new(BaseFeeMultiplier {
+            multiplier_percent: *multiplier_percent,
+            max_priority_fee_per_gas: U256::from(*max_priority_fee_per_gas),
+        }),
+    };
+
+    if let Some(cap) = config.max_fee_per_gas_cap {
+        strategy = Arc::new(MaxFeeCap {
+            inner: strategy,
+            cap: U256::from(cap),
+        });
+    }
+    if let Some(ceiling) = config.max_fee_per_gas_ceiling {
+        strategy = Arc::new(MaxFeeCeiling {
+            inner: strategy,
+            ceiling: U256::from(ceiling),
+        });
+    }
+    strategy
+}
+
+/// The default strategy: an estimation of an optimal `gas_premium` and `gas_fee_cap`
+/// considering the average premium, base_fee and reward percentile from past blocks.
+/// This is adaptation of ethers' `eip1559_default_estimator`:
+/// https://github.com/gakonst/ethers-rs/blob/5dcd3b7e754174448f9a8cbfc0523896609629f9/ethers-core/src/utils/mod.rs#L476
+pub struct Eip1559Estimator;
+
+#[async_trait]
+impl GasStrategy for Eip1559Estimator {
+    async fn estimate_fees(&self, provider: &Provider<EthTransport>) -> Result<GasFees> {
+        let base_fee_per_gas = latest_base_fee(provider).await?;
+
+        let fee_history = provider
+            .fee_history(
+                ethers::utils::EIP1559_FEE_ESTIMATION_PAST_BLOCKS,
+                BlockNumber::Latest,
+                &[ethers::utils::EIP1559_FEE_ESTIMATION_REWARD_PERCENTILE],
+            )
+            .await?;
+
+        let max_priority_fee_per_gas = estimate_priority_fee(fee_history.reward); //overestimate?
+        let potential_max_fee = base_fee_surged(base_fee_per_gas);
+        let max_fee_per_gas = if max_priority_fee_per_gas > potential_max_fee {
+            max_priority_fee_per_gas + potential_max_fee
+        } else {
+            potential_max_fee
+        };
+
+        Ok(GasFees {
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+        })
+    }
+}
+
+/// Always uses the same fees.
+pub struct FixedGas(pub GasFees);
+
+#[async_trait]
+impl GasStrategy for FixedGas {
+    async fn estimate_fees(&self, _provider: &Provider<EthTransport>) -> Result<GasFees> {
+        Ok(self.0)
+    }
+
+    async fn estimate_gas_price(&self, _provider: &Provider<EthTransport>) -> Result<U256> {
+        Ok(self.0.max_fee_per_gas)
+    }
+}
+
+/// Uses the median of the given reward percentile over the last `blocks` blocks as the
+/// premium, on top of the surged base fee of the next block.
+///
+/// Without EIP-1559 the reward of a transaction is its whole gas price, so legacy
+/// transactions pay the median of the percentile of the gas prices instead.
+pub struct FeeHistoryPercentile {
+    pub percentile: f64,
+    pub blocks: u64,
+}
+
+impl FeeHistoryPercentile {
+    /// Returns the base fee of the next block, if any, and the median of the rewards.
+    async fn history(&self, provider: &Provider<EthTransport>) -> Result<(Option<U256>, U256)> {
+        let fee_history = provider
+            .fee_history(self.blocks, BlockNumber::Latest, &[self.percentile])
+            .await?;
+
+        // the last base fee of the history is the one of the next block
+        let base_fee_per_gas = fee_history.base_fee_per_gas.last().cloned();
+
+        let mut rewards = fee_history
+            .reward
+            .iter()
+            .filter_map(|r| r.first().cloned())
+            .collect::<Vec<_>>();
+        rewards.sort();
+        let reward = rewards.get(rewards.len() / 2).cloned().unwrap_or_default();
+
+        Ok((base_fee_per_gas, reward))
+    }
+}
+
+#[async_trait]
+impl GasStrategy for FeeHistoryPercentile {
+    async fn estimate_fees(&self, provider: &Provider<EthTransport>) -> Result<GasFees> {
+        let (base_fee_per_gas, max_priority_fee_per_gas) = self.history(provider).await?;
+        let base_fee_per_gas = base_fee_per_gas.ok_or_else(|| anyhow!("EIP-1559 not activated"))?;
+
+        Ok(GasFees {
+            max_priority_fee_per_gas,
+            max_fee_per_gas: base_fee_surged(base_fee_per_gas) + max_priority_fee_per_gas,
+        })
+    }
+
+    async fn estimate_gas_price(&self, provider: &Provider<EthTransport>) -> Result<U256> {
+        let (base_fee_per_gas, reward) = self.history(provider).await?;
+        // blocks without transactions have no reward, defer to the node then.
+        if reward.is_zero() {
+            return Ok(provider.get_gas_price().await?);
+        }
+        Ok(base_fee_per_gas.unwrap_or_default() + reward)
+    }
+}
+
+/// Sets the max fee to a multiple of the current base fee plus a fixed premium. Without
+/// EIP-1559 the gas price suggested by the node stands in for the base fee.
+pub struct BaseFeeMultiplier {
+    /// The multiplier as a percentage, i.e. `150` for `1.5x` the base fee.
+    pub multiplier_percent: u64,
+    pub max_priority_fee_per_gas: U256,
+}
+
+#[async_trait]
+impl GasStrategy for BaseFeeMultiplier {
+    async fn estimate_fees(&self, provider: &Provider<EthTransport>) -> Result<GasFees> {
+        let base_fee_per_gas = latest_base_fee(provider).await?;
+        Ok(GasFees {
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+            max_fee_per_gas: base_fee_per_gas * self.multiplier_percent / 100
+                + self.max_priority_fee_per_gas,
+        })
+    }
+
+    async fn estimate_gas_price(&self, provider: &Provider<EthTransport>) -> Result<U256> {
+        let gas_price = provider.get_gas_price().await?;
+        Ok(gas_price * self.multiplier_percent / 100 + self.max_priority_fee_per_gas)
+    }
+}
+
+/// Caps the max fee computed by another strategy. The premium is capped as well, as it can
+/// never be above the max fee.
+pub struct MaxFeeCap {
+    pub inner: Arc<dyn GasStrategy>,
+    pub cap: U256,
+}
+
+#[async_trait]
+impl GasStrategy for MaxFeeCap {
+    async fn estimate_fees(&self, provider: &Provider<EthTransport>) -> Result<GasFees> {
+        let fees = self.inner.estimate_fees(provider).await?;
+        Ok(GasFees {
+            max_priority_fee_per_gas: fees.max_priority_fee_per_gas.min(self.cap),
+            max_fee_per_gas: fees.max_fee_per_gas.min(self.cap),
+        })
+    }
+
+    async fn estimate_gas_price(&self, provider: &Provider<EthTransport>) -> Result<U256> {
+        let gas_price = self.inner.estimate_gas_price(provider).await?;
+        Ok(gas_price.min(self.cap))
+    }
+}
+
+/// Fails with [`GasCeilingExceeded`] if the max fee computed by another strategy is
+/// above the ceiling, to avoid sending transactions when fees spike.
+pub struct MaxFeeCeiling {
+    pub inner: Arc<dyn GasStrategy>,
+    pub ceiling: U256,
+}
+
+impl MaxFeeCeiling {
+    fn check(&self, fees: GasFees) -> Result<()> {
+        if fees.max_fee_per_gas > self.ceiling {
+            return Err(anyhow::Error::new(GasCeilingExceeded {
+                fees,
+                ceiling: self.ceiling,
+            }));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GasStrategy for MaxFeeCeiling {
+    async fn estimate_fees(&self, provider: &Provider<EthTransport>) -> Result<GasFees> {
+        let fees = self.inner.estimate_fees(provider).await?;
+        self.check(fees)?;
+        Ok(fees)
+    }
+
+    async fn estimate_gas_price(&self, provider: &Provider<EthTransport>) -> Result<U256> {
+        let gas_price = self.inner.estimate_gas_price(provider).await?;
+        self.check(GasFees::legacy(gas_price))?;
+        Ok(gas_price)
+    }
+}
+
+/// Whether the chain has activated EIP-1559, i.e. its blocks have a base fee.
+pub async fn supports_eip1559(provider: &Provider<EthTransport>) -> Result<bool> {
+    let block = provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| anyhow!("Latest block not found"))?;
+    Ok(block.base_fee_per_gas.is_some())
+}
+
+/// Converts `tx` into a legacy transaction, for chains without EIP-1559.
+pub fn into_legacy(tx: TypedTransaction) -> TypedTransaction {
+    match tx {
+        TypedTransaction::Eip1559(tx) => TypedTransaction::Legacy(tx.into()),
+        tx => tx,
+    }
+}
+
+/// Returns the base fee of the latest block.
+async fn latest_base_fee(provider: &Provider<EthTransport>) -> Result<U256> {
+    provider
+        .get_block(BlockNumber::Latest)
+        .await?
+        .ok_or_else(|| anyhow!("Latest block not found"))?
+        .base_fee_per_gas
+        .ok_or_else(|| anyhow!("EIP-1559 not activated"))
+}
+
+/// Implementation borrowed from
+/// https://github.com/gakonst/ethers-rs/blob/ethers-v2.0.8/ethers-core/src/utils/mod.rs#L582
+/// Refer to the implementation for unit tests
+fn base_fee_surged(base_fee_per_gas: U256) -> U256 {
+    if base_fee_per_gas <= U256::from(40_000_000_000u64) {
+        base_fee_per_gas * 2
+    } else if base_fee_per_gas <= U256::from(100_000_000_000u64) {
+        base_fee_per_gas * 16 / 10
+    } else if base_fee_per_gas <= U256::from(200_000_000_000u64) {
+        base_fee_per_gas * 14 / 10
+    } else {
+        base_fee_per_gas * 12 / 10
+    }
+}
+
+/// Implementation borrowed from
+/// https://github.com/gakonst/ethers-rs/blob/ethers-v2.0.8/ethers-core/src/utils/mod.rs#L536
+/// Refer to the implementation for unit tests
+fn estimate_priority_fee(rewards: Vec<Vec<U256>>) -> U256 {
+    let mut rewards: Vec<U256> = rewards
+        .iter()
+        .map(|r| r[0])
+        .filter(|r| *r > U256::zero())
+        .collect();
+    if rewards.is_empty() {
+        return U256::zero();
+    }
+    if rewards.len() == 1 {
+        return rewards[0];
+    }
+    // Sort the rewards as we will eventually take the median.
+    rewards.sort();
+
+    // A copy of the same vector is created for convenience to calculate percentage change
+    // between subsequent fee values.
+    let mut rewards_copy = rewards.clone();
+    rewards_copy.rotate_left(1);
+
+    let mut percentage_change: Vec<I256> = rewards
+        .iter()
+        .zip(rewards_copy.iter())
+        .map(|(a, b)| {
+            let a = I256::try_from(*a).expect("priority fee overflow");
+            let b = I256::try_from(*b).expect("priority fee overflow");
+            ((b - a) * 100) / a
+        })
+        .collect();
+    percentage_change.pop();
+
+    // Fetch the max of the percentage change, and that element's index.
+    let max_change = percentage_change.iter().max().unwrap();
+    let max_change_index = percentage_change
+        .iter()
+        .position(|&c| c == *max_change)
+        .unwrap();
+
+    // If we encountered a big change in fees at a certain position, then consider only
+    // the values >= it.
+    let values = if *max_change >= ethers::utils::EIP1559_FEE_ESTIMATION_THRESHOLD_MAX_CHANGE.into()
+        && (max_change_index >= (rewards.len() / 2))
+    {
+        rewards[max_change_index..].to_vec()
+    } else {
+        rewards
+    };
+
+    // Return the median.
+    values[values.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use ethers::providers::{Http, Provider};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, Eip1559TransactionRequest, U256};
+    use serde_json::json;
+    use url::Url;
+
+    use super::super::transport::{EthTransport, FailoverTransport, RetryTransport};
+    use super::{
+        into_legacy, BaseFeeMultiplier, FeeHistoryPercentile, FixedGas, GasCeilingExceeded,
+        GasFees, GasStrategy, MaxFeeCap, MaxFeeCeiling,
+    };
+    use crate::retry::RetryPolicy;
+    use crate::testing::rpc_endpoint;
+
+    /// A provider that is never queried by the strategies under test.
+    fn unreachable_provider() -> Provider<EthTransport> {
+        let http = Http::new(Url::parse("http://127.0.0.1:1").unwrap());
+        let transport = FailoverTransport::new(vec![http], Duration::from_secs(1)).unwrap();
+        Provider::new(RetryTransport::new(transport, RetryPolicy::no_retries()))
+    }
+
+    /// A provider of a chain without EIP-1559, whose recent transactions paid a gas price
+    /// of 30 and whose node suggests a gas price of 20.
+    async fn legacy_provider() -> Provider<EthTransport> {
+        let url = rpc_endpoint(|method, _| match method {
+            "eth_gasPrice" => json!({"result": "0x14"}),
+            "eth_feeHistory" => json!({"result": {
+                "oldestBlock": "0x1",
+                "baseFeePerGas": ["0x0", "0x0", "0x0"],
+                "gasUsedRatio": [0.5, 0.5],
+                "reward": [["0x1e"], ["0x1e"]]
+            }}),
+            _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+        })
+        .await;
+        let transport =
+            FailoverTransport::new(vec![Http::new(url)], Duration::from_secs(5)).unwrap();
+        Provider::new(RetryTransport::new(transport, RetryPolicy::no_retries()))
+    }
+
+    fn fixed(prio: u64, max: u64) -> Arc<dyn GasStrategy> {
+        Arc::new(FixedGas(GasFees {
+            max_priority_fee_per_gas: U256::from(prio),
+            max_fee_per_gas: U256::from(max),
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_max_fee_cap_and_ceiling() {
+        let provider = unreachable_provider();
+
+        let capped = MaxFeeCap {
+            inner: fixed(200, 1_000),
+            cap: U256::from(100),
+        };
+        let fees = capped.estimate_fees(&provider).await.unwrap();
+        assert_eq!(fees.max_fee_per_gas, U256::from(100));
+        assert_eq!(fees.max_priority_fee_per_gas, U256::from(100));
+
+        let guarded = MaxFeeCeiling {
+            inner: fixed(10, 1_000),
+            ceiling: U256::from(500),
+        };
+        let err = guarded.estimate_fees(&provider).await.unwrap_err();
+        assert!(err.downcast_ref::<GasCeilingExceeded>().is_some());
+
+        let guarded = MaxFeeCeiling {
+            inner: fixed(10, 400),
+            ceiling: U256::from(500),
+        };
+        assert!(guarded.estimate_fees(&provider).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_gas_price() {
+        let provider = unreachable_provider();
+
+        let capped = MaxFeeCap {
+            inner: fixed(10, 1_000),
+            cap: U256::from(100),
+        };
+        assert_eq!(
+            capped.estimate_gas_price(&provider).await.unwrap(),
+            U256::from(100)
+        );
+
+        let guarded = MaxFeeCeiling {
+            inner: fixed(10, 1_000),
+            ceiling: U256::from(500),
+        };
+        assert!(guarded.estimate_gas_price(&provider).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_legacy_gas_price_of_configured_strategies() {
+        let provider = legacy_provider().await;
+
+        let fee_history = FeeHistoryPercentile {
+            percentile: 50.0,
+            blocks: 2,
+        };
+        assert_eq!(
+            fee_history.estimate_gas_price(&provider).await.unwrap(),
+            U256::from(30)
+        );
+
+        let multiplier = BaseFeeMultiplier {
+            multiplier_percent: 150,
+            max_priority_fee_per_gas: U256::from(5),
+        };
+        assert_eq!(
+            multiplier.estimate_gas_price(&provider).await.unwrap(),
+            U256::from(35)
+        );
+    }
+
+    #[test]
+    fn test_into_legacy() {
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .value(U256::from(10))
+            .into();
+        let mut tx = into_legacy(tx);
+        GasFees::legacy(U256::from(50)).apply(&mut tx);
+
+        let legacy = tx.as_legacy_ref().expect("legacy transaction");
+        assert_eq!(legacy.gas_price, Some(U256::from(50)));
+        assert_eq!(legacy.value, Some(U256::from(10)));
+    }
+}
@@ -1,13 +1,11 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 
-use std::borrow::Borrow;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use ethers::types::H256;
-use ethers_contract::{ContractError, EthLogDecode, LogMeta};
 use ipc_actors_abis::{
     gateway_getter_facet, gateway_manager_facet, gateway_messenger_facet, gateway_router_facet,
     lib_staking_change_log, subnet_actor_getter_facet, subnet_actor_manager_facet, subnet_registry,
@@ -18,48 +16,65 @@ use std::net::{IpAddr, SocketAddr};
 
 use ipc_sdk::{eth_to_fil_amount, ethers_address_to_fil_address};
 
-use crate::config::subnet::SubnetConfig;
-use crate::config::Subnet;
+use crate::config::subnet::{
+    SubnetConfig, TxType, DEFAULT_MAX_WAIT_SECS, DEFAULT_REQUIRED_CONFIRMATIONS,
+};
+use crate::config::{SignerConfig, Subnet};
 use crate::lotus::message::ipc::SubnetInfo;
+use crate::manager::evm::dry_run::simulate_transaction;
+use crate::manager::evm::error::{
+    contract_error, middleware_error, revert_data_from_middleware_error, IpcContractError,
+    TxRevertedError, TxTimeoutError,
+};
+use crate::manager::evm::events::{IpcEventFilter, IpcEventQuery, IpcLog};
+use crate::manager::evm::gas::{
+    gas_strategy_from_config, into_legacy, supports_eip1559, Eip1559Estimator, GasCeilingExceeded,
+    GasFees, GasStrategy,
+};
+use crate::manager::evm::nonce::NonceManager;
+use crate::manager::evm::offline::{SignedTransaction, UnsignedTransaction};
+use crate::manager::evm::signer::{EthSigner, TransactionSigner, Web3Signer};
+use crate::manager::evm::transport::{
+    EthTransport, FailoverTransport, RetryTransport, DEFAULT_HEALTH_CHECK_INTERVAL,
+};
 use crate::manager::subnet::{
-    BottomUpCheckpointRelayer, GetBlockHashResult, SubnetGenesisInfo, TopDownFinalityQuery,
-    TopDownQueryPayload,
+    chained_blocks, BottomUpCheckpointRelayer, CheckpointedBottomUpMsg, CommittedTopDownMsg,
+    GetBlockHashResult, IpcEvent, NullRoundError, SubnetDetails, SubnetGenesisInfo,
+    TopDownFinalityQuery, TopDownMsgs, TopDownQueryPayload, TxOutcome, TxResult, ValidatorChanges,
 };
 use crate::manager::{EthManager, SubnetManager};
+use crate::retry::subnet_retry_policy;
 use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use ethers::abi::Tokenizable;
-use ethers::prelude::k256::ecdsa::SigningKey;
+use ethers::abi::{AbiDecode, Tokenizable};
 use ethers::prelude::{Signer, SignerMiddleware};
-use ethers::providers::{Authorization, Http, Middleware, Provider};
-use ethers::signers::{LocalWallet, Wallet};
-use ethers::types::{BlockId, Eip1559TransactionRequest, I256, U256};
+use ethers::providers::{
+    Authorization, Http, Middleware, PendingTransaction, Provider, ProviderError, RpcError,
+};
+use ethers::signers::LocalWallet;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{
+    BlockId, BlockNumber, Eip1559TransactionRequest, TransactionReceipt, U256, U64,
+};
+use futures_util::{stream, StreamExt, TryStreamExt};
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
 use ipc_identity::{EthKeyAddress, EvmKeyStore, PersistentKeyStore};
 use ipc_sdk::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle, QuorumReachedEvent};
 use ipc_sdk::cross::CrossMsg;
 use ipc_sdk::gateway::Status;
-use ipc_sdk::staking::{StakingChangeRequest, ValidatorInfo, ValidatorStakingInfo};
+use ipc_sdk::staking::{
+    StakingChangeRequest, SubnetValidator, ValidatorInfo, ValidatorStakingInfo,
+};
 use ipc_sdk::subnet::ConstructParams;
 use ipc_sdk::subnet_id::SubnetID;
-use num_traits::ToPrimitive;
+use num_traits::{ToPrimitive, Zero};
 use std::result;
 
-pub type DefaultSignerMiddleware = SignerMiddleware<Provider<Http>, Wallet<SigningKey>>;
-
-/// Default polling time used by the Ethers provider to check for pending
-/// transactions and events. Default is 7, and for our child subnets we
-/// can reduce it to the block time (or potentially less)
-const ETH_PROVIDER_POLLING_TIME: Duration = Duration::from_secs(1);
-/// Maximum number of retries to fetch a transaction receipt.
-/// The number of retries should ensure that for the block time
-/// of the network the number of retires considering the polling
-/// time above waits enough tie to get the transaction receipt.
-/// We currently support a low polling time and high number of
-/// retries so these numbers accommodate fast subnets with slow
-/// roots (like Calibration and mainnet).
-const TRANSACTION_RECEIPT_RETRIES: usize = 200;
+pub type DefaultSignerMiddleware = SignerMiddleware<Provider<EthTransport>, EthSigner>;
+
+/// The maximum number of requests sent concurrently by the queries over a range of heights.
+const MAX_CONCURRENT_QUERIES: usize = 10;
 
 /// The majority vote percentage for checkpoint submission when creating a subnet.
 const SUBNET_MAJORITY_PERCENTAGE: u8 = 60;
@@ -67,6 +82,26 @@ const SUBNET_MAJORITY_PERCENTAGE: u8 = 60;
 pub struct EthSubnetManager {
     keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
     ipc_contract_info: IPCContractInfo,
+    /// When set, state-changing operations are simulated instead of sent, see
+    /// `TxResult::Simulated`.
+    dry_run: bool,
+    /// When set, state-changing operations are built but not signed, see
+    /// `TxResult::Unsigned`.
+    offline_signing: bool,
+    /// The signers of the addresses that are not signed with the keystore.
+    signers: Vec<SignerConfig>,
+    nonces: NonceManager,
+    /// Prices the transactions sent by the manager.
+    gas_strategy: Arc<dyn GasStrategy>,
+    /// The type of the transactions sent by the manager, see `tx_type`.
+    tx_type: TxType,
+    /// The type of transactions supported by the chain, detected on the first transaction
+    /// when `tx_type` is `TxType::Auto`.
+    detected_tx_type: tokio::sync::OnceCell<TxType>,
+    /// The number of confirmations to wait for after sending a transaction.
+    required_confirmations: usize,
+    /// The maximum time to wait for a transaction to be confirmed.
+    max_wait: Duration,
 }
 
 /// Keep track of the on chain information for the subnet manager
@@ -74,7 +109,7 @@ struct IPCContractInfo {
     gateway_addr: ethers::types::Address,
     registry_addr: ethers::types::Address,
     chain_id: u64,
-    provider: Provider<Http>,
+    provider: Provider<EthTransport>,
 }
 
 #[async_trait]
@@ -89,7 +124,11 @@ impl TopDownFinalityQuery for EthSubnetManager {
             self.ipc_contract_info.gateway_addr,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        let (exists, subnet) = contract.get_subnet(evm_subnet_id).call().await?;
+        let (exists, subnet) = contract
+            .get_subnet(evm_subnet_id)
+            .call()
+            .await
+            .map_err(contract_error)?;
         if !exists {
             return Err(anyhow!("subnet: {} does not exists", subnet_id));
         }
@@ -116,40 +155,30 @@ impl TopDownFinalityQuery for EthSubnetManager {
             return Err(anyhow!("invalid block hash len"));
         }
 
-        let route = subnet_id_to_evm_addresses(subnet_id)?;
-        log::debug!("getting top down messages for route: {route:?}");
-
-        let subnet_id = gateway_getter_facet::SubnetID {
-            root: subnet_id.root_id(),
-            route,
-        };
-        let gateway_contract = gateway_getter_facet::GatewayGetterFacet::new(
+        top_down_msgs_at(
             self.ipc_contract_info.gateway_addr,
             Arc::new(self.ipc_contract_info.provider.clone()),
-        );
-
-        let call = gateway_contract
-            .get_top_down_msgs(subnet_id, U256::from(epoch))
-            .block(BlockId::from(H256::from_slice(block_hash)));
-        let raw_msgs = call
-            .call()
-            .await
-            .map_err(|e| anyhow!("cannot get evm top down messages: {e:}"))?;
-
-        let mut msgs = vec![];
-        for c in raw_msgs {
-            msgs.push(ipc_sdk::cross::CrossMsg::try_from(c)?);
-        }
-        Ok(msgs)
+            subnet_id,
+            epoch,
+            H256::from_slice(block_hash),
+        )
+        .await
     }
 
     async fn get_block_hash(&self, height: ChainEpoch) -> Result<GetBlockHashResult> {
-        let block = self
+        let block = match self
             .ipc_contract_info
             .provider
             .get_block(height as u64)
-            .await?
-            .ok_or_else(|| anyhow!("height does not exist"))?;
+            .await
+        {
+            Ok(block) => block.ok_or_else(|| anyhow!("height does not exist"))?,
+            // Lotus answers the query of a null round with an error rather than no block.
+            Err(e) if is_null_round_error(&e) => {
+                return Err(anyhow::Error::new(NullRoundError { height }))
+            }
+            Err(e) => return Err(e.into()),
+        };
 
         Ok(GetBlockHashResult {
             parent_block_hash: block.parent_hash.to_fixed_bytes().to_vec(),
@@ -169,19 +198,14 @@ impl TopDownFinalityQuery for EthSubnetManager {
         let address = contract_address_from_subnet(subnet_id)?;
         log::info!("querying validator changes in evm subnet contract: {address:}");
 
-        let contract = subnet_actor_manager_facet::SubnetActorManagerFacet::new(
-            address,
-            Arc::new(self.ipc_contract_info.provider.clone()),
-        );
-
-        let ev = contract
-            .event::<lib_staking_change_log::NewStakingChangeRequestFilter>()
-            .from_block(epoch as u64)
-            .to_block(epoch as u64);
+        let query = self.events(IpcEventFilter::new().staking_changes(subnet_id)?);
 
         let mut changes = vec![];
         let mut hash = None;
-        for (event, meta) in query_with_meta(ev, contract.client()).await? {
+        for IpcLog { event, meta } in query.range(epoch, epoch).await? {
+            let IpcEvent::NewStakingChangeRequest(change) = event else {
+                continue;
+            };
             if let Some(h) = hash {
                 if h != meta.block_hash {
                     return Err(anyhow!("block hash not equal"));
@@ -189,7 +213,7 @@ impl TopDownFinalityQuery for EthSubnetManager {
             } else {
                 hash = Some(meta.block_hash);
             }
-            changes.push(StakingChangeRequest::try_from(event)?);
+            changes.push(change);
         }
 
         let block_hash = if let Some(h) = hash {
@@ -203,6 +227,91 @@ impl TopDownFinalityQuery for EthSubnetManager {
         })
     }
 
+    async fn get_top_down_msgs_range(
+        &self,
+        subnet_id: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<TopDownMsgs>> {
+        if from < 0 || to < from {
+            return Err(anyhow!("invalid height range {from}..={to}"));
+        }
+
+        // the block hashes are fetched concurrently, and must chain before the messages
+        // are queried at them.
+        let hashes = stream::iter(from..=to)
+            .map(|height| async move { (height, self.get_block_hash(height).await) })
+            .buffered(MAX_CONCURRENT_QUERIES)
+            .collect::<Vec<_>>()
+            .await;
+        let blocks = chained_blocks(hashes)?;
+
+        let msgs = stream::iter(blocks)
+            .map(|(height, hash)| async move {
+                let msgs = self
+                    .get_top_down_msgs(subnet_id, height, &hash.block_hash)
+                    .await?;
+                Ok::<_, anyhow::Error>(TopDownMsgs {
+                    height,
+                    block_hash: hash.block_hash,
+                    msgs,
+                })
+            })
+            .buffered(MAX_CONCURRENT_QUERIES)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(msgs.into_iter().filter(|m| !m.msgs.is_empty()).collect())
+    }
+
+    async fn get_validator_changeset_range(
+        &self,
+        subnet_id: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<ValidatorChanges>> {
+        let address = contract_address_from_subnet(subnet_id)?;
+        log::info!(
+            "querying validator changes in {from}..={to} in evm subnet contract: {address:}"
+        );
+
+        // the logs of the whole range are fetched in pages instead of height by height.
+        let query = self.events(IpcEventFilter::new().staking_changes(subnet_id)?);
+
+        let mut result: Vec<ValidatorChanges> = vec![];
+        for IpcLog { event, meta } in query.range(from, to).await? {
+            let IpcEvent::NewStakingChangeRequest(change) = event else {
+                continue;
+            };
+            let height = meta.block_number.as_u64() as ChainEpoch;
+            let block_hash = meta.block_hash.0.to_vec();
+            match result.last_mut() {
+                Some(changes) if changes.height == height => {
+                    if changes.block_hash != block_hash {
+                        return Err(anyhow!("block hash not equal at {height}"));
+                    }
+                    changes.changes.push(change);
+                }
+                _ => result.push(ValidatorChanges {
+                    height,
+                    block_hash,
+                    changes: vec![change],
+                }),
+            }
+        }
+
+        // the pages are fetched at different times, a block may have been reorganized since.
+        for changes in &result {
+            let hash = self.get_block_hash(changes.height).await?;
+            if hash.block_hash != changes.block_hash {
+                return Err(anyhow!(
+                    "block at {} changed while querying, chain reorg?",
+                    changes.height
+                ));
+            }
+        }
+        Ok(result)
+    }
+
     async fn latest_parent_finality(&self) -> Result<ChainEpoch> {
         log::info!("querying latest parent finality ");
 
@@ -210,14 +319,34 @@ impl TopDownFinalityQuery for EthSubnetManager {
             self.ipc_contract_info.gateway_addr,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        let finality = contract.get_latest_parent_finality().call().await?;
+        let finality = contract
+            .get_latest_parent_finality()
+            .call()
+            .await
+            .map_err(contract_error)?;
         Ok(finality.height.as_u64() as ChainEpoch)
     }
+
+    async fn applied_top_down_nonce(&self) -> Result<u64> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        Ok(contract
+            .applied_top_down_nonce()
+            .call()
+            .await
+            .map_err(contract_error)?)
+    }
 }
 
 #[async_trait]
 impl SubnetManager for EthSubnetManager {
-    async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address> {
+    async fn create_subnet(
+        &self,
+        from: Address,
+        params: ConstructParams,
+    ) -> Result<TxResult<(Address, TxOutcome)>> {
         self.ensure_same_gateway(&params.ipc_gateway_addr)?;
 
         let min_validator_stake = params
@@ -262,37 +391,16 @@ impl SubnetManager for EthSubnetManager {
             signer.clone(),
         );
 
-        let call = call_with_premium_estimation(signer, registry_contract.new_subnet_actor(params))
-            .await?;
-        // TODO: Edit call to get estimate premium
-        let pending_tx = call.send().await?;
-        // We need the retry to parse the deployment event. At the time of this writing, it's a bug
-        // in current FEVM that without the retries, events are not picked up.
+        let call = registry_contract.new_subnet_actor(params);
+        // We need to wait for the receipt to parse the deployment event. At the time of this writing,
+        // it's a bug in current FEVM that without the retries, events are not picked up.
         // See https://github.com/filecoin-project/community/discussions/638 for more info and updates.
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        match receipt {
-            Some(r) => {
-                for log in r.logs {
-                    log::debug!("log: {log:?}");
-
-                    match ethers_contract::parse_log::<subnet_registry::SubnetDeployedFilter>(log) {
-                        Ok(subnet_deploy) => {
-                            let subnet_registry::SubnetDeployedFilter { subnet_addr } =
-                                subnet_deploy;
-
-                            log::debug!("subnet deployed at {subnet_addr:?}");
-                            return ethers_address_to_fil_address(&subnet_addr);
-                        }
-                        Err(_) => {
-                            log::debug!("no event for subnet actor published yet, continue");
-                            continue;
-                        }
-                    }
-                }
-                Err(anyhow!("no logs receipt"))
-            }
-            None => Err(anyhow!("no receipt for event, txn not successful")),
-        }
+        let result = self.send_call(signer, call).await?;
+        result.try_map(|outcome| {
+            let subnet_addr = outcome.deployed_subnet()?;
+            log::debug!("subnet deployed at {subnet_addr:?}");
+            Ok((subnet_addr, outcome))
+        })
     }
 
     async fn join_subnet(
@@ -301,7 +409,7 @@ impl SubnetManager for EthSubnetManager {
         from: Address,
         collateral: TokenAmount,
         pub_key: Vec<u8>,
-    ) -> Result<ChainEpoch> {
+    ) -> Result<TxResult> {
         let collateral = collateral
             .atto()
             .to_u128()
@@ -318,14 +426,15 @@ impl SubnetManager for EthSubnetManager {
 
         let mut txn = contract.join(ethers::types::Bytes::from(pub_key));
         txn.tx.set_value(collateral);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        self.send_call(signer, txn).await
     }
 
-    async fn pre_fund(&self, subnet: SubnetID, from: Address, balance: TokenAmount) -> Result<()> {
+    async fn pre_fund(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        balance: TokenAmount,
+    ) -> Result<TxResult> {
         let balance = balance
             .atto()
             .to_u128()
@@ -340,10 +449,7 @@ impl SubnetManager for EthSubnetManager {
 
         let mut txn = contract.pre_fund();
         txn.tx.set_value(balance);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-
-        txn.send().await?;
-        Ok(())
+        self.send_call(signer, txn).await
     }
 
     async fn pre_release(
@@ -351,7 +457,7 @@ impl SubnetManager for EthSubnetManager {
         subnet: SubnetID,
         from: Address,
         amount: TokenAmount,
-    ) -> Result<()> {
+    ) -> Result<TxResult> {
         let address = contract_address_from_subnet(&subnet)?;
         log::info!("pre-release funds from {subnet:} at contract: {address:}");
 
@@ -364,16 +470,16 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.pre_release(amount.into()))
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
+        let txn = contract.pre_release(amount.into());
+        self.send_call(signer, txn).await
     }
 
-    async fn stake(&self, subnet: SubnetID, from: Address, collateral: TokenAmount) -> Result<()> {
+    async fn stake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<TxResult> {
         let collateral = collateral
             .atto()
             .to_u128()
@@ -390,11 +496,7 @@ impl SubnetManager for EthSubnetManager {
 
         let mut txn = contract.stake();
         txn.tx.set_value(collateral);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-
-        txn.send().await?.await?;
-
-        Ok(())
+        self.send_call(signer, txn).await
     }
 
     async fn unstake(
@@ -402,7 +504,7 @@ impl SubnetManager for EthSubnetManager {
         subnet: SubnetID,
         from: Address,
         collateral: TokenAmount,
-    ) -> Result<()> {
+    ) -> Result<TxResult> {
         let collateral = collateral
             .atto()
             .to_u128()
@@ -417,13 +519,11 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        let txn = call_with_premium_estimation(signer, contract.unstake(collateral.into())).await?;
-        txn.send().await?.await?;
-
-        Ok(())
+        let txn = contract.unstake(collateral.into());
+        self.send_call(signer, txn).await
     }
 
-    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
         let address = contract_address_from_subnet(&subnet)?;
         log::info!("leaving evm subnet: {subnet:} at contract: {address:}");
 
@@ -431,16 +531,11 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.leave())
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
+        let txn = contract.leave();
+        self.send_call(signer, txn).await
     }
 
-    async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<()> {
+    async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
         let address = contract_address_from_subnet(&subnet)?;
         log::info!("kill evm subnet: {subnet:} at contract: {address:}");
 
@@ -448,13 +543,8 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.kill())
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
+        let txn = contract.kill();
+        self.send_call(signer, txn).await
     }
 
     async fn list_child_subnets(
@@ -470,7 +560,11 @@ impl SubnetManager for EthSubnetManager {
 
         let mut s = HashMap::new();
 
-        let evm_subnets = gateway_contract.list_subnets().call().await?;
+        let evm_subnets = gateway_contract
+            .list_subnets()
+            .call()
+            .await
+            .map_err(contract_error)?;
         log::debug!("raw subnet: {evm_subnets:?}");
 
         for subnet in evm_subnets {
@@ -481,7 +575,7 @@ impl SubnetManager for EthSubnetManager {
         Ok(s)
     }
 
-    async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<()> {
+    async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
         let address = contract_address_from_subnet(&subnet)?;
         log::info!("claim collateral evm subnet: {subnet:} at contract: {address:}");
 
@@ -489,16 +583,11 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.claim())
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
+        let txn = contract.claim();
+        self.send_call(signer, txn).await
     }
 
-    async fn claim_relayer_reward(&self, subnet: SubnetID, from: Address) -> Result<()> {
+    async fn claim_relayer_reward(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
         let address = contract_address_from_subnet(&subnet)?;
         log::info!("claim relayer reward evm subnet: {subnet:} at contract: {address:}");
 
@@ -506,13 +595,8 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.claim_reward_for_relayer())
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
+        let txn = contract.claim_reward_for_relayer();
+        self.send_call(signer, txn).await
     }
 
     async fn fund(
@@ -522,7 +606,7 @@ impl SubnetManager for EthSubnetManager {
         from: Address,
         to: Address,
         amount: TokenAmount,
-    ) -> Result<ChainEpoch> {
+    ) -> Result<TxResult> {
         self.ensure_same_gateway(&gateway_addr)?;
 
         let value = amount
@@ -546,11 +630,7 @@ impl SubnetManager for EthSubnetManager {
             gateway_manager_facet::FvmAddress::try_from(to)?,
         );
         txn.tx.set_value(value);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        self.send_call(signer, txn).await
     }
 
     async fn release(
@@ -560,7 +640,7 @@ impl SubnetManager for EthSubnetManager {
         to: Address,
         amount: TokenAmount,
         fee: Option<TokenAmount>,
-    ) -> Result<ChainEpoch> {
+    ) -> Result<TxResult> {
         self.ensure_same_gateway(&gateway_addr)?;
 
         let value = amount
@@ -576,7 +656,11 @@ impl SubnetManager for EthSubnetManager {
                     Arc::new(self.ipc_contract_info.provider.clone()),
                 );
                 // use default cross-message fee if not set.
-                gateway_getter.cross_msg_fee().call().await?
+                gateway_getter
+                    .cross_msg_fee()
+                    .call()
+                    .await
+                    .map_err(contract_error)?
             }
         };
 
@@ -590,11 +674,7 @@ impl SubnetManager for EthSubnetManager {
         let mut txn =
             gateway_contract.release(gateway_manager_facet::FvmAddress::try_from(to)?, fee);
         txn.tx.set_value(value);
-        let txn = call_with_premium_estimation(signer, txn).await?;
-
-        let pending_tx = txn.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        self.send_call(signer, txn).await
     }
 
     /// Propagate the postbox message key. The key should be `bytes32`.
@@ -604,7 +684,7 @@ impl SubnetManager for EthSubnetManager {
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
-    ) -> Result<()> {
+    ) -> Result<TxResult> {
         if postbox_msg_key.len() != 32 {
             return Err(anyhow!(
                 "invalid message cid length, expect 32 but found {}",
@@ -625,12 +705,8 @@ impl SubnetManager for EthSubnetManager {
         let mut key = [0u8; 32];
         key.copy_from_slice(&postbox_msg_key);
 
-        call_with_premium_estimation(signer, gateway_contract.propagate(key))
-            .await?
-            .send()
-            .await?;
-
-        Ok(())
+        let txn = gateway_contract.propagate(key);
+        self.send_call(signer, txn).await
     }
 
     async fn send_cross_message(
@@ -638,7 +714,7 @@ impl SubnetManager for EthSubnetManager {
         gateway_addr: Address,
         from: Address,
         cross_msg: CrossMsg,
-    ) -> Result<()> {
+    ) -> Result<TxResult> {
         self.ensure_same_gateway(&gateway_addr)?;
 
         log::info!("send evm cross messages to gateway contract: {gateway_addr:} with message: {cross_msg:?}");
@@ -649,33 +725,28 @@ impl SubnetManager for EthSubnetManager {
             signer.clone(),
         );
 
+        // the gateway is paid the value of the message and its fee.
+        let value = fil_to_eth_amount(&(&cross_msg.msg.value + &cross_msg.msg.fee))?;
         let evm_cross_msg = gateway_messenger_facet::CrossMsg::try_from(cross_msg)?;
-        call_with_premium_estimation(signer, gateway_contract.send_cross_message(evm_cross_msg))
-            .await?
-            .send()
-            .await?;
-
-        Ok(())
+        let mut txn = gateway_contract.send_cross_message(evm_cross_msg);
+        txn.tx.set_value(value);
+        self.send_call(signer, txn).await
     }
 
     /// Send value between two addresses in a subnet
-    async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()> {
+    async fn send_value(
+        &self,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult> {
         let signer = Arc::new(self.get_signer(&from)?);
-        let (fee, fee_cap) = premium_estimation(signer.clone()).await?;
         let tx = Eip1559TransactionRequest::new()
             .to(payload_to_evm_address(to.payload())?)
-            .value(fil_to_eth_amount(&amount)?)
-            .max_priority_fee_per_gas(fee)
-            .max_fee_per_gas(fee_cap);
-
-        let tx_pending = signer.send_transaction(tx, None).await?;
+            .value(fil_to_eth_amount(&amount)?);
 
-        log::info!(
-            "sending FIL from {from:} to {to:} in tx {:?}",
-            tx_pending.tx_hash()
-        );
-        tx_pending.await?;
-        Ok(())
+        log::info!("sending FIL from {from:} to {to:}");
+        self.send_transaction(signer, "send_value", tx.into()).await
     }
 
     async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
@@ -708,18 +779,48 @@ impl SubnetManager for EthSubnetManager {
 
         Ok(SubnetGenesisInfo {
             // Active validators limit set for the child subnet.
-            active_validators_limit: contract.active_validators_limit().call().await?,
+            active_validators_limit: contract
+                .active_validators_limit()
+                .call()
+                .await
+                .map_err(contract_error)?,
             // Bottom-up checkpoint period set in the subnet actor.
-            bottom_up_checkpoint_period: contract.bottom_up_check_period().call().await?,
+            bottom_up_checkpoint_period: contract
+                .bottom_up_check_period()
+                .call()
+                .await
+                .map_err(contract_error)?,
             // Genesis epoch when the subnet was bootstrapped in the parent.
             genesis_epoch: self.genesis_epoch(subnet).await?,
             // Majority percentage of
-            majority_percentage: contract.majority_percentage().call().await?,
+            majority_percentage: contract
+                .majority_percentage()
+                .call()
+                .await
+                .map_err(contract_error)?,
             // Minimum collateral required for subnets to register into the subnet
-            min_collateral: eth_to_fil_amount(&contract.min_activation_collateral().call().await?)?,
+            min_collateral: eth_to_fil_amount(
+                &contract
+                    .min_activation_collateral()
+                    .call()
+                    .await
+                    .map_err(contract_error)?,
+            )?,
             // Custom message fee that the child subnet wants to set for cross-net messages
-            msg_fee: eth_to_fil_amount(&contract.min_cross_msg_fee().call().await?)?,
-            validators: from_contract_validators(contract.genesis_validators().call().await?)?,
+            msg_fee: eth_to_fil_amount(
+                &contract
+                    .min_cross_msg_fee()
+                    .call()
+                    .await
+                    .map_err(contract_error)?,
+            )?,
+            validators: from_contract_validators(
+                contract
+                    .genesis_validators()
+                    .call()
+                    .await
+                    .map_err(contract_error)?,
+            )?,
             genesis_balances: into_genesis_balance_map(genesis_balances.0, genesis_balances.1)?,
         })
     }
@@ -729,7 +830,7 @@ impl SubnetManager for EthSubnetManager {
         subnet: &SubnetID,
         from: &Address,
         endpoint: String,
-    ) -> Result<()> {
+    ) -> Result<TxResult> {
         let address = contract_address_from_subnet(subnet)?;
 
         if is_valid_bootstrap_addr(&endpoint).is_none() {
@@ -740,13 +841,8 @@ impl SubnetManager for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
 
-        call_with_premium_estimation(signer, contract.add_bootstrap_node(endpoint))
-            .await?
-            .send()
-            .await?
-            .await?;
-
-        Ok(())
+        let txn = contract.add_bootstrap_node(endpoint);
+        self.send_call(signer, txn).await
     }
 
     async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>> {
@@ -755,7 +851,11 @@ impl SubnetManager for EthSubnetManager {
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        Ok(contract.get_bootstrap_nodes().call().await?)
+        Ok(contract
+            .get_bootstrap_nodes()
+            .call()
+            .await
+            .map_err(contract_error)?)
     }
 
     async fn get_validator_info(
@@ -770,9 +870,21 @@ impl SubnetManager for EthSubnetManager {
         );
         let validator = payload_to_evm_address(validator.payload())?;
 
-        let validator_info = contract.get_validator(validator).call().await?;
-        let is_active = contract.is_active_validator(validator).call().await?;
-        let is_waiting = contract.is_waiting_validator(validator).call().await?;
+        let validator_info = contract
+            .get_validator(validator)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        let is_active = contract
+            .is_active_validator(validator)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        let is_waiting = contract
+            .is_waiting_validator(validator)
+            .call()
+            .await
+            .map_err(contract_error)?;
 
         Ok(ValidatorInfo {
             staking: ValidatorStakingInfo::try_from(validator_info)?,
@@ -780,6 +892,280 @@ impl SubnetManager for EthSubnetManager {
             is_waiting,
         })
     }
+
+    async fn get_subnet_info(&self, subnet: &SubnetID) -> Result<SubnetDetails> {
+        let gateway = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let evm_subnet_id = gateway_getter_facet::SubnetID::try_from(subnet)?;
+        let (exists, evm_subnet) = gateway
+            .get_subnet(evm_subnet_id)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        // the subnet is only registered in the gateway once it is bootstrapped.
+        let info = if exists {
+            SubnetInfo::try_from(evm_subnet)?
+        } else {
+            SubnetInfo {
+                id: subnet.clone(),
+                stake: TokenAmount::zero(),
+                circ_supply: TokenAmount::zero(),
+                genesis_epoch: 0,
+                status: Status::Inactive,
+            }
+        };
+
+        let address = contract_address_from_subnet(subnet)?;
+        let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let active_validators = contract
+            .get_active_validators_number()
+            .call()
+            .await
+            .map_err(contract_error)?;
+        let total_validators = contract
+            .get_total_validators_number()
+            .call()
+            .await
+            .map_err(contract_error)?;
+
+        Ok(SubnetDetails {
+            id: info.id,
+            status: info.status,
+            stake: info.stake,
+            circ_supply: info.circ_supply,
+            genesis_epoch: info.genesis_epoch,
+            bottom_up_checkpoint_period: contract
+                .bottom_up_check_period()
+                .call()
+                .await
+                .map_err(contract_error)?,
+            majority_percentage: contract
+                .majority_percentage()
+                .call()
+                .await
+                .map_err(contract_error)?,
+            min_collateral: eth_to_fil_amount(
+                &contract
+                    .min_activation_collateral()
+                    .call()
+                    .await
+                    .map_err(contract_error)?,
+            )?,
+            min_cross_msg_fee: eth_to_fil_amount(
+                &contract
+                    .min_cross_msg_fee()
+                    .call()
+                    .await
+                    .map_err(contract_error)?,
+            )?,
+            active_validators,
+            waiting_validators: total_validators.saturating_sub(active_validators),
+            bootstrap_nodes: contract
+                .get_bootstrap_nodes()
+                .call()
+                .await
+                .map_err(contract_error)?,
+        })
+    }
+
+    async fn list_validators(&self, subnet: &SubnetID) -> Result<Vec<SubnetValidator>> {
+        let address = contract_address_from_subnet(subnet)?;
+        let contract = subnet_actor_getter_facet::SubnetActorGetterFacet::new(
+            address,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+
+        // the subnet actor does not enumerate its validators, they are the genesis ones
+        // and those with staking changes since the subnet was bootstrapped.
+        let mut addresses = from_contract_validators(
+            contract
+                .genesis_validators()
+                .call()
+                .await
+                .map_err(contract_error)?,
+        )?
+        .into_iter()
+        .map(|v| v.addr)
+        .collect::<BTreeSet<_>>();
+
+        let gateway = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let evm_subnet_id = gateway_getter_facet::SubnetID::try_from(subnet)?;
+        let (exists, evm_subnet) = gateway
+            .get_subnet(evm_subnet_id)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        if exists {
+            let genesis_epoch = evm_subnet.genesis_epoch.as_u64() as ChainEpoch;
+            let query = self.events(IpcEventFilter::new().staking_changes(subnet)?);
+            let head = self.chain_head_height().await?;
+            for IpcLog { event, .. } in query.range(genesis_epoch, head).await? {
+                if let IpcEvent::NewStakingChangeRequest(change) = event {
+                    addresses.insert(change.change.validator);
+                }
+            }
+        }
+
+        let mut validators = vec![];
+        for addr in addresses {
+            let info = self.get_validator_info(subnet, &addr).await?;
+            // validators that left the subnet are still reported by the contract.
+            if info.is_active || info.is_waiting {
+                validators.push((addr, info));
+            }
+        }
+        Ok(SubnetValidator::ranked(validators))
+    }
+
+    async fn get_fund_msg(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+    ) -> Result<Option<CommittedTopDownMsg>> {
+        let Some((tx, block_hash)) = self.gateway_tx(tx_hash).await? else {
+            return Ok(None);
+        };
+        let call = gateway_manager_facet::FundCall::decode(&tx.input)
+            .map_err(|_| anyhow!("transaction {:?} is not a fund", tx.hash))?;
+
+        // the gateway does not log the nonce assigned to the message, so the message is
+        // matched by its sender, recipient and value. If the sender sent the same fund
+        // several times in the block, the messages follow the order of the transactions.
+        let from = ethers_address_to_fil_address(&tx.from)?;
+        let value = eth_to_fil_amount(&tx.value)?;
+        let height = tx
+            .block_number
+            .ok_or_else(|| anyhow!("cannot get block number"))?
+            .as_u64() as ChainEpoch;
+        let msgs = top_down_msgs_at(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+            subnet,
+            height,
+            block_hash,
+        )
+        .await?;
+        let mut candidates = vec![];
+        for msg in msgs {
+            let to = gateway_manager_facet::FvmAddress::try_from(msg.msg.to.raw_addr()?)?;
+            if msg.msg.from.raw_addr()? == from && msg.msg.value == value && to == call.to {
+                candidates.push(msg);
+            }
+        }
+
+        let index = self
+            .count_earlier_txs(&tx, block_hash, |other| {
+                other.value == tx.value && other.input == tx.input
+            })
+            .await?;
+        let msg = candidates
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| anyhow!("no top-down message to {subnet} committed by {:?}", tx.hash))?;
+        Ok(Some(CommittedTopDownMsg {
+            height,
+            block_hash: block_hash.0.to_vec(),
+            msg,
+        }))
+    }
+
+    async fn get_release_msg(
+        &self,
+        tx_hash: &[u8],
+        checkpoint_period: ChainEpoch,
+    ) -> Result<Option<CheckpointedBottomUpMsg>> {
+        let Some((tx, block_hash)) = self.gateway_tx(tx_hash).await? else {
+            return Ok(None);
+        };
+        let call = gateway_manager_facet::ReleaseCall::decode(&tx.input)
+            .map_err(|_| anyhow!("transaction {:?} is not a release", tx.hash))?;
+        if checkpoint_period <= 0 {
+            return Err(anyhow!("invalid checkpoint period: {checkpoint_period}"));
+        }
+
+        // the message is matched by its sender and recipient, in the order of the releases
+        // of the sender to the same recipient committed to the same checkpoint.
+        let from = ethers_address_to_fil_address(&tx.from)?;
+        let same_recipient = |other: &ethers::types::Transaction| {
+            gateway_manager_facet::ReleaseCall::decode(&other.input)
+                .map(|c| c.to == call.to)
+                .unwrap_or_default()
+        };
+        let in_block = self
+            .count_earlier_txs(&tx, block_hash, same_recipient)
+            .await?;
+
+        let height = tx
+            .block_number
+            .ok_or_else(|| anyhow!("cannot get block number"))?
+            .as_u64() as ChainEpoch;
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        // the message is carried by the next checkpoint, which may be the one at the height
+        // of the release itself if it is a checkpoint height.
+        let mut checkpoint_heights = vec![
+            (height + checkpoint_period - 1) / checkpoint_period * checkpoint_period,
+            (height / checkpoint_period + 1) * checkpoint_period,
+        ];
+        checkpoint_heights.dedup();
+        for checkpoint_height in checkpoint_heights {
+            // the gateway commits the messages sent from the previous checkpoint height on.
+            let index = in_block
+                + self
+                    .count_txs_in_blocks(
+                        &tx,
+                        checkpoint_height - checkpoint_period,
+                        height,
+                        same_recipient,
+                    )
+                    .await?;
+
+            let msgs = contract
+                .bottom_up_messages(checkpoint_height as u64)
+                .call()
+                .await
+                .map_err(contract_error)?;
+            let mut candidates = vec![];
+            for msg in msgs {
+                let msg = CrossMsg::try_from(msg)?;
+                let to = gateway_manager_facet::FvmAddress::try_from(msg.msg.to.raw_addr()?)?;
+                if msg.msg.from.raw_addr()? == from && to == call.to {
+                    candidates.push(msg);
+                }
+            }
+            if let Some(msg) = candidates.into_iter().nth(index) {
+                return Ok(Some(CheckpointedBottomUpMsg {
+                    height,
+                    checkpoint_height,
+                    msg,
+                }));
+            }
+        }
+        Err(anyhow!("no bottom-up message sent by {:?}", tx.hash))
+    }
+
+    async fn cross_msg_fee(&self) -> Result<TokenAmount> {
+        let gateway_getter = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let fee = gateway_getter
+            .cross_msg_fee()
+            .call()
+            .await
+            .map_err(contract_error)?;
+        eth_to_fil_amount(&fee)
+    }
 }
 
 #[async_trait]
@@ -805,7 +1191,8 @@ impl EthManager for EthSubnetManager {
         let checkpoint = gateway_contract
             .bottom_up_checkpoint(epoch as u64)
             .call()
-            .await?;
+            .await
+            .map_err(contract_error)?;
         log::debug!("raw bottom up checkpoint from gateway: {checkpoint:?}");
         let token = checkpoint.into_token();
         let c = subnet_actor_manager_facet::BottomUpCheckpoint::from_token(token)?;
@@ -845,7 +1232,11 @@ impl EthManager for EthSubnetManager {
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        Ok(contract.bottom_up_check_period().call().await? as ChainEpoch)
+        Ok(contract
+            .bottom_up_check_period()
+            .call()
+            .await
+            .map_err(contract_error)? as ChainEpoch)
     }
 
     async fn prev_bottom_up_checkpoint_hash(
@@ -878,7 +1269,11 @@ impl EthManager for EthSubnetManager {
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        Ok(contract.min_validators().call().await?)
+        Ok(contract
+            .min_validators()
+            .call()
+            .await
+            .map_err(contract_error)?)
     }
 }
 
@@ -887,7 +1282,7 @@ impl EthSubnetManager {
         gateway_addr: ethers::types::Address,
         registry_addr: ethers::types::Address,
         chain_id: u64,
-        provider: Provider<Http>,
+        provider: Provider<EthTransport>,
         keystore: Option<Arc<RwLock<PersistentKeyStore<EthKeyAddress>>>>,
     ) -> Self {
         Self {
@@ -898,7 +1293,424 @@ impl EthSubnetManager {
                 chain_id,
                 provider,
             },
+            dry_run: false,
+            offline_signing: false,
+            signers: vec![],
+            nonces: NonceManager::new(),
+            gas_strategy: Arc::new(Eip1559Estimator),
+            tx_type: TxType::Auto,
+            detected_tx_type: tokio::sync::OnceCell::new(),
+            required_confirmations: DEFAULT_REQUIRED_CONFIRMATIONS,
+            max_wait: Duration::from_secs(DEFAULT_MAX_WAIT_SECS),
+        }
+    }
+
+    /// Sets whether state-changing operations are only simulated. In dry-run mode every
+    /// mutating operation returns a `TxResult::Simulated` report instead of sending the
+    /// transaction, including when its fees exceed the `MaxFeeCeiling` of the subnet.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets whether state-changing operations are only built, to be signed offline. In
+    /// offline-signing mode every mutating operation returns a `TxResult::Unsigned`
+    /// transaction instead of sending it, and the private key of the sender does not need
+    /// to be in the keystore.
+    pub fn with_offline_signing(mut self, offline_signing: bool) -> Self {
+        self.offline_signing = offline_signing;
+        self
+    }
+
+    /// Sets how the transactions of each address are signed, addresses without a signer are
+    /// signed with their key in the keystore.
+    pub fn with_signers(mut self, signers: Vec<SignerConfig>) -> Self {
+        self.signers = signers;
+        self
+    }
+
+    /// Sets how long to wait for the transactions sent by the manager. Operations wait until
+    /// the transaction has `required_confirmations`, and fail with a `TxTimeoutError` if that
+    /// does not happen within `max_wait`.
+    pub fn with_tx_wait(mut self, required_confirmations: usize, max_wait: Duration) -> Self {
+        self.required_confirmations = required_confirmations;
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Sets the nonce manager used to allocate the nonces of the transactions, to share
+    /// it with other managers sending transactions from the same signers.
+    pub fn with_nonce_manager(mut self, nonces: NonceManager) -> Self {
+        self.nonces = nonces;
+        self
+    }
+
+    /// Sets the strategy used to price the transactions sent by the manager.
+    pub fn with_gas_strategy(mut self, gas_strategy: Arc<dyn GasStrategy>) -> Self {
+        self.gas_strategy = gas_strategy;
+        self
+    }
+
+    /// Forces the type of the transactions sent by the manager. With `TxType::Auto`, legacy
+    /// transactions are sent if the chain has not activated EIP-1559.
+    pub fn with_tx_type(mut self, tx_type: TxType) -> Self {
+        self.tx_type = tx_type;
+        self
+    }
+
+    /// Queries the IPC events matching `filter` through the provider of the manager.
+    pub fn events(&self, filter: IpcEventFilter) -> IpcEventQuery<Provider<EthTransport>> {
+        let provider = self.ipc_contract_info.provider.clone();
+        let interval = provider.get_interval();
+        IpcEventQuery::new(Arc::new(provider), filter, interval)
+    }
+
+    pub fn gateway_addr(&self) -> ethers::types::Address {
+        self.ipc_contract_info.gateway_addr
+    }
+
+    pub fn registry_addr(&self) -> ethers::types::Address {
+        self.ipc_contract_info.registry_addr
+    }
+
+    /// Returns the type of the transactions to send, detecting whether the chain supports
+    /// EIP-1559 unless the type is forced.
+    async fn tx_type(&self) -> Result<TxType> {
+        if self.tx_type != TxType::Auto {
+            return Ok(self.tx_type);
         }
+
+        let tx_type = self
+            .detected_tx_type
+            .get_or_try_init(|| async {
+                let tx_type = if supports_eip1559(&self.ipc_contract_info.provider).await? {
+                    TxType::Eip1559
+                } else {
+                    log::info!("EIP-1559 not activated, falling back to legacy transactions");
+                    TxType::Legacy
+                };
+                Ok::<_, anyhow::Error>(tx_type)
+            })
+            .await?;
+        Ok(*tx_type)
+    }
+
+    /// Fills in the nonce and gas of `tx` so that it can be signed offline.
+    async fn unsigned_tx(
+        &self,
+        signer: Arc<DefaultSignerMiddleware>,
+        method: &str,
+        mut tx: TypedTransaction,
+    ) -> Result<UnsignedTransaction> {
+        // the nonce manager is bypassed, as the transaction is sent by another process
+        // once signed and the nonce it reserves would never be used.
+        signer
+            .fill_transaction(&mut tx, None)
+            .await
+            .map_err(middleware_error)?;
+        let unsigned = UnsignedTransaction {
+            method: method.to_string(),
+            chain_id: self.ipc_contract_info.chain_id,
+            tx,
+        };
+        log::debug!("unsigned transaction: {unsigned:?}");
+
+        Ok(unsigned)
+    }
+
+    /// Broadcasts a transaction signed offline and waits for its receipt.
+    pub async fn send_raw_transaction(&self, signed: &SignedTransaction) -> Result<TxOutcome> {
+        let chain_id = self.ipc_contract_info.chain_id;
+        if signed.chain_id != chain_id {
+            return Err(anyhow!(
+                "transaction signed for chain {}, but the subnet has chain id {chain_id}",
+                signed.chain_id
+            ));
+        }
+
+        let pending_tx = self
+            .ipc_contract_info
+            .provider
+            .send_raw_transaction(signed.raw.clone())
+            .await?;
+        log::debug!("sent {} in tx {:?}", signed.method, pending_tx.tx_hash());
+        // the transaction took a nonce behind the back of the nonce manager.
+        self.nonces.resync(chain_id, signed.from).await;
+
+        self.wait_for_outcome(pending_tx).await
+    }
+
+    /// Sends `call` and waits for its receipt, see `send_transaction`.
+    async fn send_call<D>(
+        &self,
+        signer: Arc<DefaultSignerMiddleware>,
+        call: ethers_contract::FunctionCall<
+            Arc<DefaultSignerMiddleware>,
+            DefaultSignerMiddleware,
+            D,
+        >,
+    ) -> Result<TxResult>
+    where
+        D: ethers::abi::Detokenize,
+    {
+        self.send_transaction(signer, &call.function.name, call.tx)
+            .await
+    }
+
+    /// Sends `tx` and waits for its receipt, or only simulates it or builds it to be signed
+    /// offline if the manager is in dry-run or offline-signing mode. The fees of the
+    /// transaction are set by the gas strategy of the subnet and its nonce is allocated by
+    /// the nonce manager, every write goes through here so that transactions from the same
+    /// signer never reuse a nonce. On chains without EIP-1559 the transaction is sent as a
+    /// legacy one.
+    async fn send_transaction(
+        &self,
+        signer: Arc<DefaultSignerMiddleware>,
+        method: &str,
+        mut tx: TypedTransaction,
+    ) -> Result<TxResult> {
+        let (fees, gas_ceiling_exceeded) = match self.estimate_fees(&mut tx).await {
+            Ok(fees) => (fees, None),
+            // the simulation reports fees above the ceiling instead of failing.
+            Err(e) if self.dry_run && e.is::<GasCeilingExceeded>() => {
+                let exceeded = e.downcast::<GasCeilingExceeded>()?;
+                (exceeded.fees, Some(exceeded))
+            }
+            Err(e) => return Err(e),
+        };
+        fees.apply(&mut tx);
+        log::debug!("gas fees for {method}: {fees:?}");
+
+        if self.dry_run {
+            let mut report = simulate_transaction(&signer, method, &tx, &fees).await?;
+            report.gas_ceiling_exceeded = gas_ceiling_exceeded;
+            log::debug!("dry run report: {report:?}");
+            return Ok(TxResult::Simulated(report));
+        }
+        if self.offline_signing {
+            let unsigned = self.unsigned_tx(signer, method, tx).await?;
+            return Ok(TxResult::Unsigned(unsigned));
+        }
+
+        let chain_id = self.ipc_contract_info.chain_id;
+        let from = signer.address();
+        let nonce = self.nonces.next(signer.as_ref(), chain_id, from).await?;
+        tx.set_nonce(nonce);
+
+        let pending_tx = match signer.send_transaction(tx, None).await {
+            Ok(pending_tx) => pending_tx,
+            Err(e) => {
+                // the nonce may not have been consumed, or may be out of sync with
+                // transactions sent by other clients.
+                self.nonces.resync(chain_id, from).await;
+                return Err(middleware_error(e));
+            }
+        };
+        log::debug!(
+            "sent {method} with nonce {nonce} in tx {:?}",
+            pending_tx.tx_hash()
+        );
+
+        let outcome = self.wait_for_outcome(pending_tx).await;
+        if outcome.is_err() {
+            self.nonces.resync(chain_id, from).await;
+        }
+        outcome.map(TxResult::Sent)
+    }
+
+    /// Estimates the fees of `tx` with the gas strategy of the subnet, turning it into a
+    /// legacy transaction on chains without EIP-1559.
+    async fn estimate_fees(&self, tx: &mut TypedTransaction) -> Result<GasFees> {
+        let provider = &self.ipc_contract_info.provider;
+        match self.tx_type().await? {
+            TxType::Legacy => {
+                *tx = into_legacy(tx.clone());
+                let gas_price = self.gas_strategy.estimate_gas_price(provider).await?;
+                Ok(GasFees::legacy(gas_price))
+            }
+            TxType::Eip1559 | TxType::Auto => self.gas_strategy.estimate_fees(provider).await,
+        }
+    }
+
+    /// Waits for the receipt of a sent transaction and converts it into a `TxOutcome`.
+    async fn wait_for_outcome(
+        &self,
+        pending_tx: PendingTransaction<'_, EthTransport>,
+    ) -> Result<TxOutcome> {
+        let tx_hash = pending_tx.tx_hash();
+        // the transaction is not considered dropped until the maximum wait time is reached,
+        // so keep retrying to fetch it until then.
+        let pending_tx = pending_tx
+            .confirmations(self.required_confirmations)
+            .retries(usize::MAX);
+
+        match tokio::time::timeout(self.max_wait, pending_tx).await {
+            Ok(Ok(Some(receipt))) => self.outcome_from_receipt(receipt).await,
+            Ok(Ok(None)) => Err(anyhow!(
+                "txn {tx_hash:?} sent to network, but receipt cannot be obtained, please check scanner"
+            )),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => Err(anyhow::Error::new(TxTimeoutError {
+                tx_hash,
+                waited: self.max_wait,
+            })),
+        }
+    }
+
+    /// Converts the receipt of a transaction into a `TxOutcome`, failing with a
+    /// `TxRevertedError` if the transaction reverted.
+    async fn outcome_from_receipt(&self, receipt: TransactionReceipt) -> Result<TxOutcome> {
+        if receipt.status == Some(1.into()) {
+            return tx_outcome_from_receipt(receipt);
+        }
+
+        let tx_hash = receipt.transaction_hash;
+        let block_number = receipt
+            .block_number
+            .ok_or_else(|| anyhow!("cannot get block number"))?;
+        let reason = self.revert_reason(tx_hash, block_number).await;
+        Err(anyhow::Error::new(TxRevertedError {
+            tx_hash,
+            block_number: block_number.as_u64(),
+            reason,
+        }))
+    }
+
+    /// Replays a reverted transaction at its block to decode the reason of the revert.
+    async fn revert_reason(&self, tx_hash: H256, block_number: U64) -> Option<IpcContractError> {
+        let provider = &self.ipc_contract_info.provider;
+        let tx = match provider.get_transaction(tx_hash).await {
+            Ok(Some(tx)) => tx,
+            Ok(None) => return None,
+            Err(e) => {
+                log::warn!("cannot get reverted transaction {tx_hash:?}: {e}");
+                return None;
+            }
+        };
+
+        let call = TypedTransaction::from(&tx);
+        let block = BlockId::Number(BlockNumber::Number(block_number));
+        match provider.call(&call, Some(block)).await {
+            Ok(_) => None,
+            Err(e) => revert_data_from_middleware_error(&e)
+                .map(|d| IpcContractError::from_revert_data(&d)),
+        }
+    }
+
+    /// Returns a successful transaction to the gateway and the hash of the block that
+    /// included it, or `None` if the transaction is not included in a block yet.
+    async fn gateway_tx(
+        &self,
+        tx_hash: &[u8],
+    ) -> Result<Option<(ethers::types::Transaction, H256)>> {
+        if tx_hash.len() != 32 {
+            return Err(anyhow!("invalid tx hash len"));
+        }
+        let hash = H256::from_slice(tx_hash);
+        let provider = &self.ipc_contract_info.provider;
+
+        let Some(receipt) = provider.get_transaction_receipt(hash).await? else {
+            return Ok(None);
+        };
+        let Some(block_hash) = receipt.block_hash else {
+            return Ok(None);
+        };
+        if receipt.status != Some(1.into()) {
+            return Err(anyhow!("transaction {hash:?} failed"));
+        }
+        let tx = provider
+            .get_transaction(hash)
+            .await?
+            .ok_or_else(|| anyhow!("transaction {hash:?} not found"))?;
+        if tx.to != Some(self.ipc_contract_info.gateway_addr) {
+            return Err(anyhow!("transaction {hash:?} was not sent to the gateway"));
+        }
+        Ok(Some((tx, block_hash)))
+    }
+
+    /// Counts the successful transactions of the sender of `tx` to the same contract that
+    /// precede it in its block and match `f`.
+    async fn count_earlier_txs<F>(
+        &self,
+        tx: &ethers::types::Transaction,
+        block_hash: H256,
+        f: F,
+    ) -> Result<usize>
+    where
+        F: Fn(&ethers::types::Transaction) -> bool,
+    {
+        let provider = &self.ipc_contract_info.provider;
+        let block = provider
+            .get_block_with_txs(block_hash)
+            .await?
+            .ok_or_else(|| anyhow!("block {block_hash:?} not found"))?;
+
+        let mut count = 0;
+        for other in block.transactions {
+            if other.transaction_index >= tx.transaction_index
+                || other.from != tx.from
+                || other.to != tx.to
+                || !f(&other)
+            {
+                continue;
+            }
+            if self.tx_succeeded(other.hash).await? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Counts the successful transactions of the sender of `tx` to the same contract that
+    /// match `f` in the blocks from `from` up to `to`, exclusive.
+    async fn count_txs_in_blocks<F>(
+        &self,
+        tx: &ethers::types::Transaction,
+        from: ChainEpoch,
+        to: ChainEpoch,
+        f: F,
+    ) -> Result<usize>
+    where
+        F: Fn(&ethers::types::Transaction) -> bool,
+    {
+        let provider = &self.ipc_contract_info.provider;
+        let f = &f;
+
+        // the range spans up to a checkpoint period, so the blocks are fetched concurrently
+        // and only the receipts of the matching transactions are queried.
+        let hashes = stream::iter(from.max(0)..to)
+            .map(|height| async move {
+                let block = provider
+                    .get_block_with_txs(height as u64)
+                    .await?
+                    .ok_or_else(|| anyhow!("block {height} not found"))?;
+                Ok::<_, anyhow::Error>(
+                    block
+                        .transactions
+                        .into_iter()
+                        .filter(|other| other.from == tx.from && other.to == tx.to && f(other))
+                        .map(|other| other.hash)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .buffered(MAX_CONCURRENT_QUERIES)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let succeeded = stream::iter(hashes.into_iter().flatten())
+            .map(|hash| self.tx_succeeded(hash))
+            .buffered(MAX_CONCURRENT_QUERIES)
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(succeeded.into_iter().filter(|ok| *ok).count())
+    }
+
+    async fn tx_succeeded(&self, hash: H256) -> Result<bool> {
+        let receipt = self
+            .ipc_contract_info
+            .provider
+            .get_transaction_receipt(hash)
+            .await?;
+        Ok(receipt.and_then(|r| r.status) == Some(1.into()))
     }
 
     pub fn ensure_same_gateway(&self, gateway: &Address) -> Result<()> {
@@ -920,19 +1732,47 @@ impl EthSubnetManager {
     /// We use filecoin addresses throughout our whole code-base
     /// and translate them to evm addresses when relevant.
     fn get_signer(&self, addr: &Address) -> Result<DefaultSignerMiddleware> {
+        let config = self.signers.iter().find(|s| s.address() == addr);
         // convert to its underlying eth address
         let addr = payload_to_evm_address(addr.payload())?;
-        let keystore = self.keystore()?;
-        let keystore = keystore.read().unwrap();
-        let private_key = keystore
-            .get(&addr.into())?
-            .ok_or_else(|| anyhow!("address {addr:} does not have private key in key store"))?;
-        let wallet = LocalWallet::from_bytes(private_key.private_key())?
-            .with_chain_id(self.ipc_contract_info.chain_id);
+        let chain_id = self.ipc_contract_info.chain_id;
+
+        let signer: Option<Arc<dyn TransactionSigner>> = match config {
+            Some(SignerConfig::Web3Signer {
+                url, auth_token, ..
+            }) => Some(Arc::new(Web3Signer::new(
+                addr,
+                url.clone(),
+                auth_token.as_deref(),
+            ))),
+            Some(SignerConfig::Keystore { .. }) | None => {
+                let private_key = match &self.keystore {
+                    Some(keystore) => keystore.read().unwrap().get(&addr.into())?,
+                    None => None,
+                };
+                match private_key {
+                    Some(private_key) => Some(Arc::new(
+                        LocalWallet::from_bytes(private_key.private_key())?.with_chain_id(chain_id),
+                    )),
+                    None => None,
+                }
+            }
+        };
+        let signer = match signer {
+            Some(signer) => EthSigner::new(signer, chain_id),
+            // transactions signed offline only need the address of the sender.
+            None if self.offline_signing => EthSigner::offline(addr, chain_id),
+            None if self.keystore.is_none() => return Err(anyhow!("no evm keystore available")),
+            None => {
+                return Err(anyhow!(
+                    "address {addr:} does not have private key in key store"
+                ))
+            }
+        };
 
         Ok(SignerMiddleware::new(
             self.ipc_contract_info.provider.clone(),
-            wallet,
+            signer,
         ))
     }
 
@@ -943,19 +1783,33 @@ impl EthSubnetManager {
         let url = subnet.rpc_http().clone();
         let auth_token = subnet.auth_token();
 
-        let SubnetConfig::Fevm(config) = &subnet.config;
+        let SubnetConfig::Fevm(config) = &subnet.config else {
+            return Err(anyhow!("subnet {} is not an fevm subnet", subnet.id));
+        };
 
         let provider = if auth_token.is_some() {
             Http::new_with_auth(url, Authorization::Bearer(auth_token.unwrap()))?
         } else {
             Http::new(url)
         };
+        let mut endpoints = vec![provider];
+        for fallback in &config.fallback_providers_http {
+            endpoints.push(match fallback.auth_token() {
+                Some(token) => Http::new_with_auth(
+                    fallback.url().clone(),
+                    Authorization::Bearer(token.to_string()),
+                )?,
+                None => Http::new(fallback.url().clone()),
+            });
+        }
 
-        let mut provider = Provider::new(provider);
+        let transport = FailoverTransport::new(endpoints, config.rpc_timeout())?
+            .with_read_quorum(config.rpc_read_quorum())
+            .with_health_check_interval(DEFAULT_HEALTH_CHECK_INTERVAL);
+        let transport = RetryTransport::new(transport, subnet_retry_policy(subnet));
+        let mut provider = Provider::new(transport);
         // set polling interval for provider to fit fast child subnets block times.
-        // TODO: We may want to make it dynamic so it adjusts depending on the type of network
-        // so we don't have a too slow or too fast polling for the underlying block times.
-        provider.set_interval(ETH_PROVIDER_POLLING_TIME);
+        provider.set_interval(config.polling_interval());
         let gateway_address = payload_to_evm_address(config.gateway_addr.payload())?;
         let registry_address = payload_to_evm_address(config.registry_addr.payload())?;
 
@@ -965,7 +1819,12 @@ impl EthSubnetManager {
             subnet.id.chain_id(),
             provider,
             keystore,
+        )
+        .with_tx_wait(config.required_confirmations(), config.max_wait())
+        .with_gas_strategy(gas_strategy_from_config(
+            &config.gas.clone().unwrap_or_default(),
         ))
+        .with_tx_type(config.tx_type()))
     }
 }
 
@@ -975,7 +1834,7 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
         &self,
         submitter: &Address,
         bundle: BottomUpCheckpointBundle,
-    ) -> anyhow::Result<ChainEpoch> {
+    ) -> anyhow::Result<TxResult> {
         let BottomUpCheckpointBundle {
             checkpoint,
             signatures,
@@ -1006,11 +1865,7 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
         let contract =
             subnet_actor_manager_facet::SubnetActorManagerFacet::new(address, signer.clone());
         let call = contract.submit_checkpoint(checkpoint, cross_msgs, signatories, signatures);
-        let call = call_with_premium_estimation(signer, call).await?;
-
-        let pending_tx = call.send().await?;
-        let receipt = pending_tx.retries(TRANSACTION_RECEIPT_RETRIES).await?;
-        block_number_from_receipt(receipt)
+        self.send_call(signer, call).await
     }
 
     async fn last_bottom_up_checkpoint_height(
@@ -1022,10 +1877,30 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        let epoch = contract.last_bottom_up_checkpoint_height().call().await?;
+        let epoch = contract
+            .last_bottom_up_checkpoint_height()
+            .call()
+            .await
+            .map_err(contract_error)?;
         Ok(epoch as ChainEpoch)
     }
 
+    async fn applied_bottom_up_nonce(&self, subnet_id: &SubnetID) -> Result<u64> {
+        let contract = gateway_getter_facet::GatewayGetterFacet::new(
+            self.ipc_contract_info.gateway_addr,
+            Arc::new(self.ipc_contract_info.provider.clone()),
+        );
+        let (exists, subnet) = contract
+            .get_subnet(gateway_getter_facet::SubnetID::try_from(subnet_id)?)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        if !exists {
+            return Err(anyhow!("subnet: {} does not exists", subnet_id));
+        }
+        Ok(subnet.applied_bottom_up_nonce)
+    }
+
     async fn has_submitted_in_last_checkpoint_height(
         &self,
         subnet_id: &SubnetID,
@@ -1040,7 +1915,8 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
         Ok(contract
             .has_submitted_in_last_bottom_up_checkpoint_height(addr)
             .call()
-            .await?)
+            .await
+            .map_err(contract_error)?)
     }
 
     async fn checkpoint_period(&self, subnet_id: &SubnetID) -> anyhow::Result<ChainEpoch> {
@@ -1049,7 +1925,11 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
             address,
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
-        let epoch = contract.bottom_up_check_period().call().await?;
+        let epoch = contract
+            .bottom_up_check_period()
+            .call()
+            .await
+            .map_err(contract_error)?;
         Ok(epoch as ChainEpoch)
     }
 
@@ -1062,9 +1942,16 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
             Arc::new(self.ipc_contract_info.provider.clone()),
         );
 
-        let (checkpoint, _, signatories, signatures) =
-            contract.get_signature_bundle(height as u64).call().await?;
-        let cross_msgs = contract.bottom_up_messages(height as u64).call().await?;
+        let (checkpoint, _, signatories, signatures) = contract
+            .get_signature_bundle(height as u64)
+            .call()
+            .await
+            .map_err(contract_error)?;
+        let cross_msgs = contract
+            .bottom_up_messages(height as u64)
+            .call()
+            .await
+            .map_err(contract_error)?;
 
         let checkpoint = BottomUpCheckpoint::try_from(checkpoint)?;
         let signatories = signatories
@@ -1089,27 +1976,37 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
     }
 
     async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>> {
-        let contract = gateway_router_facet::GatewayRouterFacet::new(
-            self.ipc_contract_info.gateway_addr,
-            Arc::new(self.ipc_contract_info.provider.clone()),
-        );
-
-        let ev = contract
-            .event::<gateway_router_facet::QuorumReachedFilter>()
-            .from_block(height as u64)
-            .to_block(height as u64);
+        let query =
+            self.events(IpcEventFilter::new().quorum_reached(self.ipc_contract_info.gateway_addr));
 
         let mut events = vec![];
-        for (event, _meta) in query_with_meta(ev, contract.client()).await? {
-            events.push(QuorumReachedEvent {
-                height: event.height as ChainEpoch,
-                checkpoint: event.checkpoint.to_vec(),
-                quorum_weight: eth_to_fil_amount(&event.quorum_weight)?,
-            });
+        for IpcLog { event, .. } in query.range(height, height).await? {
+            if let IpcEvent::QuorumReached(event) = event {
+                events.push(event);
+            }
         }
 
         Ok(events)
     }
+
+    async fn quorum_reached_events_range(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<QuorumReachedEvent>> {
+        // the logs of the whole range are fetched in pages instead of height by height.
+        let query =
+            self.events(IpcEventFilter::new().quorum_reached(self.ipc_contract_info.gateway_addr));
+
+        let mut events = vec![];
+        for IpcLog { event, .. } in query.range(from, to).await? {
+            if let IpcEvent::QuorumReached(event) = event {
+                events.push(event);
+            }
+        }
+        Ok(events)
+    }
+
     async fn current_epoch(&self) -> Result<ChainEpoch> {
         let epoch = self
             .ipc_contract_info
@@ -1121,139 +2018,108 @@ impl BottomUpCheckpointRelayer for EthSubnetManager {
     }
 }
 
-/// Receives an input `FunctionCall` and returns a new instance
-/// after estimating an optimal `gas_premium` for the transaction
-pub(crate) async fn call_with_premium_estimation<B, D, M>(
-    signer: Arc<DefaultSignerMiddleware>,
-    call: ethers_contract::FunctionCall<B, D, M>,
-) -> Result<ethers_contract::FunctionCall<B, D, M>>
-where
-    B: std::borrow::Borrow<D>,
-    M: ethers::abi::Detokenize,
-{
-    let (max_priority_fee_per_gas, _) = premium_estimation(signer).await?;
-    Ok(call.gas_price(max_priority_fee_per_gas))
-}
+/// Get the outcome of a transaction from its receipt, decoding the IPC events in its logs.
+fn tx_outcome_from_receipt(receipt: TransactionReceipt) -> Result<TxOutcome> {
+    let block_number = receipt
+        .block_number
+        .ok_or_else(|| anyhow!("cannot get block number"))?;
+    let block_hash = receipt
+        .block_hash
+        .ok_or_else(|| anyhow!("cannot get block hash"))?;
+    let effective_gas_price = receipt
+        .effective_gas_price
+        .map(|p| eth_to_fil_amount(&p))
+        .transpose()?;
+
+    let mut events = vec![];
+    for log in receipt.logs {
+        log::debug!("log: {log:?}");
+        if let Some(event) = ipc_event_from_log(log)? {
+            events.push(event);
+        }
+    }
 
-/// Returns an estimation of an optimal `gas_premium` and `gas_fee_cap`
-/// for a transaction considering the average premium, base_fee and reward percentile from
-/// past blocks
-/// This is adaptation of ethers' `eip1559_default_estimator`:
-/// https://github.com/gakonst/ethers-rs/blob/5dcd3b7e754174448f9a8cbfc0523896609629f9/ethers-core/src/utils/mod.rs#L476
-async fn premium_estimation(
-    signer: Arc<DefaultSignerMiddleware>,
-) -> Result<(ethers::types::U256, ethers::types::U256)> {
-    let base_fee_per_gas = signer
-        .get_block(ethers::types::BlockNumber::Latest)
-        .await?
-        .ok_or_else(|| anyhow!("Latest block not found"))?
-        .base_fee_per_gas
-        .ok_or_else(|| anyhow!("EIP-1559 not activated"))?;
-
-    let fee_history = signer
-        .fee_history(
-            ethers::utils::EIP1559_FEE_ESTIMATION_PAST_BLOCKS,
-            ethers::types::BlockNumber::Latest,
-            &[ethers::utils::EIP1559_FEE_ESTIMATION_REWARD_PERCENTILE],
-        )
-        .await?;
+    Ok(TxOutcome {
+        tx_hash: receipt.transaction_hash.to_fixed_bytes().to_vec(),
+        block_number: block_number.as_u64() as ChainEpoch,
+        block_hash: block_hash.to_fixed_bytes().to_vec(),
+        gas_used: receipt.gas_used.map(|g| g.as_u64()).unwrap_or_default(),
+        effective_gas_price,
+        events,
+    })
+}
 
-    let max_priority_fee_per_gas = estimate_priority_fee(fee_history.reward); //overestimate?
-    let potential_max_fee = base_fee_surged(base_fee_per_gas);
-    let max_fee_per_gas = if max_priority_fee_per_gas > potential_max_fee {
-        max_priority_fee_per_gas + potential_max_fee
-    } else {
-        potential_max_fee
+/// Returns the top-down messages committed for `subnet_id` at `epoch` in the gateway at
+/// `gateway_addr`, reading the state of the parent at `block_hash`.
+pub(crate) async fn top_down_msgs_at<M: Middleware + 'static>(
+    gateway_addr: ethers::types::Address,
+    client: Arc<M>,
+    subnet_id: &SubnetID,
+    epoch: ChainEpoch,
+    block_hash: H256,
+) -> Result<Vec<CrossMsg>> {
+    let route = subnet_id_to_evm_addresses(subnet_id)?;
+    log::debug!("getting top down messages for route: {route:?}");
+
+    let subnet_id = gateway_getter_facet::SubnetID {
+        root: subnet_id.root_id(),
+        route,
     };
+    let gateway_contract = gateway_getter_facet::GatewayGetterFacet::new(gateway_addr, client);
 
-    Ok((max_priority_fee_per_gas, max_fee_per_gas))
-}
+    let call = gateway_contract
+        .get_top_down_msgs(subnet_id, U256::from(epoch))
+        .block(BlockId::from(block_hash));
+    let raw_msgs = call
+        .call()
+        .await
+        .map_err(|e| anyhow!("cannot get evm top down messages: {e:}"))?;
 
-/// Implementation borrowed from
-/// https://github.com/gakonst/ethers-rs/blob/ethers-v2.0.8/ethers-core/src/utils/mod.rs#L582
-/// Refer to the implementation for unit tests
-fn base_fee_surged(base_fee_per_gas: U256) -> U256 {
-    if base_fee_per_gas <= U256::from(40_000_000_000u64) {
-        base_fee_per_gas * 2
-    } else if base_fee_per_gas <= U256::from(100_000_000_000u64) {
-        base_fee_per_gas * 16 / 10
-    } else if base_fee_per_gas <= U256::from(200_000_000_000u64) {
-        base_fee_per_gas * 14 / 10
-    } else {
-        base_fee_per_gas * 12 / 10
+    let mut msgs = vec![];
+    for c in raw_msgs {
+        msgs.push(ipc_sdk::cross::CrossMsg::try_from(c)?);
     }
+    Ok(msgs)
 }
 
-/// Implementation borrowed from
-/// https://github.com/gakonst/ethers-rs/blob/ethers-v2.0.8/ethers-core/src/utils/mod.rs#L536
-/// Refer to the implementation for unit tests
-fn estimate_priority_fee(rewards: Vec<Vec<U256>>) -> U256 {
-    let mut rewards: Vec<U256> = rewards
-        .iter()
-        .map(|r| r[0])
-        .filter(|r| *r > U256::zero())
-        .collect();
-    if rewards.is_empty() {
-        return U256::zero();
-    }
-    if rewards.len() == 1 {
-        return rewards[0];
-    }
-    // Sort the rewards as we will eventually take the median.
-    rewards.sort();
-
-    // A copy of the same vector is created for convenience to calculate percentage change
-    // between subsequent fee values.
-    let mut rewards_copy = rewards.clone();
-    rewards_copy.rotate_left(1);
-
-    let mut percentage_change: Vec<I256> = rewards
-        .iter()
-        .zip(rewards_copy.iter())
-        .map(|(a, b)| {
-            let a = I256::try_from(*a).expect("priority fee overflow");
-            let b = I256::try_from(*b).expect("priority fee overflow");
-            ((b - a) * 100) / a
-        })
-        .collect();
-    percentage_change.pop();
-
-    // Fetch the max of the percentage change, and that element's index.
-    let max_change = percentage_change.iter().max().unwrap();
-    let max_change_index = percentage_change
-        .iter()
-        .position(|&c| c == *max_change)
-        .unwrap();
-
-    // If we encountered a big change in fees at a certain position, then consider only
-    // the values >= it.
-    let values = if *max_change >= ethers::utils::EIP1559_FEE_ESTIMATION_THRESHOLD_MAX_CHANGE.into()
-        && (max_change_index >= (rewards.len() / 2))
-    {
-        rewards[max_change_index..].to_vec()
-    } else {
-        rewards
-    };
-
-    // Return the median.
-    values[values.len() / 2]
+/// Whether the node failed a query because the queried height is a null round.
+fn is_null_round_error(e: &ProviderError) -> bool {
+    e.as_error_response()
+        .map_or(false, |e| e.message.contains("null round"))
 }
 
-/// Get the block number from the transaction receipt
-fn block_number_from_receipt(
-    receipt: Option<ethers::types::TransactionReceipt>,
-) -> Result<ChainEpoch> {
-    match receipt {
-        Some(r) => {
-            let block_number = r
-                .block_number
-                .ok_or_else(|| anyhow!("cannot get block number"))?;
-            Ok(block_number.as_u64() as ChainEpoch)
-        }
-        None => Err(anyhow!(
-            "txn sent to network, but receipt cannot be obtained, please check scanner"
-        )),
+/// Decodes a log emitted by the IPC contracts, returns `None` for any other log.
+pub(crate) fn ipc_event_from_log(log: ethers::types::Log) -> Result<Option<IpcEvent>> {
+    if let Ok(event) =
+        ethers_contract::parse_log::<subnet_registry::SubnetDeployedFilter>(log.clone())
+    {
+        return Ok(Some(IpcEvent::SubnetDeployed {
+            subnet_addr: ethers_address_to_fil_address(&event.subnet_addr)?,
+        }));
+    }
+    if let Ok(event) = ethers_contract::parse_log::<
+        lib_staking_change_log::NewStakingChangeRequestFilter,
+    >(log.clone())
+    {
+        return Ok(Some(IpcEvent::NewStakingChangeRequest(
+            StakingChangeRequest::try_from(event)?,
+        )));
     }
+    if let Ok(event) = ethers_contract::parse_log::<gateway_router_facet::QuorumReachedFilter>(log)
+    {
+        return Ok(Some(IpcEvent::QuorumReached(quorum_reached_event(event)?)));
+    }
+    Ok(None)
+}
+
+fn quorum_reached_event(
+    event: gateway_router_facet::QuorumReachedFilter,
+) -> Result<QuorumReachedEvent> {
+    Ok(QuorumReachedEvent {
+        height: event.height as ChainEpoch,
+        checkpoint: event.checkpoint.to_vec(),
+        quorum_weight: eth_to_fil_amount(&event.quorum_weight)?,
+    })
 }
 
 fn is_valid_bootstrap_addr(input: &str) -> Option<(String, IpAddr, u16)> {
@@ -1271,37 +2137,6 @@ fn is_valid_bootstrap_addr(input: &str) -> Option<(String, IpAddr, u16)> {
     None
 }
 
-/// This is a replacement for `Event::query_with_meta` in `ethers-contract`
-/// because in that one we don't get access to the `reverted` field, which
-/// we need to filteron in the currently deployed `1.25-rc4` version of Lotus.
-async fn query_with_meta<B, M, D>(
-    event: ethers::contract::Event<B, M, D>,
-    client: B,
-) -> Result<Vec<(D, LogMeta)>, ContractError<M>>
-where
-    B: Borrow<M>,
-    M: Middleware,
-    D: EthLogDecode,
-{
-    let logs = client
-        .borrow()
-        .get_logs(&event.filter)
-        .await
-        .map_err(ContractError::from_middleware_error)?;
-
-    let events = logs
-        .into_iter()
-        .filter(|l| !l.removed.unwrap_or_default())
-        .map(|log| {
-            let meta = LogMeta::from(&log);
-            let event = ethers::contract::parse_log::<D>(log)?;
-            Ok((event, meta))
-        })
-        .collect::<Result<_, ContractError<M>>>()?;
-
-    Ok(events)
-}
-
 fn into_genesis_balance_map(
     addrs: Vec<ethers::types::Address>,
     balances: Vec<ethers::types::U256>,
@@ -1345,10 +2180,188 @@ impl TryFrom<gateway_getter_facet::Subnet> for SubnetInfo {
 
 #[cfg(test)]
 mod tests {
-    use crate::manager::evm::manager::contract_address_from_subnet;
+    use crate::config::subnet::TxType;
+    use crate::manager::evm::manager::{contract_address_from_subnet, EthSubnetManager};
+    use crate::manager::evm::transport::{FailoverTransport, RetryTransport};
+    use crate::manager::evm::{
+        FixedGas, GasFees, IpcContractError, MaxFeeCeiling, TxRevertedError,
+    };
+    use crate::manager::subnet::{NullRoundError, TopDownFinalityQuery, TxResult};
+    use crate::manager::SubnetManager;
+    use crate::retry::RetryPolicy;
+    use crate::testing::rpc_endpoint;
+    use ethers::abi::Token;
+    use ethers::contract::EthEvent;
+    use ethers::providers::{Http, Provider};
+    use ethers::types::{Block, Log, Transaction, TransactionReceipt, H256, U64};
+    use ethers::utils::id;
     use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_actors_abis::lib_staking_change_log;
+    use ipc_sdk::address::IPCAddress;
+    use ipc_sdk::cross::CrossMsgBuilder;
+    use ipc_sdk::ethers_address_to_fil_address;
+    use ipc_sdk::evm::fil_to_eth_amount;
     use ipc_sdk::subnet_id::SubnetID;
+    use serde_json::{json, Value};
     use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use url::Url;
+
+    /// An evm subnet manager sending its requests to `endpoint`, without retries.
+    fn subnet_manager(endpoint: Url) -> EthSubnetManager {
+        let transport =
+            FailoverTransport::new(vec![Http::new(endpoint)], Duration::from_secs(5)).unwrap();
+        let provider = Provider::new(RetryTransport::new(transport, RetryPolicy::no_retries()));
+        EthSubnetManager::new(
+            ethers::types::Address::repeat_byte(1),
+            ethers::types::Address::repeat_byte(2),
+            31415926,
+            provider,
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reverted_transaction_is_an_error() {
+        let manager = subnet_manager(
+            rpc_endpoint(|method, _| match method {
+                "eth_getTransactionByHash" => json!({"result": {
+                    "hash": format!("{:?}", H256::repeat_byte(7)),
+                    "nonce": "0x0",
+                    "blockHash": format!("{:?}", H256::repeat_byte(8)),
+                    "blockNumber": "0x5",
+                    "transactionIndex": "0x0",
+                    "from": "0x0101010101010101010101010101010101010101",
+                    "to": "0x0202020202020202020202020202020202020202",
+                    "value": "0x0",
+                    "gasPrice": "0x1",
+                    "gas": "0x5208",
+                    "input": "0x",
+                    "v": "0x1b",
+                    "r": "0x1",
+                    "s": "0x1"
+                }}),
+                // replaying the transaction reverts with the same error.
+                "eth_call" => json!({"error": {
+                    "code": 3,
+                    "message": "execution reverted",
+                    "data": format!("0x{}", hex::encode(id("NotEnoughCollateral()")))
+                }}),
+                _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+            })
+            .await,
+        );
+
+        let receipt = TransactionReceipt {
+            transaction_hash: H256::repeat_byte(7),
+            block_hash: Some(H256::repeat_byte(8)),
+            block_number: Some(5.into()),
+            status: Some(0.into()),
+            ..Default::default()
+        };
+        let err = manager.outcome_from_receipt(receipt).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<TxRevertedError>().unwrap(),
+            TxRevertedError {
+                tx_hash: H256::repeat_byte(7),
+                block_number: 5,
+                reason: Some(IpcContractError::NotEnoughCollateral),
+            }
+        );
+
+        let receipt = TransactionReceipt {
+            transaction_hash: H256::repeat_byte(7),
+            block_hash: Some(H256::repeat_byte(8)),
+            block_number: Some(5.into()),
+            status: Some(1.into()),
+            ..Default::default()
+        };
+        let outcome = manager.outcome_from_receipt(receipt).await.unwrap();
+        assert_eq!(outcome.block_number, 5);
+    }
+
+    #[tokio::test]
+    async fn test_cross_message_pays_value_and_fee() {
+        let manager = subnet_manager(
+            rpc_endpoint(|method, _| match method {
+                "eth_estimateGas" => json!({"result": "0x5208"}),
+                "eth_getTransactionCount" => json!({"result": "0x0"}),
+                "eth_chainId" => json!({"result": "0x1df5e76"}),
+                _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+            })
+            .await,
+        )
+        .with_tx_type(TxType::Eip1559)
+        .with_gas_strategy(Arc::new(FixedGas(GasFees {
+            max_priority_fee_per_gas: 1.into(),
+            max_fee_per_gas: 2.into(),
+        })))
+        .with_offline_signing(true);
+
+        let gateway =
+            ethers_address_to_fil_address(&ethers::types::Address::repeat_byte(1)).unwrap();
+        let from = ethers_address_to_fil_address(&ethers::types::Address::repeat_byte(3)).unwrap();
+        let parent = SubnetID::new(31415926, vec![]);
+        let subnet = SubnetID::new_from_parent(&parent, Address::new_id(1001));
+        let to = IPCAddress::new(&parent, &Address::new_id(100)).unwrap();
+        let cross_msg = CrossMsgBuilder::new(to)
+            .value(TokenAmount::from_whole(2))
+            .fee(TokenAmount::from_whole(1))
+            .build(IPCAddress::new(&subnet, &from).unwrap())
+            .unwrap();
+
+        let result = manager
+            .send_cross_message(gateway, from, cross_msg)
+            .await
+            .unwrap();
+        let TxResult::Unsigned(unsigned) = result else {
+            panic!("cross message was not built to be signed");
+        };
+        assert_eq!(
+            unsigned.tx.value(),
+            Some(&fil_to_eth_amount(&TokenAmount::from_whole(3)).unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_fees_above_ceiling() {
+        let manager = subnet_manager(
+            rpc_endpoint(|method, _| match method {
+                "eth_call" => json!({"result": "0x"}),
+                "eth_estimateGas" => json!({"result": "0x5208"}),
+                "eth_getTransactionCount" => json!({"result": "0x0"}),
+                "eth_chainId" => json!({"result": "0x1df5e76"}),
+                _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+            })
+            .await,
+        )
+        .with_tx_type(TxType::Eip1559)
+        .with_gas_strategy(Arc::new(MaxFeeCeiling {
+            inner: Arc::new(FixedGas(GasFees {
+                max_priority_fee_per_gas: 1.into(),
+                max_fee_per_gas: 20.into(),
+            })),
+            ceiling: 10.into(),
+        }))
+        .with_dry_run(true);
+
+        let subnet = SubnetID::new_from_parent(
+            &SubnetID::new(31415926, vec![]),
+            ethers_address_to_fil_address(&ethers::types::Address::repeat_byte(4)).unwrap(),
+        );
+        let from = ethers_address_to_fil_address(&ethers::types::Address::repeat_byte(3)).unwrap();
+
+        let result = manager.leave_subnet(subnet, from).await.unwrap();
+        let TxResult::Simulated(report) = result else {
+            panic!("leave was not simulated");
+        };
+        assert_eq!(report.max_fee_per_gas, 20.into());
+        assert_eq!(report.gas, Some(0x5208.into()));
+        assert_eq!(report.gas_ceiling_exceeded.unwrap().ceiling, 10.into());
+    }
 
     #[test]
     fn test_agent_subnet_to_evm_address() {
@@ -1361,4 +2374,223 @@ mod tests {
             "0x2e714a3c385ea88a09998ed74db265dae9853667"
         );
     }
+
+    /// A `NewStakingChangeRequest` log with configuration number `nonce`.
+    fn staking_change_log(height: u64, index: u64, block_hash: H256, nonce: u64) -> Value {
+        let data = ethers::abi::encode(&[
+            Token::Uint(0.into()),
+            Token::Address(ethers::types::Address::repeat_byte(3)),
+            Token::Bytes(vec![]),
+            Token::Uint(nonce.into()),
+        ]);
+        serde_json::to_value(Log {
+            topics: vec![lib_staking_change_log::NewStakingChangeRequestFilter::signature()],
+            data: data.into(),
+            block_number: Some(height.into()),
+            block_hash: Some(block_hash),
+            transaction_hash: Some(H256::repeat_byte(9)),
+            transaction_index: Some(U64::zero()),
+            log_index: Some(index.into()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    /// Answers the queries of the validator changes in `0..=1500`, in pages of 1000 blocks:
+    /// two changes at height 5 and one at 7 in the first page, one at 1200 in the second.
+    /// With `reorged`, the block at 7 has another hash when queried after the logs.
+    fn staking_changes_rpc(method: &str, params: &Value, reorged: bool) -> Value {
+        let block_hash = H256::from_low_u64_be;
+        match method {
+            "eth_getLogs" => match params[0]["fromBlock"].as_str() {
+                Some("0x0") => json!({"result": [
+                    staking_change_log(5, 0, block_hash(5), 1),
+                    staking_change_log(5, 1, block_hash(5), 2),
+                    staking_change_log(7, 0, block_hash(7), 3),
+                ]}),
+                Some("0x3e8") => json!({"result": [
+                    staking_change_log(1200, 0, block_hash(1200), 4),
+                ]}),
+                _ => json!({"result": []}),
+            },
+            "eth_getBlockByNumber" => {
+                let height = params[0].as_str().unwrap().trim_start_matches("0x");
+                let height = u64::from_str_radix(height, 16).unwrap();
+                let hash = if reorged && height == 7 {
+                    H256::repeat_byte(0xff)
+                } else {
+                    block_hash(height)
+                };
+                json!({"result": Block::<H256> {
+                    hash: Some(hash),
+                    number: Some(height.into()),
+                    ..Default::default()
+                }})
+            }
+            _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validator_changeset_range() {
+        let addr = Address::from_str("f410ffzyuupbyl2uiucmzr3lu3mtf3luyknthaz4xsrq").unwrap();
+        let subnet = SubnetID::new(0, vec![addr]);
+
+        let manager = subnet_manager(rpc_endpoint(|m, p| staking_changes_rpc(m, p, false)).await);
+        let changes = manager
+            .get_validator_changeset_range(&subnet, 0, 1500)
+            .await
+            .unwrap();
+
+        // the changes of both pages are grouped by height.
+        let heights = changes
+            .iter()
+            .map(|c| (c.height, c.changes.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![(5, 2), (7, 1), (1200, 1)]);
+        assert_eq!(changes[1].block_hash, H256::from_low_u64_be(7).0.to_vec());
+        let nonces = changes
+            .iter()
+            .flat_map(|c| c.changes.iter().map(|r| r.configuration_number))
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, vec![1, 2, 3, 4]);
+
+        // a block reorganized after its logs were fetched fails the query.
+        let manager = subnet_manager(rpc_endpoint(|m, p| staking_changes_rpc(m, p, true)).await);
+        let err = manager
+            .get_validator_changeset_range(&subnet, 0, 1500)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("changed while querying"));
+    }
+
+    /// Answers the queries of the top-down messages in `1..=4`, where 3 is a null round
+    /// and no height has messages. With `reorged`, the block at 4 is not a child of 2.
+    fn top_down_msgs_rpc(method: &str, params: &Value, reorged: bool) -> Value {
+        let block_hash = H256::from_low_u64_be;
+        match method {
+            "eth_getBlockByNumber" => {
+                let height = params[0].as_str().unwrap().trim_start_matches("0x");
+                let height = u64::from_str_radix(height, 16).unwrap();
+                let parent_hash = match height {
+                    3 => {
+                        return json!({"error": {
+                            "code": 1,
+                            "message": "requested epoch was a null round"
+                        }})
+                    }
+                    4 if reorged => H256::repeat_byte(0xff),
+                    4 => block_hash(2),
+                    height => block_hash(height - 1),
+                };
+                json!({"result": Block::<H256> {
+                    hash: Some(block_hash(height)),
+                    parent_hash,
+                    number: Some(height.into()),
+                    ..Default::default()
+                }})
+            }
+            "eth_call" => json!({
+                "result": format!("0x{}", hex::encode(ethers::abi::encode(&[Token::Array(vec![])])))
+            }),
+            _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_top_down_msgs_range() {
+        let addr = Address::from_str("f410ffzyuupbyl2uiucmzr3lu3mtf3luyknthaz4xsrq").unwrap();
+        let subnet = SubnetID::new(0, vec![addr]);
+
+        // the messages are only queried at the blocks produced.
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let manager = subnet_manager(
+            rpc_endpoint(move |m, p| {
+                if m == "eth_call" {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                }
+                top_down_msgs_rpc(m, p, false)
+            })
+            .await,
+        );
+        let msgs = manager
+            .get_top_down_msgs_range(&subnet, 1, 4)
+            .await
+            .unwrap();
+        assert!(msgs.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let err = manager.get_block_hash(3).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<NullRoundError>().unwrap(),
+            NullRoundError { height: 3 }
+        );
+
+        let manager = subnet_manager(rpc_endpoint(|m, p| top_down_msgs_rpc(m, p, true)).await);
+        let err = manager
+            .get_top_down_msgs_range(&subnet, 1, 4)
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("not a child of the previous block"));
+    }
+
+    /// A transaction with hash `hash` from the address of `sender` to the one of `contract`.
+    fn transaction(hash: u64, sender: u8, contract: u8) -> Transaction {
+        Transaction {
+            hash: H256::from_low_u64_be(hash),
+            from: ethers::types::Address::repeat_byte(sender),
+            to: Some(ethers::types::Address::repeat_byte(contract)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_count_txs_in_blocks() {
+        // block 1 has a matching transaction and one from another sender, block 2 a
+        // failed matching transaction and block 3 one to another contract.
+        let receipts = Arc::new(AtomicUsize::new(0));
+        let counter = receipts.clone();
+        let manager = subnet_manager(
+            rpc_endpoint(move |method, params| match method {
+                "eth_getBlockByNumber" => {
+                    let height = params[0].as_str().unwrap().trim_start_matches("0x");
+                    let height = u64::from_str_radix(height, 16).unwrap();
+                    let transactions = match height {
+                        1 => vec![transaction(1, 3, 1), transaction(2, 4, 1)],
+                        2 => vec![transaction(3, 3, 1)],
+                        3 => vec![transaction(4, 3, 2)],
+                        _ => vec![],
+                    };
+                    json!({"result": Block::<Transaction> {
+                        hash: Some(H256::from_low_u64_be(height)),
+                        number: Some(height.into()),
+                        transactions,
+                        ..Default::default()
+                    }})
+                }
+                "eth_getTransactionReceipt" => {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let hash: H256 = serde_json::from_value(params[0].clone()).unwrap();
+                    json!({"result": TransactionReceipt {
+                        transaction_hash: hash,
+                        status: Some(U64::from(u64::from(hash == H256::from_low_u64_be(1)))),
+                        ..Default::default()
+                    }})
+                }
+                _ => json!({"error": {"code": -32601, "message": "method not found"}}),
+            })
+            .await,
+        );
+
+        let count = manager
+            .count_txs_in_blocks(&transaction(5, 3, 1), 0, 4, |_| true)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        // only the receipts of the matching transactions are queried.
+        assert_eq!(receipts.load(Ordering::SeqCst), 2);
+    }
 }
@@ -1,14 +1,39 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 
+mod dry_run;
+mod error;
+mod events;
+mod gas;
 mod manager;
+mod nonce;
+mod offline;
+mod signer;
+mod subscription;
+mod transport;
 
 use async_trait::async_trait;
 use fvm_shared::clock::ChainEpoch;
 use ipc_sdk::subnet_id::SubnetID;
 
 use super::subnet::SubnetManager;
+pub use dry_run::DryRunReport;
+pub use error::{IpcContractError, TxRevertedError, TxTimeoutError};
+pub use events::{
+    decode_log, decode_logs, query_with_meta, IpcEventFilter, IpcEventQuery, IpcLog, IpcLogStream,
+};
+pub use gas::{
+    gas_strategy_from_config, BaseFeeMultiplier, Eip1559Estimator, FeeHistoryPercentile, FixedGas,
+    GasCeilingExceeded, GasFees, GasStrategy, MaxFeeCap, MaxFeeCeiling,
+};
 pub use manager::EthSubnetManager;
+pub use nonce::NonceManager;
+pub use offline::{SignedTransaction, UnsignedTransaction};
+pub use signer::{EthSigner, EthSignerError, TransactionSigner, Web3Signer};
+pub use subscription::{EventSubscriber, EventSubscription, IpcEventStream};
+pub use transport::{
+    EndpointStatus, EthTransport, FailoverError, FailoverTransport, RetryTransport,
+};
 
 use ipc_actors_abis::subnet_actor_manager_facet;
 
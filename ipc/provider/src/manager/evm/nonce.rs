@@ -0,0 +1,103 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Local allocation of transaction nonces.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use ethers::providers::Middleware;
+use ethers::types::{Address, BlockNumber, U256};
+use tokio::sync::Mutex;
+
+/// Allocates the nonces of the transactions sent by each signer on each chain.
+///
+/// The nonce of a signer is read from `eth_getTransactionCount` (including pending
+/// transactions) the first time it is needed, and allocated locally from then on, so
+/// that consecutive transactions from the same signer do not race for the same nonce
+/// while the previous ones are still in the mempool. When sending a transaction fails,
+/// the signer is resynced so that the next allocation reads the nonce from the chain again.
+///
+/// The manager is cheap to clone and clones share the same nonces.
+#[derive(Clone, Default)]
+pub struct NonceManager {
+    nonces: Arc<Mutex<HashMap<(u64, Address), U256>>>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the next nonce of `signer` on the chain `chain_id`, querying `client`
+    /// if the nonce of the signer is not known yet.
+    pub async fn next<M: Middleware>(
+        &self,
+        client: &M,
+        chain_id: u64,
+        signer: Address,
+    ) -> Result<U256> {
+        let mut nonces = self.nonces.lock().await;
+
+        let nonce = match nonces.get(&(chain_id, signer)) {
+            Some(nonce) => *nonce,
+            None => {
+                let nonce = client
+                    .get_transaction_count(signer, Some(BlockNumber::Pending.into()))
+                    .await
+                    .map_err(|e| anyhow!("cannot get transaction count of {signer:?}: {e}"))?;
+                log::debug!("synced nonce of {signer:?} on chain {chain_id}: {nonce}");
+                nonce
+            }
+        };
+
+        nonces.insert((chain_id, signer), nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Forgets the nonce allocated for `signer` on `chain_id`, so that it is read from
+    /// the chain on the next allocation.
+    pub async fn resync(&self, chain_id: u64, signer: Address) {
+        log::debug!("resyncing nonce of {signer:?} on chain {chain_id}");
+        self.nonces.lock().await.remove(&(chain_id, signer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::providers::Provider;
+    use ethers::types::{Address, U256};
+
+    use super::NonceManager;
+
+    #[tokio::test]
+    async fn test_allocates_and_resyncs_nonces() {
+        let (provider, mock) = Provider::mocked();
+        let signer = Address::repeat_byte(1);
+        let manager = NonceManager::new();
+
+        mock.push(U256::from(7)).unwrap();
+        assert_eq!(
+            manager.next(&provider, 1, signer).await.unwrap(),
+            U256::from(7)
+        );
+        assert_eq!(
+            manager.next(&provider, 1, signer).await.unwrap(),
+            U256::from(8)
+        );
+
+        // a different chain is tracked separately
+        mock.push(U256::from(0)).unwrap();
+        assert_eq!(
+            manager.next(&provider, 2, signer).await.unwrap(),
+            U256::from(0)
+        );
+
+        manager.resync(1, signer).await;
+        mock.push(U256::from(20)).unwrap();
+        assert_eq!(
+            manager.next(&provider, 1, signer).await.unwrap(),
+            U256::from(20)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Transactions built on a connected machine, signed on an offline one and broadcast back
+//! from the connected machine.
+
+use std::fmt::{Display, Formatter};
+
+use anyhow::{anyhow, Result};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::{Address, Bytes, H256};
+use serde::{Deserialize, Serialize};
+
+/// A transaction ready to be signed, with its nonce, gas and fees already set.
+///
+/// When the manager runs in offline-signing mode, mutating operations return the unsigned
+/// transaction as a `TxResult::Unsigned` instead of sending it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsignedTransaction {
+    /// The contract method (or plain transfer) called by the transaction.
+    pub method: String,
+    pub chain_id: u64,
+    pub tx: TypedTransaction,
+}
+
+impl UnsignedTransaction {
+    /// The address that must sign the transaction.
+    pub fn from(&self) -> Result<Address> {
+        self.tx
+            .from()
+            .copied()
+            .ok_or_else(|| anyhow!("transaction has no sender"))
+    }
+
+    /// Signs the transaction with `wallet`, which must hold the key of the sender.
+    pub fn sign(&self, wallet: &LocalWallet) -> Result<SignedTransaction> {
+        let from = self.from()?;
+        if wallet.address() != from {
+            return Err(anyhow!(
+                "transaction sent from {from:?} cannot be signed by {:?}",
+                wallet.address()
+            ));
+        }
+        if self.tx.nonce().is_none() || self.tx.gas().is_none() {
+            return Err(anyhow!("transaction has no nonce or gas limit"));
+        }
+
+        let mut tx = self.tx.clone();
+        tx.set_chain_id(self.chain_id);
+        let signature = wallet
+            .clone()
+            .with_chain_id(self.chain_id)
+            .sign_transaction_sync(&tx)?;
+
+        Ok(SignedTransaction {
+            method: self.method.clone(),
+            chain_id: self.chain_id,
+            from,
+            tx_hash: tx.hash(&signature),
+            raw: tx.rlp_signed(&signature),
+        })
+    }
+}
+
+impl Display for UnsignedTransaction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "unsigned transaction for {}", self.method)?;
+        match self.tx.from() {
+            Some(from) => writeln!(f, "  from: {from:?}")?,
+            None => writeln!(f, "  from: n/a")?,
+        }
+        match self.tx.nonce() {
+            Some(nonce) => writeln!(f, "  nonce: {nonce}")?,
+            None => writeln!(f, "  nonce: n/a")?,
+        }
+        match self.tx.gas() {
+            Some(gas) => writeln!(f, "  gas: {gas}")?,
+            None => writeln!(f, "  gas: n/a")?,
+        }
+        write!(f, "  chain id: {}", self.chain_id)
+    }
+}
+
+/// A transaction signed offline, ready to be broadcast with `eth_sendRawTransaction`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTransaction {
+    /// The contract method (or plain transfer) called by the transaction.
+    pub method: String,
+    pub chain_id: u64,
+    pub from: Address,
+    pub tx_hash: H256,
+    /// The RLP encoding of the signed transaction.
+    pub raw: Bytes,
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, Eip1559TransactionRequest, Transaction};
+
+    use super::UnsignedTransaction;
+
+    #[test]
+    fn test_sign_unsigned_transaction() {
+        let wallet = LocalWallet::new(&mut rand::thread_rng());
+        let tx = Eip1559TransactionRequest::new()
+            .from(wallet.address())
+            .to(Address::from_low_u64_be(100))
+            .value(1000)
+            .nonce(7)
+            .gas(21000)
+            .max_fee_per_gas(100)
+            .max_priority_fee_per_gas(10);
+        let unsigned = UnsignedTransaction {
+            method: "transfer".to_string(),
+            chain_id: 31415926,
+            tx: TypedTransaction::Eip1559(tx),
+        };
+
+        // the unsigned transaction is written to and read from a file.
+        let json = serde_json::to_string(&unsigned).unwrap();
+        let unsigned: UnsignedTransaction = serde_json::from_str(&json).unwrap();
+
+        let other = LocalWallet::new(&mut rand::thread_rng());
+        assert!(unsigned.sign(&other).is_err());
+
+        let signed = unsigned.sign(&wallet).unwrap();
+        let decoded = ethers::utils::rlp::decode::<Transaction>(&signed.raw).unwrap();
+        assert_eq!(decoded.hash, signed.tx_hash);
+        assert_eq!(decoded.recover_from().unwrap(), wallet.address());
+        assert_eq!(decoded.nonce, 7.into());
+        assert_eq!(decoded.chain_id, Some(31415926.into()));
+    }
+}
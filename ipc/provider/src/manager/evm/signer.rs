@@ -0,0 +1,246 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! The signers of the transactions sent by the [`EthSubnetManager`](super::EthSubnetManager),
+//! backed by the local keystore or by a remote signing service.
+
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::transaction::eip2718::TypedTransaction;
+use ethers::types::transaction::eip712::Eip712;
+use ethers::types::{Address, Bytes, Signature};
+use ethers::utils::rlp::Rlp;
+use serde_json::json;
+use url::Url;
+
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+
+/// Signs the transactions of an address.
+#[async_trait]
+pub trait TransactionSigner: Debug + Send + Sync {
+    /// The address whose transactions are signed.
+    fn address(&self) -> Address;
+
+    /// Signs `tx`, which has its chain id set.
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature>;
+}
+
+/// Signs with a private key of the local keystore.
+#[async_trait]
+impl TransactionSigner for LocalWallet {
+    fn address(&self) -> Address {
+        Signer::address(self)
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        Ok(self.sign_transaction_sync(tx)?)
+    }
+}
+
+/// Signs with a remote service implementing the `eth_signTransaction` method of the
+/// Web3Signer JSON-RPC API, so that the private key is never held by the client.
+pub struct Web3Signer {
+    address: Address,
+    url: Url,
+    client: JsonRpcClientImpl,
+}
+
+impl Web3Signer {
+    pub fn new(address: Address, url: Url, auth_token: Option<&str>) -> Self {
+        Self {
+            address,
+            client: JsonRpcClientImpl::new(url.clone(), auth_token),
+            url,
+        }
+    }
+}
+
+impl Debug for Web3Signer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Web3Signer")
+            .field("address", &self.address)
+            .field("url", &self.url.as_str())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl TransactionSigner for Web3Signer {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+
+        // the service returns the RLP encoding of the signed transaction.
+        let raw: Bytes = self
+            .client
+            .request("eth_signTransaction", json!([tx]))
+            .await
+            .map_err(|e| anyhow!("remote signer at {} failed: {e:#}", self.url))?;
+        let (_, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw))
+            .map_err(|e| anyhow!("cannot decode transaction signed remotely: {e}"))?;
+
+        // the signature must be of the transaction we built, a misbehaving service must
+        // not get a different transaction sent on our behalf.
+        let signer = signature.recover(tx.sighash())?;
+        if signer != self.address {
+            return Err(anyhow!(
+                "remote signer at {} did not sign the transaction of {:?}",
+                self.url,
+                self.address
+            ));
+        }
+        Ok(signature)
+    }
+}
+
+/// The ethers signer of the transactions of an address, see [`TransactionSigner`].
+///
+/// Without a signer for the address, it can still be used to build the transactions of
+/// the address, which are then signed offline, see `UnsignedTransaction`.
+#[derive(Debug, Clone)]
+pub struct EthSigner {
+    address: Address,
+    chain_id: u64,
+    signer: Option<Arc<dyn TransactionSigner>>,
+}
+
+impl EthSigner {
+    pub fn new(signer: Arc<dyn TransactionSigner>, chain_id: u64) -> Self {
+        Self {
+            address: signer.address(),
+            chain_id,
+            signer: Some(signer),
+        }
+    }
+
+    /// A signer for an address whose transactions are signed offline.
+    pub fn offline(address: Address, chain_id: u64) -> Self {
+        Self {
+            address,
+            chain_id,
+            signer: None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum EthSignerError {
+    #[error(transparent)]
+    Signer(#[from] anyhow::Error),
+    #[error("the private key of {0:?} is not available, the transaction must be signed offline")]
+    Offline(Address),
+    #[error("{0} is not supported by the transaction signers")]
+    Unsupported(&'static str),
+}
+
+#[async_trait]
+impl Signer for EthSigner {
+    type Error = EthSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        _message: S,
+    ) -> Result<Signature, Self::Error> {
+        Err(EthSignerError::Unsupported("signing messages"))
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        let Some(signer) = &self.signer else {
+            return Err(EthSignerError::Offline(self.address));
+        };
+
+        let mut tx = tx.clone();
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+        Ok(signer.sign_transaction(&tx).await?)
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        _payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        Err(EthSignerError::Unsupported("signing typed data"))
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    fn with_chain_id<T: Into<u64>>(mut self, chain_id: T) -> Self {
+        self.chain_id = chain_id.into();
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::transaction::eip2718::TypedTransaction;
+    use ethers::types::{Address, Eip1559TransactionRequest};
+    use serde_json::json;
+    use url::Url;
+
+    use super::{EthSigner, Web3Signer};
+    use crate::testing::rpc_endpoint;
+
+    /// Starts a stand-in Web3Signer service that signs every transaction with `wallet`.
+    async fn web3signer_endpoint(wallet: LocalWallet) -> Url {
+        rpc_endpoint(move |_, params| {
+            let tx: TypedTransaction = serde_json::from_value(params[0].clone()).unwrap();
+            let signature = wallet.sign_transaction_sync(&tx).unwrap();
+            json!({ "result": tx.rlp_signed(&signature) })
+        })
+        .await
+    }
+
+    fn transfer(from: Address) -> TypedTransaction {
+        TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .from(from)
+                .to(Address::from_low_u64_be(100))
+                .value(1000)
+                .nonce(3)
+                .gas(21000)
+                .max_fee_per_gas(100)
+                .max_priority_fee_per_gas(10),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_web3signer() {
+        let chain_id = 31415926;
+        let wallet = LocalWallet::new(&mut rand::thread_rng()).with_chain_id(chain_id);
+        let url = web3signer_endpoint(wallet.clone()).await;
+
+        let signer = EthSigner::new(
+            Arc::new(Web3Signer::new(wallet.address(), url.clone(), None)),
+            chain_id,
+        );
+        let mut tx = transfer(wallet.address());
+        let signature = signer.sign_transaction(&tx).await.unwrap();
+        tx.set_chain_id(chain_id);
+        assert_eq!(signature.recover(tx.sighash()).unwrap(), wallet.address());
+
+        // a signature by any other key is rejected
+        let other = Web3Signer::new(Address::from_low_u64_be(1), url, None);
+        let signer = EthSigner::new(Arc::new(other), chain_id);
+        assert!(signer.sign_transaction(&tx).await.is_err());
+
+        let offline = EthSigner::offline(wallet.address(), chain_id);
+        assert!(offline.sign_transaction(&tx).await.is_err());
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Subscriptions to the events of the IPC contracts through the websocket endpoint of an
+//! EVM subnet.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_channel::Sender;
+use ethers::providers::{Authorization, ConnectionDetails, Middleware, Provider, Ws};
+use ethers::types::{Address, BlockNumber, Log, U256, U64};
+use futures_util::{Stream, StreamExt};
+use fvm_shared::clock::ChainEpoch;
+use ipc_sdk::evm::payload_to_evm_address;
+use ipc_sdk::subnet_id::SubnetID;
+use url::Url;
+
+use crate::config::subnet::SubnetConfig;
+use crate::config::Subnet;
+use crate::manager::evm::events::{
+    decode_log, IpcEventFilter, IpcEventQuery, IpcLog, IpcLogStream,
+};
+use crate::manager::evm::manager::top_down_msgs_at;
+use crate::manager::subnet::IpcEvent;
+use crate::retry::RetryPolicy;
+
+/// The number of events buffered for a slow consumer before the subscription waits.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+/// The maximum number of missed parent blocks that are checked for top-down messages
+/// after a reconnection.
+const MAX_TOP_DOWN_BACKFILL: u64 = 1000;
+
+/// A stream of IPC events, see [`EventSubscriber::subscribe`].
+pub type IpcEventStream = Pin<Box<dyn Stream<Item = Result<IpcEvent>> + Send>>;
+
+/// The events to subscribe to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventSubscription {
+    /// `QuorumReached` events of the gateway.
+    QuorumReached,
+    /// `NewStakingChangeRequest` events of the subnet actor of a child subnet.
+    NewStakingChangeRequest(SubnetID),
+    /// `SubnetDeployed` events of the registry.
+    SubnetDeployed,
+    /// The top-down messages committed for a child subnet, checked at every new block.
+    NewTopDownMessages(SubnetID),
+}
+
+/// Subscribes to the events of the IPC contracts deployed in an EVM subnet.
+///
+/// Every subscription owns its websocket connection, which is re-established with an
+/// exponential backoff whenever it drops, for as long as the stream is being consumed.
+/// Log subscriptions resume from the last delivered log: as `eth_subscribe` only delivers
+/// new logs, the ones emitted while disconnected are queried with `eth_getLogs` first.
+#[derive(Debug, Clone)]
+pub struct EventSubscriber {
+    ws_url: Url,
+    auth_token: Option<String>,
+    gateway_addr: Address,
+    registry_addr: Address,
+    reconnect: RetryPolicy,
+}
+
+impl EventSubscriber {
+    pub fn new(ws_url: Url, gateway_addr: Address, registry_addr: Address) -> Self {
+        Self {
+            ws_url,
+            auth_token: None,
+            gateway_addr,
+            registry_addr,
+            reconnect: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_auth_token(mut self, auth_token: Option<String>) -> Self {
+        self.auth_token = auth_token;
+        self
+    }
+
+    /// Sets the backoff between reconnections. The retry limit of the policy is ignored,
+    /// the subscription keeps reconnecting while the stream is consumed.
+    pub fn with_reconnect_policy(mut self, reconnect: RetryPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn from_subnet(subnet: &Subnet) -> Result<Self> {
+        let SubnetConfig::Fevm(config) = &subnet.config else {
+            return Err(anyhow!("subnet {} is not an fevm subnet", subnet.id));
+        };
+        let ws_url = config
+            .provider_ws
+            .clone()
+            .ok_or_else(|| anyhow!("no provider_ws configured for subnet {}", subnet.id))?;
+
+        Ok(Self::new(
+            ws_url,
+            payload_to_evm_address(config.gateway_addr.payload())?,
+            payload_to_evm_address(config.registry_addr.payload())?,
+        )
+        .with_auth_token(subnet.auth_token())
+        .with_reconnect_policy(RetryPolicy::from_config(&subnet.retry_config())))
+    }
+
+    /// Subscribes to the events in `subscription`. The subscription runs in the background
+    /// until the returned stream is dropped. Errors decoding an event are yielded in the
+    /// stream, connection errors are logged and followed by a reconnection.
+    pub fn subscribe(&self, subscription: EventSubscription) -> Result<IpcEventStream> {
+        let filter = match subscription {
+            EventSubscription::NewTopDownMessages(subnet_id) => {
+                let (tx, rx) = async_channel::bounded(EVENT_CHANNEL_CAPACITY);
+                self.spawn(SubscriptionKind::TopDownMessages(subnet_id, tx));
+                return Ok(Box::pin(rx));
+            }
+            EventSubscription::QuorumReached => {
+                IpcEventFilter::new().quorum_reached(self.gateway_addr)
+            }
+            EventSubscription::NewStakingChangeRequest(subnet_id) => {
+                IpcEventFilter::new().staking_changes(&subnet_id)?
+            }
+            EventSubscription::SubnetDeployed => {
+                IpcEventFilter::new().subnet_deployed(self.registry_addr)
+            }
+        };
+        let events = self
+            .subscribe_logs(&filter, None)
+            .map(|log| log.map(|log| log.event));
+        Ok(Box::pin(events))
+    }
+
+    /// Subscribes to the events matching `filter`, with the metadata of their logs. With
+    /// `from`, the events emitted since that block are queried and delivered first.
+    pub fn subscribe_logs(
+        &self,
+        filter: &IpcEventFilter,
+        from: Option<ChainEpoch>,
+    ) -> IpcLogStream {
+        let (tx, rx) = async_channel::bounded(EVENT_CHANNEL_CAPACITY);
+        self.spawn(SubscriptionKind::Logs {
+            filter: filter.clone(),
+            from: from.map(|h| h as u64),
+            tx,
+        });
+        Box::pin(rx)
+    }
+
+    fn spawn(&self, kind: SubscriptionKind) {
+        let subscriber = self.clone();
+        tokio::spawn(async move { subscriber.run(kind).await });
+    }
+
+    async fn connect(&self) -> Result<Provider<Ws>> {
+        let auth = self.auth_token.clone().map(Authorization::Bearer);
+        let conn = ConnectionDetails::new(self.ws_url.as_str(), auth);
+        Provider::<Ws>::connect(conn)
+            .await
+            .with_context(|| format!("cannot connect to {}", self.ws_url))
+    }
+
+    async fn run(self, kind: SubscriptionKind) {
+        let mut cursor = Cursor::default();
+        let mut attempt = 0;
+
+        loop {
+            let result = match &kind {
+                SubscriptionKind::Logs { filter, from, tx } => {
+                    self.stream_logs(filter, *from, &mut cursor, &mut attempt, tx)
+                        .await
+                }
+                SubscriptionKind::TopDownMessages(subnet_id, tx) => {
+                    self.stream_top_down_msgs(subnet_id, &mut cursor, &mut attempt, tx)
+                        .await
+                }
+            };
+            if kind.is_closed() {
+                log::debug!(
+                    "event stream dropped, closing subscription to {}",
+                    self.ws_url
+                );
+                return;
+            }
+
+            attempt += 1;
+            let backoff = self.reconnect.max_backoff(attempt);
+            match result {
+                Ok(()) => log::warn!("subscription to {} closed", self.ws_url),
+                Err(e) => log::warn!("subscription to {} failed: {e:#}", self.ws_url),
+            }
+            log::info!(
+                "reconnecting to {} in {}ms (attempt {attempt})",
+                self.ws_url,
+                backoff.as_millis()
+            );
+            tokio::time::sleep(backoff).await;
+        }
+    }
+
+    /// Forwards the logs matching `filter` until the connection drops or the receiver is
+    /// closed. The logs emitted since the cursor, or since `from` on the first connection,
+    /// are queried up to the chain head before the new ones are forwarded.
+    async fn stream_logs(
+        &self,
+        filter: &IpcEventFilter,
+        from: Option<u64>,
+        cursor: &mut Cursor,
+        attempt: &mut u32,
+        tx: &Sender<Result<IpcLog>>,
+    ) -> Result<()> {
+        let provider = Arc::new(self.connect().await?);
+
+        // subscribe before querying the missed logs so that none is lost in between, the
+        // logs returned by both are skipped by the cursor.
+        let mut logs = provider.subscribe_logs(&filter.filter()).await?;
+        *attempt = 0;
+
+        let backfill_from = match (cursor.log, from) {
+            // the logs of `block` already delivered are skipped below.
+            (Some((block, _)), _) => Some(block.as_u64()),
+            (None, from) => from,
+        };
+        if let Some(start) = backfill_from {
+            let head = provider.get_block_number().await?.as_u64();
+            if head >= start {
+                // the poll interval is only used to tail the query.
+                let query = IpcEventQuery::new(provider.clone(), filter.clone(), Duration::ZERO);
+                for log in query.range(start as ChainEpoch, head as ChainEpoch).await? {
+                    if !cursor.advance(log.meta.block_number, log.meta.log_index) {
+                        continue;
+                    }
+                    if tx.send(Ok(log)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        while let Some(log) = logs.next().await {
+            if !cursor.advance_log(&log) {
+                continue;
+            }
+            let event = match decode_log(log) {
+                Ok(Some(event)) => Ok(event),
+                Ok(None) => continue,
+                Err(e) => Err(e),
+            };
+            if tx.send(event).await.is_err() {
+                return Ok(());
+            }
+        }
+        Ok(())
+    }
+
+    /// Queries the top-down messages of `subnet_id` at every new block, pinned to the block
+    /// hash, until the connection drops or the receiver is closed. Only blocks with messages
+    /// yield an event.
+    async fn stream_top_down_msgs(
+        &self,
+        subnet_id: &SubnetID,
+        cursor: &mut Cursor,
+        attempt: &mut u32,
+        tx: &Sender<Result<IpcEvent>>,
+    ) -> Result<()> {
+        let provider = Arc::new(self.connect().await?);
+        let mut blocks = provider.subscribe_blocks().await?;
+        *attempt = 0;
+
+        while let Some(block) = blocks.next().await {
+            let (Some(number), Some(hash)) = (block.number, block.hash) else {
+                continue;
+            };
+            let number = number.as_u64();
+
+            // check the blocks missed while disconnected or skipped by the endpoint.
+            let from = match cursor.height {
+                Some(height) if height >= number => continue,
+                Some(height) => (height + 1).max(number.saturating_sub(MAX_TOP_DOWN_BACKFILL)),
+                None => number,
+            };
+            for height in from..=number {
+                let block_hash = if height == number {
+                    hash
+                } else {
+                    provider
+                        .get_block(BlockNumber::Number(U64::from(height)))
+                        .await?
+                        .and_then(|b| b.hash)
+                        .ok_or_else(|| anyhow!("cannot get block hash at {height}"))?
+                };
+
+                let msgs = top_down_msgs_at(
+                    self.gateway_addr,
+                    provider.clone(),
+                    subnet_id,
+                    height as ChainEpoch,
+                    block_hash,
+                )
+                .await?;
+                cursor.height = Some(height);
+
+                if msgs.is_empty() {
+                    continue;
+                }
+                let event = IpcEvent::NewTopDownMessages {
+                    subnet_id: subnet_id.clone(),
+                    height: height as ChainEpoch,
+                    block_hash: block_hash.to_fixed_bytes().to_vec(),
+                    msgs,
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum SubscriptionKind {
+    Logs {
+        filter: IpcEventFilter,
+        from: Option<u64>,
+        tx: Sender<Result<IpcLog>>,
+    },
+    TopDownMessages(SubnetID, Sender<Result<IpcEvent>>),
+}
+
+impl SubscriptionKind {
+    /// Whether the stream of the subscription was dropped.
+    fn is_closed(&self) -> bool {
+        match self {
+            SubscriptionKind::Logs { tx, .. } => tx.is_closed(),
+            SubscriptionKind::TopDownMessages(_, tx) => tx.is_closed(),
+        }
+    }
+}
+
+/// The position of a subscription, kept across reconnections.
+#[derive(Debug, Default)]
+struct Cursor {
+    /// The block number and log index of the last delivered log.
+    log: Option<(U64, U256)>,
+    /// The last block checked for top-down messages.
+    height: Option<u64>,
+}
+
+impl Cursor {
+    /// Moves the cursor to `log`, returns false if the log was already delivered or has
+    /// been removed by a reorg.
+    fn advance_log(&mut self, log: &Log) -> bool {
+        if log.removed.unwrap_or_default() {
+            return false;
+        }
+        let (Some(block), Some(index)) = (log.block_number, log.log_index) else {
+            return true;
+        };
+        self.advance(block, index)
+    }
+
+    /// Moves the cursor to the log at `index` in `block`, returns false if it was already
+    /// delivered.
+    fn advance(&mut self, block: U64, index: U256) -> bool {
+        if let Some(last) = self.log {
+            if (block, index) <= last {
+                return false;
+            }
+        }
+        self.log = Some((block, index));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::{Log, U256, U64};
+
+    use super::Cursor;
+
+    fn log_at(block: u64, index: u64) -> Log {
+        Log {
+            block_number: Some(U64::from(block)),
+            log_index: Some(U256::from(index)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cursor_skips_delivered_logs() {
+        let mut cursor = Cursor::default();
+        assert!(cursor.advance_log(&log_at(10, 0)));
+        assert!(cursor.advance_log(&log_at(10, 1)));
+        // resubscribing from block 10 replays its logs
+        assert!(!cursor.advance_log(&log_at(10, 0)));
+        assert!(!cursor.advance_log(&log_at(10, 1)));
+        assert!(cursor.advance_log(&log_at(11, 0)));
+
+        let removed = Log {
+            removed: Some(true),
+            ..log_at(12, 0)
+        };
+        assert!(!cursor.advance_log(&removed));
+
+        // logs queried after a reconnection move the same cursor
+        assert!(!cursor.advance(U64::from(11), U256::zero()));
+        assert!(cursor.advance(U64::from(11), U256::one()));
+        assert!(!cursor.advance_log(&log_at(11, 1)));
+    }
+}
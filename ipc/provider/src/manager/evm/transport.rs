@@ -0,0 +1,680 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! An ethers transport spreading the requests to a subnet over several RPC endpoints.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use ethers::providers::{Http, HttpClientError, JsonRpcClient, ProviderError, RpcError};
+use futures_util::future::join_all;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::retry::{RetryPolicy, Retryable};
+
+/// The transport of the providers used by the evm subnet manager.
+pub type EthTransport = RetryTransport<FailoverTransport>;
+
+/// Default time an endpoint that failed is skipped for before it is tried again.
+const DEFAULT_ENDPOINT_COOLDOWN: Duration = Duration::from_secs(30);
+/// Default interval between the health checks of the endpoints of a subnet.
+pub(crate) const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A JSON-RPC transport over a list of HTTP endpoints.
+///
+/// Requests are sent to the first healthy endpoint and fail over to the next one on
+/// connection errors, invalid responses or timeouts. Transactions only fail over if they
+/// could not be delivered, as an endpoint that timed out may have accepted them. An
+/// endpoint that fails is considered unhealthy and only tried after the healthy ones until
+/// its cooldown expires. JSON-RPC error responses (e.g. reverts) are answers from a healthy
+/// node and are returned as is.
+/// Optionally, every endpoint is probed before a request once the health check interval
+/// has elapsed, so that failed endpoints are skipped before a request waits on them and
+/// recovered ones are preferred again.
+///
+/// Optionally, read requests can require a quorum of endpoints returning the same result.
+/// Lookups that some endpoints answer with `null` return `null` until enough endpoints
+/// agree on the object, so that a lagging endpoint delays a receipt rather than failing it.
+/// Requests that depend on the state of a single node, like sending transactions or
+/// polling filters, always go to a single endpoint. So do the requests relative to the head
+/// of the node, like the latest block or the pending nonce, whose results legitimately
+/// differ between healthy nodes a few blocks apart.
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<Vec<Endpoint>>,
+    request_timeout: Duration,
+    cooldown: Duration,
+    read_quorum: usize,
+    health_check_interval: Option<Duration>,
+    last_health_check: Arc<Mutex<Option<Instant>>>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    client: Http,
+    state: Mutex<EndpointState>,
+}
+
+#[derive(Debug, Default)]
+struct EndpointState {
+    failed_at: Option<Instant>,
+    served: u64,
+    failures: u64,
+}
+
+/// The health of an endpoint of a [`FailoverTransport`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointStatus {
+    pub url: Url,
+    pub healthy: bool,
+    /// The number of requests served by the endpoint.
+    pub served: u64,
+    /// The number of requests the endpoint failed to serve.
+    pub failures: u64,
+}
+
+/// The error of a request sent through a [`FailoverTransport`].
+#[derive(Debug, thiserror::Error)]
+pub enum FailoverError {
+    /// The error returned by the endpoint that served the request.
+    #[error("{url}: {source}")]
+    Endpoint { url: Url, source: HttpClientError },
+    #[error("all rpc endpoints failed: {}", .0.join("; "))]
+    AllFailed(Vec<String>),
+    #[error("{method}: less than {quorum} rpc endpoints agree on the result")]
+    NoQuorum { method: String, quorum: usize },
+    /// A transaction that may have been accepted by the endpoint, which failed to answer.
+    /// It is neither sent to another endpoint nor retried, so that it is not sent twice.
+    #[error("{url}: {method} failed after the transaction was sent, it may still be included")]
+    MaybeSent { url: Url, method: String },
+    #[error(transparent)]
+    SerdeJson(#[from] serde_json::Error),
+}
+
+impl RpcError for FailoverError {
+    fn as_error_response(&self) -> Option<&ethers::providers::JsonRpcError> {
+        match self {
+            FailoverError::Endpoint { source, .. } => source.as_error_response(),
+            _ => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            FailoverError::Endpoint { source, .. } => source.as_serde_error(),
+            FailoverError::SerdeJson(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<FailoverError> for ProviderError {
+    fn from(src: FailoverError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(src))
+    }
+}
+
+impl Retryable for FailoverError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            FailoverError::Endpoint { source, .. } => source.is_retryable(),
+            // the endpoints may recover, or agree once they are in sync.
+            FailoverError::AllFailed(_) | FailoverError::NoQuorum { .. } => true,
+            FailoverError::MaybeSent { .. } | FailoverError::SerdeJson(_) => false,
+        }
+    }
+}
+
+impl FailoverTransport {
+    /// Creates a transport over `endpoints`, in order of preference.
+    pub fn new(endpoints: Vec<Http>, request_timeout: Duration) -> anyhow::Result<Self> {
+        if endpoints.is_empty() {
+            return Err(anyhow!("at least one rpc endpoint is required"));
+        }
+
+        Ok(Self {
+            endpoints: Arc::new(
+                endpoints
+                    .into_iter()
+                    .map(|client| Endpoint {
+                        client,
+                        state: Mutex::new(EndpointState::default()),
+                    })
+                    .collect(),
+            ),
+            request_timeout,
+            cooldown: DEFAULT_ENDPOINT_COOLDOWN,
+            read_quorum: 1,
+            health_check_interval: None,
+            last_health_check: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Probes the endpoints with a [`health_check`](Self::health_check) before a request
+    /// whenever `interval` has elapsed since the last one. Only applies to transports with
+    /// more than one endpoint.
+    pub fn with_health_check_interval(mut self, interval: Duration) -> Self {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Sets how long an endpoint that failed is skipped for.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Sets the number of endpoints that need to return the same result for a read to be
+    /// accepted. It is capped to the number of endpoints.
+    pub fn with_read_quorum(mut self, read_quorum: usize) -> Self {
+        self.read_quorum = read_quorum.clamp(1, self.endpoints.len());
+        self
+    }
+
+    /// Returns the health of every endpoint.
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .iter()
+            .map(|e| {
+                let state = e.state.lock().unwrap();
+                EndpointStatus {
+                    url: e.client.url().clone(),
+                    healthy: state.is_healthy(self.cooldown),
+                    served: state.served,
+                    failures: state.failures,
+                }
+            })
+            .collect()
+    }
+
+    /// Probes every endpoint with `eth_chainId`, updating and returning their health.
+    pub async fn health_check(&self) -> Vec<EndpointStatus> {
+        join_all(
+            self.endpoints
+                .iter()
+                .map(|e| self.send(e, "eth_chainId", &Value::Null)),
+        )
+        .await;
+        self.status()
+    }
+
+    /// Runs a health check if the interval has elapsed since the last one.
+    async fn maybe_health_check(&self) {
+        let Some(interval) = self.health_check_interval else {
+            return;
+        };
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        {
+            let mut last = self.last_health_check.lock().unwrap();
+            if last.map_or(false, |t| t.elapsed() < interval) {
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+
+        let status = self.health_check().await;
+        log::debug!("rpc endpoints health: {status:?}");
+    }
+
+    /// The endpoints in the order they should be tried: the healthy ones in order of
+    /// preference, then the unhealthy ones starting with the one that failed first.
+    fn ordered_endpoints(&self) -> Vec<&Endpoint> {
+        let mut endpoints = self
+            .endpoints
+            .iter()
+            .map(|e| {
+                let state = e.state.lock().unwrap();
+                (e, state.is_healthy(self.cooldown), state.failed_at)
+            })
+            .collect::<Vec<_>>();
+        // the sort is stable, so healthy endpoints keep their order of preference.
+        endpoints.sort_by_key(|(_, healthy, failed_at)| if *healthy { None } else { *failed_at });
+        endpoints.into_iter().map(|(e, _, _)| e).collect()
+    }
+
+    /// Sends the request to a single endpoint, recording whether it served it. Returns
+    /// an `EndpointFailure` if the endpoint failed, in which case the request should fail
+    /// over.
+    async fn send(
+        &self,
+        endpoint: &Endpoint,
+        method: &str,
+        params: &Value,
+    ) -> Result<Result<Value, HttpClientError>, EndpointFailure> {
+        let url = endpoint.client.url();
+        let request = async {
+            // requests without parameters omit them, as ethers does.
+            if params.is_null() {
+                endpoint.client.request(method, ()).await
+            } else {
+                endpoint.client.request(method, params).await
+            }
+        };
+
+        let result = match tokio::time::timeout(self.request_timeout, request).await {
+            Ok(Ok(value)) => Ok(Ok(value)),
+            Ok(Err(e)) if e.as_error_response().is_some() => Ok(Err(e)),
+            Ok(Err(e)) => {
+                log::warn!("rpc endpoint {url} failed to serve {method}: {e}");
+                match e {
+                    HttpClientError::ReqwestError(e) if e.is_connect() => {
+                        Err(EndpointFailure::Unreachable)
+                    }
+                    _ => Err(EndpointFailure::Failed),
+                }
+            }
+            Err(_) => {
+                log::warn!(
+                    "rpc endpoint {url} timed out serving {method} after {}s",
+                    self.request_timeout.as_secs()
+                );
+                Err(EndpointFailure::Failed)
+            }
+        };
+
+        let mut state = endpoint.state.lock().unwrap();
+        if result.is_ok() {
+            log::debug!("{method} served by rpc endpoint {url}");
+            state.failed_at = None;
+            state.served += 1;
+        } else {
+            state.failed_at = Some(Instant::now());
+            state.failures += 1;
+        }
+        result
+    }
+
+    async fn request_failover(&self, method: &str, params: &Value) -> Result<Value, FailoverError> {
+        let mut errors = vec![];
+        for endpoint in self.ordered_endpoints() {
+            match self.send(endpoint, method, params).await {
+                Ok(result) => {
+                    return result.map_err(|source| FailoverError::Endpoint {
+                        url: endpoint.client.url().clone(),
+                        source,
+                    })
+                }
+                Err(EndpointFailure::Failed) if is_transaction_submission(method) => {
+                    return Err(FailoverError::MaybeSent {
+                        url: endpoint.client.url().clone(),
+                        method: method.to_string(),
+                    })
+                }
+                Err(_) => errors.push(format!("{} failed", endpoint.client.url())),
+            }
+        }
+        Err(FailoverError::AllFailed(errors))
+    }
+
+    async fn request_quorum(&self, method: &str, params: &Value) -> Result<Value, FailoverError> {
+        let endpoints = self.ordered_endpoints();
+        let results = join_all(endpoints.iter().map(|e| self.send(e, method, params))).await;
+
+        // group the answers by their content, error responses included, so that reverts
+        // are only returned if enough endpoints agree on them. Null answers, like the
+        // receipt of a transaction a lagging endpoint has not seen yet, do not vote.
+        let mut votes: HashMap<String, (usize, Result<Value, FailoverError>)> = HashMap::new();
+        let mut not_found = false;
+        for (endpoint, result) in endpoints.into_iter().zip(results) {
+            let Ok(result) = result else {
+                continue;
+            };
+            if matches!(result, Ok(Value::Null)) {
+                not_found = true;
+                continue;
+            }
+            let key = match &result {
+                Ok(value) => value.to_string(),
+                Err(e) => format!("{:?}", e.as_error_response()),
+            };
+            let result = result.map_err(|source| FailoverError::Endpoint {
+                url: endpoint.client.url().clone(),
+                source,
+            });
+            votes.entry(key).or_insert((0, result)).0 += 1;
+        }
+
+        votes
+            .into_values()
+            .find(|(count, _)| *count >= self.read_quorum)
+            .map(|(_, result)| result)
+            .unwrap_or_else(|| {
+                // not enough endpoints know the object yet, callers poll until they do.
+                if not_found {
+                    return Ok(Value::Null);
+                }
+                Err(FailoverError::NoQuorum {
+                    method: method.to_string(),
+                    quorum: self.read_quorum,
+                })
+            })
+    }
+}
+
+/// Why an endpoint failed to serve a request.
+enum EndpointFailure {
+    /// The request could not be delivered to the endpoint.
+    Unreachable,
+    /// The endpoint timed out or did not return a valid response, it may have received
+    /// the request.
+    Failed,
+}
+
+impl EndpointState {
+    fn is_healthy(&self, cooldown: Duration) -> bool {
+        self.failed_at.map_or(true, |t| t.elapsed() >= cooldown)
+    }
+}
+
+/// Methods whose result depends on the head of the node serving them.
+const HEAD_RELATIVE_METHODS: &[&str] = &[
+    "eth_blockNumber",
+    "eth_gasPrice",
+    "eth_maxPriorityFeePerGas",
+    "eth_feeHistory",
+    "eth_estimateGas",
+    "eth_syncing",
+];
+
+/// Whether the result of `method` depends on the state of the node serving it, so it
+/// cannot be compared across endpoints.
+fn is_node_local(method: &str) -> bool {
+    method.starts_with("eth_send") || method.contains("Filter")
+}
+
+/// Whether `method` submits a transaction, which must not be sent twice.
+fn is_transaction_submission(method: &str) -> bool {
+    method.starts_with("eth_send")
+}
+
+/// Whether the result of the request is pinned to blocks all the endpoints agree on, so it
+/// can be subject to a read quorum.
+fn is_pinned_read(method: &str, params: &Value) -> bool {
+    if is_node_local(method) || HEAD_RELATIVE_METHODS.contains(&method) {
+        return false;
+    }
+    // a log query without an upper bound runs up to the latest block.
+    if method == "eth_getLogs" {
+        let filter = &params[0];
+        if filter.get("toBlock").is_none() && filter.get("blockHash").is_none() {
+            return false;
+        }
+    }
+    !refers_to_head(params)
+}
+
+/// Whether the params refer to a block by a tag relative to the head of the node.
+fn refers_to_head(params: &Value) -> bool {
+    match params {
+        Value::String(s) => matches!(s.as_str(), "latest" | "pending" | "safe" | "finalized"),
+        Value::Array(values) => values.iter().any(refers_to_head),
+        Value::Object(values) => values.values().any(refers_to_head),
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverTransport {
+    type Error = FailoverError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = serde_json::to_value(params)?;
+        self.maybe_health_check().await;
+        let value = if self.read_quorum > 1 && is_pinned_read(method, &params) {
+            self.request_quorum(method, &params).await?
+        } else {
+            self.request_failover(method, &params).await?
+        };
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Retries the requests of another transport on transient errors and rate limits them,
+/// according to a [`RetryPolicy`].
+#[derive(Debug, Clone)]
+pub struct RetryTransport<T> {
+    inner: T,
+    policy: RetryPolicy,
+}
+
+impl<T> RetryTransport<T> {
+    pub fn new(inner: T, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<T> JsonRpcClient for RetryTransport<T>
+where
+    T: JsonRpcClient,
+    T::Error: Retryable + From<serde_json::Error>,
+{
+    type Error = T::Error;
+
+    async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+    where
+        P: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // the params are serialized once, as they are sent again on every retry.
+        let params = serde_json::to_value(params)?;
+        let params = &params;
+        self.policy
+            .run(method, || async move {
+                if params.is_null() {
+                    self.inner.request(method, ()).await
+                } else {
+                    self.inner.request(method, params).await
+                }
+            })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use ethers::providers::{Http, JsonRpcClient};
+    use ethers::types::{Address, H256, U64};
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+    use url::Url;
+
+    use super::{FailoverError, FailoverTransport};
+    use crate::retry::Retryable;
+    use crate::testing::rpc_endpoint;
+
+    /// Starts a stand-in rpc endpoint answering every request with `result`.
+    async fn endpoint(result: Value) -> Http {
+        Http::new(rpc_endpoint(move |_, _| json!({ "result": result.clone() })).await)
+    }
+
+    fn unreachable_endpoint() -> Http {
+        Http::new(Url::parse("http://127.0.0.1:1").unwrap())
+    }
+
+    /// Starts a stand-in rpc endpoint that accepts requests but never answers them.
+    async fn hanging_endpoint() -> Http {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+
+        tokio::spawn(async move {
+            let mut streams = vec![];
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                streams.push(stream);
+            }
+        });
+
+        Http::new(url)
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_to_healthy_endpoint() {
+        let transport = FailoverTransport::new(
+            vec![unreachable_endpoint(), endpoint(json!("0x10")).await],
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let block: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block, U64::from(16));
+
+        let status = transport.status();
+        assert!(!status[0].healthy);
+        assert_eq!(status[0].failures, 1);
+        assert!(status[1].healthy);
+        assert_eq!(status[1].served, 1);
+
+        // the unhealthy endpoint is skipped until its cooldown expires
+        let _: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(transport.status()[0].failures, 1);
+        assert_eq!(transport.status()[1].served, 2);
+    }
+
+    #[tokio::test]
+    async fn test_transactions_do_not_fail_over_after_timeout() {
+        let transport = FailoverTransport::new(
+            vec![hanging_endpoint().await, endpoint(json!("0x10")).await],
+            Duration::from_millis(200),
+        )
+        .unwrap();
+
+        let err = transport
+            .request::<_, U64>("eth_sendRawTransaction", ["0x00"])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FailoverError::MaybeSent { .. }));
+        assert!(!err.is_retryable());
+        assert_eq!(transport.status()[1].served, 0);
+
+        // reads fail over as usual.
+        let block: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block, U64::from(16));
+
+        // transactions that cannot be delivered are safe to send elsewhere.
+        let transport = FailoverTransport::new(
+            vec![unreachable_endpoint(), endpoint(json!("0x10")).await],
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        let hash: U64 = transport
+            .request("eth_sendRawTransaction", ["0x00"])
+            .await
+            .unwrap();
+        assert_eq!(hash, U64::from(16));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_before_request() {
+        let transport = FailoverTransport::new(
+            vec![endpoint(json!("0x10")).await, unreachable_endpoint()],
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_health_check_interval(Duration::from_secs(60));
+
+        let block: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block, U64::from(16));
+
+        // the fallback endpoint is found unhealthy before any request fails over to it.
+        let status = transport.status();
+        assert!(status[0].healthy);
+        assert_eq!(status[0].served, 2);
+        assert!(!status[1].healthy);
+        assert_eq!(status[1].failures, 1);
+
+        // and it is not probed again until the interval elapses.
+        let _: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(transport.status()[0].served, 3);
+        assert_eq!(transport.status()[1].failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_quorum() {
+        let transport = FailoverTransport::new(
+            vec![endpoint(json!("0x10")).await, endpoint(json!("0x11")).await],
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_read_quorum(2);
+
+        let pinned = (Address::zero(), "0x5");
+        let err = transport
+            .request::<_, U64>("eth_getBalance", pinned)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FailoverError::NoQuorum { quorum: 2, .. }));
+
+        // requests relative to the head of the node are served by a single endpoint.
+        let block: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block, U64::from(16));
+        let balance: U64 = transport
+            .request("eth_getBalance", (Address::zero(), "latest"))
+            .await
+            .unwrap();
+        assert_eq!(balance, U64::from(16));
+
+        let transport = FailoverTransport::new(
+            vec![endpoint(json!("0x10")).await, endpoint(json!("0x10")).await],
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_read_quorum(2);
+
+        let balance: U64 = transport.request("eth_getBalance", pinned).await.unwrap();
+        assert_eq!(balance, U64::from(16));
+    }
+
+    #[tokio::test]
+    async fn test_read_quorum_with_lagging_endpoint() {
+        let receipt = json!({"transactionHash": format!("{:?}", H256::repeat_byte(7))});
+        let hash = [H256::repeat_byte(7)];
+
+        // the receipt is not found until enough endpoints see it.
+        let transport = FailoverTransport::new(
+            vec![endpoint(receipt.clone()).await, endpoint(Value::Null).await],
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_read_quorum(2);
+        let found: Value = transport
+            .request("eth_getTransactionReceipt", hash)
+            .await
+            .unwrap();
+        assert!(found.is_null());
+
+        let transport = FailoverTransport::new(
+            vec![
+                endpoint(receipt.clone()).await,
+                endpoint(Value::Null).await,
+                endpoint(receipt.clone()).await,
+            ],
+            Duration::from_secs(5),
+        )
+        .unwrap()
+        .with_read_quorum(2);
+        let found: Value = transport
+            .request("eth_getTransactionReceipt", hash)
+            .await
+            .unwrap();
+        assert_eq!(found, receipt);
+    }
+}
@@ -0,0 +1,880 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use cid::Cid;
+use fil_actors_runtime::cbor;
+use fvm_ipld_encoding::RawBytes;
+use fvm_shared::address::Address;
+use fvm_shared::bigint::BigInt;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_identity::Wallet;
+use ipc_sdk::checkpoint::{BottomUpCheckpointBundle, QuorumReachedEvent};
+use ipc_sdk::cross::CrossMsg;
+use ipc_sdk::gateway::{FundParams, PropagateParams, ReleaseParams, Status};
+use ipc_sdk::staking::{
+    StakingChangeRequest, SubnetValidator, ValidatorInfo, ValidatorStakingInfo,
+};
+use ipc_sdk::subnet::{ConstructParams, JoinParams, MANIFEST_ID};
+use ipc_sdk::subnet_id::SubnetID;
+use ipc_sdk::validator::Validator;
+use num_traits::Zero;
+use serde::de::DeserializeOwned;
+use serde_tuple::{Deserialize_tuple, Serialize_tuple};
+
+use crate::config::subnet::{SubnetOperation, UnsupportedOperationError};
+use crate::config::Subnet;
+use crate::jsonrpc::{JsonRpcClient, JsonRpcClientImpl};
+use crate::lotus::client::LotusJsonRPCClient;
+use crate::lotus::message::ipc::{
+    IPCReadGatewayStateResponse, IPCReadSubnetActorStateResponse, SubnetInfo,
+};
+use crate::lotus::message::mpool::MpoolPushMessage;
+use crate::lotus::message::state::StateWaitMsgResponse;
+use crate::lotus::LotusClient;
+use crate::manager::subnet::{
+    BottomUpCheckpointRelayer, CheckpointedBottomUpMsg, CommittedTopDownMsg, GetBlockHashResult,
+    NullRoundError, SubnetDetails, SubnetGenesisInfo, TopDownFinalityQuery, TopDownQueryPayload,
+    TxOutcome, TxResult,
+};
+use crate::manager::SubnetManager;
+
+/// The address of the built-in init actor used to deploy new subnet actors.
+const INIT_ACTOR_ADDR: Address = Address::new_id(1);
+
+/// The method numbers of the native actors the manager interacts with. The IPC actors
+/// follow the FRC-42 calling convention, so their numbers are derived from the method names.
+mod methods {
+    use fvm_shared::MethodNum;
+
+    pub const SEND: MethodNum = 0;
+    pub const INIT_EXEC: MethodNum = 2;
+
+    pub const SUBNET_JOIN: MethodNum = 2758473253;
+    pub const SUBNET_LEAVE: MethodNum = 79496443;
+    pub const SUBNET_KILL: MethodNum = 3399233477;
+
+    pub const GATEWAY_FUND: MethodNum = 209017424;
+    pub const GATEWAY_RELEASE: MethodNum = 2866883080;
+    pub const GATEWAY_SEND_CROSS: MethodNum = 1716885404;
+    pub const GATEWAY_PROPAGATE: MethodNum = 3773577263;
+}
+
+/// The parameters of the init actor `Exec` method.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct ExecParams {
+    code_cid: Cid,
+    constructor_params: RawBytes,
+}
+
+/// The return value of the init actor `Exec` method.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct ExecReturn {
+    id_address: Address,
+    robust_address: Address,
+}
+
+impl Default for ExecReturn {
+    fn default() -> Self {
+        Self {
+            id_address: Address::new_id(0),
+            robust_address: Address::new_id(0),
+        }
+    }
+}
+
+/// The parameters of the gateway `SendCross` method.
+#[derive(Serialize_tuple, Deserialize_tuple)]
+struct SendCrossParams {
+    cross_msg: CrossMsg,
+    destination: SubnetID,
+}
+
+/// A `SubnetManager` that talks to the native IPC actors of a Lotus-based network. The
+/// operations the native actors do not support are listed in `config::FVMSubnet`.
+pub struct LotusSubnetManager<T: JsonRpcClient> {
+    lotus_client: LotusJsonRPCClient<T>,
+    gateway_addr: Address,
+}
+
+impl<T: JsonRpcClient + Send + Sync> LotusSubnetManager<T> {
+    pub fn new(lotus_client: LotusJsonRPCClient<T>, gateway_addr: Address) -> Self {
+        Self {
+            lotus_client,
+            gateway_addr,
+        }
+    }
+
+    /// Pushes the message to the memory pool and waits for its execution.
+    async fn mpool_push_and_wait(&self, message: MpoolPushMessage) -> Result<StateWaitMsgResponse> {
+        let message_cid = self.lotus_client.mpool_push(message).await?;
+        log::debug!("message published with cid: {message_cid:?}");
+
+        self.lotus_client.state_wait_msg(message_cid).await
+    }
+
+    /// Same as `mpool_push_and_wait` but returns the outcome of the executed message.
+    async fn mpool_push_and_wait_outcome(&self, message: MpoolPushMessage) -> Result<TxResult> {
+        let r = self.mpool_push_and_wait(message).await?;
+        tx_outcome_from_response(&r).map(TxResult::Sent)
+    }
+
+    /// Reads the state of the subnet actor of `subnet` at the current chain head.
+    async fn subnet_actor_state(
+        &self,
+        subnet: &SubnetID,
+    ) -> Result<IPCReadSubnetActorStateResponse> {
+        self.read_state_at_head(subnet.subnet_actor()).await
+    }
+
+    /// Reads the state of the gateway actor at the current chain head.
+    async fn gateway_state(&self) -> Result<IPCReadGatewayStateResponse> {
+        self.read_state_at_head(self.gateway_addr).await
+    }
+
+    async fn read_state_at_head<State: DeserializeOwned + std::fmt::Debug>(
+        &self,
+        address: Address,
+    ) -> Result<State> {
+        let tipset = self.chain_head_tipset().await?;
+        let response = self
+            .lotus_client
+            .read_state::<State>(address, tipset)
+            .await?;
+        Ok(response.state)
+    }
+
+    async fn chain_head_tipset(&self) -> Result<Cid> {
+        let head = self.lotus_client.chain_head().await?;
+        let cid_map = head
+            .cids
+            .first()
+            .ok_or_else(|| anyhow!("chain head has an empty tipset"))?;
+        Cid::try_from(cid_map)
+    }
+
+    /// The validators of `subnet`, all of them active as the native subnet actor has no
+    /// waiting validators.
+    async fn validators(&self, subnet: &SubnetID) -> Result<Vec<(Address, ValidatorInfo)>> {
+        let state = self.subnet_actor_state(subnet).await?;
+        let validators = native_validators(&state)?
+            .into_iter()
+            .map(|v| {
+                let info = ValidatorInfo {
+                    staking: ValidatorStakingInfo::new(v.weight.clone(), v.weight, v.metadata),
+                    is_active: true,
+                    is_waiting: false,
+                };
+                (v.addr, info)
+            })
+            .collect();
+        Ok(validators)
+    }
+}
+
+impl LotusSubnetManager<JsonRpcClientImpl> {
+    pub fn from_subnet(subnet: &Subnet) -> Self {
+        Self::new(
+            LotusJsonRPCClient::from_subnet(subnet),
+            subnet.gateway_addr(),
+        )
+    }
+
+    pub fn from_subnet_with_wallet_store(subnet: &Subnet, wallet: Arc<RwLock<Wallet>>) -> Self {
+        Self::new(
+            LotusJsonRPCClient::from_subnet_with_wallet_store(subnet, wallet),
+            subnet.gateway_addr(),
+        )
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> SubnetManager for LotusSubnetManager<T> {
+    async fn create_subnet(
+        &self,
+        from: Address,
+        params: ConstructParams,
+    ) -> Result<TxResult<(Address, TxOutcome)>> {
+        let network_version = self.lotus_client.state_network_version(vec![]).await?;
+        let code_cids = self
+            .lotus_client
+            .state_actor_code_cids(network_version)
+            .await?;
+        let code_cid = code_cids
+            .get(MANIFEST_ID)
+            .ok_or_else(|| anyhow!("actor code cid for {MANIFEST_ID} not found"))?;
+
+        let exec_params = ExecParams {
+            code_cid: *code_cid,
+            constructor_params: cbor::serialize(&params, "create subnet actor params")?,
+        };
+        let message = MpoolPushMessage::new(
+            INIT_ACTOR_ADDR,
+            from,
+            methods::INIT_EXEC,
+            cbor::serialize(&exec_params, "init exec params")?.to_vec(),
+        );
+
+        let r = self.mpool_push_and_wait(message).await?;
+        // an aborted message has no return value to parse.
+        let outcome = tx_outcome_from_response(&r)?;
+        let result = r.receipt.parse_result_into::<ExecReturn>()?;
+        log::info!(
+            "created subnet actor with id address: {}",
+            result.id_address
+        );
+
+        Ok(TxResult::Sent((result.id_address, outcome)))
+    }
+
+    async fn join_subnet(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+        metadata: Vec<u8>,
+    ) -> Result<TxResult> {
+        let params = JoinParams {
+            validator_net_addr: String::from_utf8(metadata)
+                .map_err(|_| anyhow!("fvm subnets expect the validator net address as metadata"))?,
+        };
+        let mut message = MpoolPushMessage::new(
+            subnet.subnet_actor(),
+            from,
+            methods::SUBNET_JOIN,
+            cbor::serialize(&params, "join subnet params")?.to_vec(),
+        );
+        message.value = collateral;
+
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn pre_fund(
+        &self,
+        _subnet: SubnetID,
+        _from: Address,
+        _balance: TokenAmount,
+    ) -> Result<TxResult> {
+        Err(unsupported(SubnetOperation::PreFund))
+    }
+
+    async fn pre_release(
+        &self,
+        _subnet: SubnetID,
+        _from: Address,
+        _amount: TokenAmount,
+    ) -> Result<TxResult> {
+        Err(unsupported(SubnetOperation::PreRelease))
+    }
+
+    async fn stake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<TxResult> {
+        // the native subnet actor adds the value of a join from an existing validator to
+        // its stake, so the validator joins again with the net address it registered.
+        let (_, info) = self
+            .validators(&subnet)
+            .await?
+            .into_iter()
+            .find(|(address, _)| *address == from)
+            .ok_or_else(|| anyhow!("{from} is not a validator of subnet {subnet}"))?;
+        let metadata = info.staking.metadata().to_vec();
+
+        self.join_subnet(subnet, from, collateral, metadata).await
+    }
+
+    async fn unstake(
+        &self,
+        _subnet: SubnetID,
+        _from: Address,
+        _collateral: TokenAmount,
+    ) -> Result<TxResult> {
+        Err(unsupported(SubnetOperation::Unstake))
+    }
+
+    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
+        let message =
+            MpoolPushMessage::new(subnet.subnet_actor(), from, methods::SUBNET_LEAVE, vec![]);
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
+        let message =
+            MpoolPushMessage::new(subnet.subnet_actor(), from, methods::SUBNET_KILL, vec![]);
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn list_child_subnets(
+        &self,
+        gateway_addr: Address,
+    ) -> Result<HashMap<SubnetID, SubnetInfo>> {
+        let subnets = self
+            .lotus_client
+            .ipc_list_child_subnets(gateway_addr)
+            .await?;
+        Ok(subnets.into_iter().map(|s| (s.id.clone(), s)).collect())
+    }
+
+    async fn claim_collateral(&self, _subnet: SubnetID, _from: Address) -> Result<TxResult> {
+        Err(unsupported(SubnetOperation::ClaimCollateral))
+    }
+
+    async fn claim_relayer_reward(&self, _subnet: SubnetID, _from: Address) -> Result<TxResult> {
+        Err(unsupported(SubnetOperation::ClaimRelayerReward))
+    }
+
+    async fn fund(
+        &self,
+        subnet: SubnetID,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult> {
+        let params = FundParams { subnet, to };
+        let mut message = MpoolPushMessage::new(
+            gateway_addr,
+            from,
+            methods::GATEWAY_FUND,
+            cbor::serialize(&params, "fund params")?.to_vec(),
+        );
+        message.value = amount;
+
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn release(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+        fee: Option<TokenAmount>,
+    ) -> Result<TxResult> {
+        let params = ReleaseParams { to };
+        let mut message = MpoolPushMessage::new(
+            gateway_addr,
+            from,
+            methods::GATEWAY_RELEASE,
+            cbor::serialize(&params, "release params")?.to_vec(),
+        );
+        message.value = amount + fee.unwrap_or_default();
+
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn propagate(
+        &self,
+        _subnet: SubnetID,
+        gateway_addr: Address,
+        from: Address,
+        postbox_msg_key: Vec<u8>,
+    ) -> Result<TxResult> {
+        let params = PropagateParams {
+            postbox_cid: Cid::try_from(postbox_msg_key.as_slice())?,
+        };
+        let message = MpoolPushMessage::new(
+            gateway_addr,
+            from,
+            methods::GATEWAY_PROPAGATE,
+            cbor::serialize(&params, "propagate params")?.to_vec(),
+        );
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn send_cross_message(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        cross_msg: CrossMsg,
+    ) -> Result<TxResult> {
+        let destination = cross_msg.msg.to.subnet()?;
+        let value = cross_msg.msg.value.clone();
+        let params = SendCrossParams {
+            cross_msg,
+            destination,
+        };
+        let mut message = MpoolPushMessage::new(
+            gateway_addr,
+            from,
+            methods::GATEWAY_SEND_CROSS,
+            cbor::serialize(&params, "send cross params")?.to_vec(),
+        );
+        message.value = value;
+
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn send_value(
+        &self,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult> {
+        let mut message = MpoolPushMessage::new(to, from, methods::SEND, vec![]);
+        message.value = amount;
+
+        self.mpool_push_and_wait_outcome(message).await
+    }
+
+    async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
+        self.lotus_client.wallet_balance(address).await
+    }
+
+    async fn get_chain_id(&self) -> Result<String> {
+        self.lotus_client.state_network_name().await
+    }
+
+    async fn get_genesis_info(&self, subnet: &SubnetID) -> Result<SubnetGenesisInfo> {
+        let state = self.subnet_actor_state(subnet).await?;
+        let gateway = self.gateway_state().await?;
+
+        Ok(SubnetGenesisInfo {
+            // the native subnet actor does not cap the number of validators.
+            active_validators_limit: u16::MAX,
+            bottom_up_checkpoint_period: state.bottom_up_check_period as u64,
+            genesis_epoch: state.genesis_epoch,
+            majority_percentage: state.bottom_up_checkpoint_voting.ratio.percentage(),
+            min_collateral: state.min_validator_stake.clone(),
+            // native subnets charge the cross-net message fee of the gateway.
+            msg_fee: gateway.cross_msg_fee,
+            validators: native_validators(&state)?,
+            // native subnets cannot be pre-funded.
+            genesis_balances: BTreeMap::new(),
+        })
+    }
+
+    async fn add_bootstrap(
+        &self,
+        _subnet: &SubnetID,
+        _from: &Address,
+        _endpoint: String,
+    ) -> Result<TxResult> {
+        Err(unsupported(SubnetOperation::AddBootstrap))
+    }
+
+    async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>> {
+        // the validators of native subnets are reached through the net address they
+        // registered when joining.
+        let state = self.subnet_actor_state(subnet).await?;
+        Ok(state
+            .validator_set
+            .validators
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| v.net_addr)
+            .collect())
+    }
+
+    async fn get_validator_info(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<ValidatorInfo> {
+        let info = self
+            .validators(subnet)
+            .await?
+            .into_iter()
+            .find(|(address, _)| address == validator)
+            .map(|(_, info)| info)
+            .unwrap_or_else(|| ValidatorInfo {
+                staking: ValidatorStakingInfo::new(
+                    TokenAmount::zero(),
+                    TokenAmount::zero(),
+                    vec![],
+                ),
+                is_active: false,
+                is_waiting: false,
+            });
+        Ok(info)
+    }
+
+    async fn get_subnet_info(&self, subnet: &SubnetID) -> Result<SubnetDetails> {
+        let state = self.subnet_actor_state(subnet).await?;
+        let gateway = self.gateway_state().await?;
+        // the subnet is only registered in the gateway once it has enough stake.
+        let info = self
+            .list_child_subnets(self.gateway_addr)
+            .await?
+            .remove(subnet);
+        let (status, stake, circ_supply) = match info {
+            Some(info) => (info.status, info.stake, info.circ_supply),
+            None => (Status::Inactive, TokenAmount::zero(), TokenAmount::zero()),
+        };
+        let validators = state.validator_set.validators.unwrap_or_default();
+
+        Ok(SubnetDetails {
+            id: subnet.clone(),
+            status,
+            stake,
+            circ_supply,
+            genesis_epoch: state.genesis_epoch,
+            bottom_up_checkpoint_period: state.bottom_up_check_period as u64,
+            majority_percentage: state.bottom_up_checkpoint_voting.ratio.percentage(),
+            min_collateral: state.min_validator_stake,
+            min_cross_msg_fee: gateway.cross_msg_fee,
+            active_validators: validators.len() as u16,
+            waiting_validators: 0,
+            bootstrap_nodes: validators.into_iter().map(|v| v.net_addr).collect(),
+        })
+    }
+
+    async fn list_validators(&self, subnet: &SubnetID) -> Result<Vec<SubnetValidator>> {
+        Ok(SubnetValidator::ranked(self.validators(subnet).await?))
+    }
+
+    async fn get_fund_msg(
+        &self,
+        _subnet: &SubnetID,
+        _tx_hash: &[u8],
+    ) -> Result<Option<CommittedTopDownMsg>> {
+        Err(unsupported(SubnetOperation::FundStatus))
+    }
+
+    async fn get_release_msg(
+        &self,
+        _tx_hash: &[u8],
+        _checkpoint_period: ChainEpoch,
+    ) -> Result<Option<CheckpointedBottomUpMsg>> {
+        Err(unsupported(SubnetOperation::ReleaseStatus))
+    }
+
+    async fn cross_msg_fee(&self) -> Result<TokenAmount> {
+        Ok(self.gateway_state().await?.cross_msg_fee)
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> TopDownFinalityQuery for LotusSubnetManager<T> {
+    async fn genesis_epoch(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.subnet_actor_state(subnet_id).await?;
+        Ok(state.bottom_up_checkpoint_voting.genesis_epoch)
+    }
+
+    async fn chain_head_height(&self) -> Result<ChainEpoch> {
+        self.lotus_client.current_epoch().await
+    }
+
+    async fn get_top_down_msgs(
+        &self,
+        _subnet_id: &SubnetID,
+        _epoch: ChainEpoch,
+        _block_hash: &[u8],
+    ) -> Result<Vec<CrossMsg>> {
+        Err(unsupported(SubnetOperation::TopDownMessages))
+    }
+
+    async fn get_block_hash(&self, height: ChainEpoch) -> Result<GetBlockHashResult> {
+        let head = self.chain_head_tipset().await?;
+        let tipset = self.lotus_client.get_tipset_by_height(height, head).await?;
+        // lotus returns the last tipset before the height if it is a null round.
+        if tipset.height != height {
+            return Err(anyhow::Error::new(NullRoundError { height }));
+        }
+
+        let block_hash = tipset
+            .tip_set_cids()?
+            .first()
+            .ok_or_else(|| anyhow!("tipset at height {height} has no blocks"))?
+            .to_bytes();
+        let parent_block_hash = tipset
+            .parent_cids()?
+            .first()
+            .map(|cid| cid.to_bytes())
+            .unwrap_or_default();
+        Ok(GetBlockHashResult {
+            parent_block_hash,
+            block_hash,
+        })
+    }
+
+    async fn get_validator_changeset(
+        &self,
+        _subnet_id: &SubnetID,
+        _epoch: ChainEpoch,
+    ) -> Result<TopDownQueryPayload<Vec<StakingChangeRequest>>> {
+        Err(unsupported(SubnetOperation::ValidatorChanges))
+    }
+
+    async fn latest_parent_finality(&self) -> Result<ChainEpoch> {
+        // the native gateway commits the parent state through top-down checkpoint votes.
+        let state = self.gateway_state().await?;
+        Ok(state.top_down_checkpoint_voting.last_voting_executed)
+    }
+
+    async fn applied_top_down_nonce(&self) -> Result<u64> {
+        Ok(self.gateway_state().await?.applied_topdown_nonce)
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient + Send + Sync> BottomUpCheckpointRelayer for LotusSubnetManager<T> {
+    async fn submit_checkpoint(
+        &self,
+        _submitter: &Address,
+        _bundle: BottomUpCheckpointBundle,
+    ) -> Result<TxResult> {
+        // the native subnet actor expects its own checkpoint format that cannot be
+        // derived from the signature bundles produced by the fevm gateway.
+        Err(unsupported(SubnetOperation::CheckpointRelaying))
+    }
+
+    async fn last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.subnet_actor_state(subnet_id).await?;
+        Ok(state.bottom_up_checkpoint_voting.last_voting_executed)
+    }
+
+    async fn applied_bottom_up_nonce(&self, _subnet_id: &SubnetID) -> Result<u64> {
+        // the native gateway keeps a single nonce for the messages of all its children.
+        Ok(self.gateway_state().await?.applied_bottomup_nonce)
+    }
+
+    async fn has_submitted_in_last_checkpoint_height(
+        &self,
+        _subnet_id: &SubnetID,
+        _submitter: &Address,
+    ) -> Result<bool> {
+        Err(unsupported(SubnetOperation::CheckpointRelaying))
+    }
+
+    async fn checkpoint_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.subnet_actor_state(subnet_id).await?;
+        Ok(state.bottom_up_check_period)
+    }
+
+    async fn checkpoint_bundle_at(&self, _height: ChainEpoch) -> Result<BottomUpCheckpointBundle> {
+        Err(unsupported(SubnetOperation::CheckpointRelaying))
+    }
+
+    async fn quorum_reached_events(&self, _height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>> {
+        Err(unsupported(SubnetOperation::CheckpointRelaying))
+    }
+
+    async fn current_epoch(&self) -> Result<ChainEpoch> {
+        self.lotus_client.current_epoch().await
+    }
+}
+
+/// A message that was included in a block but aborted with a non-zero exit code.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("message {message} aborted at epoch {height} with exit code {exit_code}")]
+pub struct MessageAbortedError {
+    pub message: Cid,
+    pub height: ChainEpoch,
+    pub exit_code: u32,
+}
+
+/// Converts the result of waiting for a message into the outcome of its transaction, or a
+/// `MessageAbortedError` if the message failed. Native messages are identified by their
+/// CID, and the actor events are not decoded.
+fn tx_outcome_from_response(r: &StateWaitMsgResponse) -> Result<TxOutcome> {
+    if r.receipt.exit_code != 0 {
+        return Err(anyhow::Error::new(MessageAbortedError {
+            message: Cid::try_from(&r.message)?,
+            height: r.height as ChainEpoch,
+            exit_code: r.receipt.exit_code,
+        }));
+    }
+
+    let block_hash = match r.tip_set.first() {
+        Some(cid) => Cid::try_from(cid)?.to_bytes(),
+        None => vec![],
+    };
+    Ok(TxOutcome {
+        tx_hash: Cid::try_from(&r.message)?.to_bytes(),
+        block_number: r.height as ChainEpoch,
+        block_hash,
+        gas_used: r.receipt.gas_used,
+        effective_gas_price: None,
+        events: vec![],
+    })
+}
+
+/// The validators of the native subnet actor, whose metadata is the net address they
+/// joined with.
+fn native_validators(state: &IPCReadSubnetActorStateResponse) -> Result<Vec<Validator>> {
+    state
+        .validator_set
+        .validators
+        .iter()
+        .flatten()
+        .map(|v| {
+            Ok(Validator {
+                addr: Address::from_str(&v.addr)?,
+                metadata: v.net_addr.clone().into_bytes(),
+                weight: TokenAmount::from_atto(BigInt::from_str(&v.weight)?),
+            })
+        })
+        .collect()
+}
+
+/// The error of the operations the native actors do not support. The provider does not
+/// connect to fvm subnets for them, see `config::Subnet::supports`.
+fn unsupported(op: SubnetOperation) -> anyhow::Error {
+    anyhow::Error::new(UnsupportedOperationError { op })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use cid::Cid;
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+    use ipc_sdk::subnet_id::SubnetID;
+    use serde_json::{json, Value};
+    use url::Url;
+
+    use super::{tx_outcome_from_response, LotusSubnetManager, MessageAbortedError};
+    use crate::jsonrpc::JsonRpcClientImpl;
+    use crate::lotus::client::LotusJsonRPCClient;
+    use crate::lotus::message::state::StateWaitMsgResponse;
+    use crate::manager::subnet::{NullRoundError, TopDownFinalityQuery};
+    use crate::manager::SubnetManager;
+    use crate::testing::rpc_endpoint;
+
+    const MESSAGE_CID: &str = "bafy2bzacecwgnejfzcq7a4zvvownmb4oae6xzyu323z5wuuufesbtikortt6k";
+    const PARENT_CID: &str = "bafy2bzacedkoa623kvi5gfis2yks7xxjl73vg7xwbojz4tpq63dd5jpfz757i";
+
+    fn subnet_manager(endpoint: Url) -> LotusSubnetManager<JsonRpcClientImpl> {
+        let client =
+            LotusJsonRPCClient::new(JsonRpcClientImpl::new(endpoint, None), SubnetID::default());
+        LotusSubnetManager::new(client, Address::new_id(64))
+    }
+
+    /// Answers the chain queries of a chain whose height 5 is a null round, and the state
+    /// reads of the gateway and of a subnet actor with two validators.
+    fn lotus_rpc(method: &str, params: &Value) -> Value {
+        let tipset = |height: i64| {
+            json!({
+                "Cids": [{"/": MESSAGE_CID}],
+                "Blocks": [{"ParentStateRoot": {"/": PARENT_CID}, "Parents": [{"/": PARENT_CID}]}],
+                "Height": height,
+            })
+        };
+        match method {
+            "Filecoin.ChainHead" => json!({ "result": tipset(10) }),
+            "Filecoin.ChainGetTipSetByHeight" => match params[0].as_i64().unwrap() {
+                5 => json!({ "result": tipset(4) }),
+                height => json!({ "result": tipset(height) }),
+            },
+            "Filecoin.StateReadState" if params[0] == "f064" => json!({"result": {
+                "Balance": "0",
+                "Code": {"/": PARENT_CID},
+                "State": {
+                    "BottomUpCheckPeriod": 10,
+                    "TopDownCheckPeriod": 10,
+                    "AppliedTopdownNonce": 3,
+                    "AppliedBottomupNonce": 4,
+                    "CrossMsgFee": "100",
+                    "TopDownCheckpointVoting": {
+                        "GenesisEpoch": 0,
+                        "LastVotingExecuted": 8,
+                        "Ratio": {"Num": 2, "Denom": 3},
+                    },
+                    "Initialized": true,
+                },
+            }}),
+            "Filecoin.StateReadState" => json!({"result": {
+                "Balance": "0",
+                "Code": {"/": PARENT_CID},
+                "State": {
+                    "BottomUpCheckPeriod": 10,
+                    "GenesisEpoch": 2,
+                    "MinValidatorStake": "1000000000000000000",
+                    "ValidatorSet": {
+                        "validators": [
+                            {"addr": "f0100", "net_addr": "/ip4/1", "weight": "1000"},
+                            {"addr": "f0101", "net_addr": "/ip4/2", "weight": "3000"},
+                        ],
+                        "configuration_number": 2,
+                    },
+                    "MinValidators": 1,
+                    "BottomUpCheckpointVoting": {
+                        "GenesisEpoch": 2,
+                        "LastVotingExecuted": 0,
+                        "Ratio": {"Num": 2, "Denom": 3},
+                    },
+                },
+            }}),
+            _ => json!({"error": {"code": -32601, "message": format!("unknown method {method}")}}),
+        }
+    }
+
+    fn response(exit_code: u32) -> StateWaitMsgResponse {
+        serde_json::from_value(json!({
+            "Message": {"/": MESSAGE_CID},
+            "Receipt": {"ExitCode": exit_code, "Return": null, "GasUsed": 1000},
+            "TipSet": [{"/": MESSAGE_CID}],
+            "Height": 10
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_aborted_message_is_an_error() {
+        let err = tx_outcome_from_response(&response(16)).unwrap_err();
+        assert_eq!(
+            err.downcast::<MessageAbortedError>().unwrap(),
+            MessageAbortedError {
+                message: Cid::from_str(MESSAGE_CID).unwrap(),
+                height: 10,
+                exit_code: 16,
+            }
+        );
+
+        let outcome = tx_outcome_from_response(&response(0)).unwrap();
+        assert_eq!(outcome.block_number, 10);
+        assert_eq!(outcome.gas_used, 1000);
+    }
+
+    #[tokio::test]
+    async fn test_block_hash_of_null_round() {
+        let manager = subnet_manager(rpc_endpoint(lotus_rpc).await);
+
+        let r = manager.get_block_hash(6).await.unwrap();
+        assert_eq!(r.block_hash, Cid::from_str(MESSAGE_CID).unwrap().to_bytes());
+        assert_eq!(
+            r.parent_block_hash,
+            Cid::from_str(PARENT_CID).unwrap().to_bytes()
+        );
+
+        let err = manager.get_block_hash(5).await.unwrap_err();
+        assert_eq!(
+            err.downcast::<NullRoundError>().unwrap(),
+            NullRoundError { height: 5 }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_validators_from_subnet_actor_state() {
+        let manager = subnet_manager(rpc_endpoint(lotus_rpc).await);
+        let subnet = SubnetID::new_from_parent(&SubnetID::default(), Address::new_id(1001));
+
+        let validators = manager.list_validators(&subnet).await.unwrap();
+        assert_eq!(
+            validators.iter().map(|v| v.address).collect::<Vec<_>>(),
+            vec![Address::new_id(101), Address::new_id(100)]
+        );
+        assert!(validators.iter().all(|v| v.info.is_active));
+        assert_eq!(validators[0].power, TokenAmount::from_atto(3000));
+
+        let genesis = manager.get_genesis_info(&subnet).await.unwrap();
+        assert_eq!(genesis.genesis_epoch, 2);
+        assert_eq!(genesis.majority_percentage, 66);
+        assert_eq!(genesis.min_collateral, TokenAmount::from_whole(1));
+        assert_eq!(genesis.msg_fee, TokenAmount::from_atto(100));
+        assert_eq!(genesis.validators[0].metadata, b"/ip4/1".to_vec());
+
+        assert_eq!(manager.latest_parent_finality().await.unwrap(), 8);
+    }
+}
@@ -0,0 +1,9 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+//! The subnet manager for subnets whose IPC actors are native (built-in) FVM actors
+//! accessed through the Lotus JSON-RPC API.
+
+mod manager;
+
+pub use manager::{LotusSubnetManager, MessageAbortedError};
@@ -0,0 +1,1335 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! In-memory subnet manager.
+//!
+//! The mock keeps the state of a full IPC hierarchy (chains, gateways and subnet actors)
+//! in memory so that the provider and the bottom-up checkpoint relayer can be exercised
+//! without a running network. Every mutating call is included in a new block, and block
+//! hashes are derived from the chain id and the height, so runs are fully deterministic.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::abi::Token;
+use ethers::utils::keccak256;
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::checkpoint::{BottomUpCheckpoint, BottomUpCheckpointBundle, QuorumReachedEvent};
+use ipc_sdk::cross::{CrossMsg, IPCMsgType, StorableMsg};
+use ipc_sdk::evm::fil_to_eth_amount;
+use ipc_sdk::gateway::Status;
+use ipc_sdk::staking::{
+    StakingChange, StakingChangeRequest, StakingOperation, SubnetValidator, ValidatorInfo,
+    ValidatorStakingInfo,
+};
+use ipc_sdk::subnet::ConstructParams;
+use ipc_sdk::subnet_id::SubnetID;
+use ipc_sdk::validator::Validator;
+use num_traits::Zero;
+
+use crate::config::Subnet;
+use crate::lotus::message::ipc::SubnetInfo;
+use crate::manager::subnet::{
+    BottomUpCheckpointRelayer, CheckpointedBottomUpMsg, CommittedTopDownMsg, GetBlockHashResult,
+    IpcEvent, SubnetDetails, SubnetGenesisInfo, SubnetManager, TopDownFinalityQuery,
+    TopDownQueryPayload, TxOutcome, TxResult,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The majority percentage reported for mocked subnets, same as the one used by
+/// the evm manager when creating subnets.
+const SUBNET_MAJORITY_PERCENTAGE: u8 = 60;
+/// The ethereum address manager namespace used to derive the address of new subnet actors.
+const EAM_NAMESPACE: u64 = 10;
+
+/// The in-memory state of a mocked IPC hierarchy. It is cheap to clone, and all the
+/// managers created from the same hierarchy share the same state, so interactions between
+/// a parent and its children (funding, checkpointing...) can be simulated end-to-end.
+#[derive(Clone, Default)]
+pub struct MockHierarchy {
+    state: Arc<Mutex<HierarchyState>>,
+}
+
+/// The subnet manager backed by a `MockHierarchy`. Like the rest of the managers, it
+/// is bound to a single chain of the hierarchy.
+pub struct MockSubnetManager {
+    chain: SubnetID,
+    gateway_addr: Address,
+    hierarchy: MockHierarchy,
+}
+
+#[derive(Default)]
+struct HierarchyState {
+    chains: HashMap<SubnetID, ChainState>,
+}
+
+/// The state of a single chain, including its gateway and the subnet actors deployed on it.
+struct ChainState {
+    id: SubnetID,
+    /// Block hashes indexed by height.
+    blocks: Vec<[u8; 32]>,
+    balances: HashMap<Address, TokenAmount>,
+    /// The subnet actors deployed in this chain, keyed by the id of the child subnet.
+    subnets: HashMap<SubnetID, SubnetActorState>,
+    /// The number of subnet actors deployed so far, used to derive new actor addresses.
+    deployed_actors: u64,
+    /// The latest parent height committed in this chain.
+    parent_finality: ChainEpoch,
+    /// The nonce of the next top-down message to be executed in this chain.
+    applied_top_down_nonce: u64,
+    /// Bottom-up messages waiting to be included in the next checkpoint.
+    pending_bottom_up: Vec<CrossMsg>,
+    bottom_up_nonce: u64,
+    /// The nonces of the bottom-up messages sent by releases, by the height of the release.
+    released_nonces: HashMap<ChainEpoch, u64>,
+    /// The checkpoint bundles cut in this chain, indexed by height.
+    checkpoints: BTreeMap<ChainEpoch, BottomUpCheckpointBundle>,
+}
+
+struct SubnetActorState {
+    params: ConstructParams,
+    status: Status,
+    genesis_epoch: ChainEpoch,
+    circ_supply: TokenAmount,
+    validators: BTreeMap<Address, MockValidator>,
+    genesis_validators: Vec<Validator>,
+    genesis_balances: BTreeMap<Address, TokenAmount>,
+    /// Collateral released by validators that is ready to be claimed.
+    claimable: HashMap<Address, TokenAmount>,
+    bootstrap_nodes: Vec<String>,
+    configuration_number: u64,
+    /// The staking changes emitted by the subnet actor, indexed by height.
+    changes: BTreeMap<ChainEpoch, Vec<StakingChangeRequest>>,
+    /// The top-down messages committed in the gateway for the subnet, indexed by height.
+    top_down_msgs: BTreeMap<ChainEpoch, Vec<CrossMsg>>,
+    top_down_nonce: u64,
+    last_checkpoint_height: ChainEpoch,
+    last_checkpoint_submitters: HashSet<Address>,
+    /// The nonce of the next bottom-up message from the subnet to be executed.
+    applied_bottom_up_nonce: u64,
+}
+
+struct MockValidator {
+    collateral: TokenAmount,
+    metadata: Vec<u8>,
+}
+
+impl MockHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a new chain in the hierarchy. The chain starts with its genesis block
+    /// at height zero. Adding an existing chain is a no-op.
+    pub fn add_chain(&self, id: &SubnetID) {
+        self.lock()
+            .chains
+            .entry(id.clone())
+            .or_insert_with(|| ChainState::new(id.clone()));
+    }
+
+    /// Creates a manager for the chain described by the subnet config, registering the
+    /// chain in the hierarchy if needed.
+    pub fn manager(&self, subnet: &Subnet) -> MockSubnetManager {
+        self.add_chain(&subnet.id);
+        MockSubnetManager {
+            chain: subnet.id.clone(),
+            gateway_addr: subnet.gateway_addr(),
+            hierarchy: self.clone(),
+        }
+    }
+
+    /// Sets the balance of an address in a chain.
+    pub fn set_balance(&self, chain: &SubnetID, addr: Address, amount: TokenAmount) -> Result<()> {
+        self.lock().chain_mut(chain)?.balances.insert(addr, amount);
+        Ok(())
+    }
+
+    /// Produces `blocks` empty blocks in the chain and returns the new chain head.
+    pub fn advance_blocks(&self, chain: &SubnetID, blocks: ChainEpoch) -> Result<ChainEpoch> {
+        let mut state = self.lock();
+        let mut height = state.chain(chain)?.head();
+        for _ in 0..blocks {
+            height = state.mine(chain)?;
+        }
+        Ok(height)
+    }
+
+    /// Commits the parent finality at `height` in the child `subnet`, executing all the
+    /// top-down messages committed in the parent up to that height.
+    pub fn commit_parent_finality(&self, subnet: &SubnetID, height: ChainEpoch) -> Result<()> {
+        let parent = subnet
+            .parent()
+            .ok_or_else(|| anyhow!("subnet {subnet} has no parent"))?;
+
+        let mut state = self.lock();
+        let parent_chain = state.chain(&parent)?;
+        if height > parent_chain.head() {
+            return Err(anyhow!("height {height} not yet produced in parent"));
+        }
+
+        let next_nonce = state.chain(subnet)?.applied_top_down_nonce;
+        let msgs = parent_chain
+            .subnet_actor(subnet)?
+            .top_down_msgs
+            .range(..=height)
+            .flat_map(|(_, msgs)| msgs.iter())
+            .filter(|m| m.msg.nonce >= next_nonce)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let child = state.chain_mut(subnet)?;
+        for m in msgs {
+            child.credit(&m.msg.to.raw_addr()?, &m.msg.value);
+            child.applied_top_down_nonce = m.msg.nonce + 1;
+        }
+        child.parent_finality = height;
+
+        state.mine(subnet)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<HierarchyState> {
+        self.state.lock().unwrap()
+    }
+}
+
+impl HierarchyState {
+    fn chain(&self, id: &SubnetID) -> Result<&ChainState> {
+        self.chains
+            .get(id)
+            .ok_or_else(|| anyhow!("chain {id} not found in mock hierarchy"))
+    }
+
+    fn chain_mut(&mut self, id: &SubnetID) -> Result<&mut ChainState> {
+        self.chains
+            .get_mut(id)
+            .ok_or_else(|| anyhow!("chain {id} not found in mock hierarchy"))
+    }
+
+    /// Produces a new block in the chain and returns its height. If the height is a
+    /// checkpoint height for the chain, a new bottom-up checkpoint is cut and signed by
+    /// the active validators of the subnet.
+    fn mine(&mut self, id: &SubnetID) -> Result<ChainEpoch> {
+        let committee = self.checkpoint_committee(id);
+
+        let chain = self.chain_mut(id)?;
+        let height = chain.push_block();
+        if let Some((period, signers)) = committee {
+            if period > 0 && height % period == 0 {
+                chain.cut_checkpoint(height, signers)?;
+            }
+        }
+        Ok(height)
+    }
+
+    /// Returns the checkpoint period and the active validators of a chain from the
+    /// subnet actor in its parent, if any.
+    fn checkpoint_committee(
+        &self,
+        id: &SubnetID,
+    ) -> Option<(ChainEpoch, Vec<(Address, TokenAmount)>)> {
+        let parent = self.chains.get(&id.parent()?)?;
+        let actor = parent.subnets.get(id)?;
+        if actor.status != Status::Active {
+            return None;
+        }
+
+        let signers = actor
+            .active_validators()
+            .into_iter()
+            .map(|(addr, v)| (*addr, v.collateral.clone()))
+            .collect();
+        Some((actor.params.bottomup_check_period, signers))
+    }
+}
+
+impl ChainState {
+    fn new(id: SubnetID) -> Self {
+        let genesis = block_hash(&id, 0, &[0; 32]);
+        Self {
+            id,
+            blocks: vec![genesis],
+            balances: HashMap::new(),
+            subnets: HashMap::new(),
+            deployed_actors: 0,
+            parent_finality: 0,
+            applied_top_down_nonce: 0,
+            pending_bottom_up: vec![],
+            bottom_up_nonce: 0,
+            released_nonces: HashMap::new(),
+            checkpoints: BTreeMap::new(),
+        }
+    }
+
+    fn head(&self) -> ChainEpoch {
+        (self.blocks.len() - 1) as ChainEpoch
+    }
+
+    /// The height of the block that will include the next transaction.
+    fn next_height(&self) -> ChainEpoch {
+        self.head() + 1
+    }
+
+    fn push_block(&mut self) -> ChainEpoch {
+        let height = self.next_height();
+        let parent = self.blocks[self.blocks.len() - 1];
+        self.blocks.push(block_hash(&self.id, height, &parent));
+        height
+    }
+
+    fn block_hash_at(&self, height: ChainEpoch) -> Result<[u8; 32]> {
+        if height < 0 || height > self.head() {
+            return Err(anyhow!("height does not exist"));
+        }
+        Ok(self.blocks[height as usize])
+    }
+
+    fn balance(&self, addr: &Address) -> TokenAmount {
+        self.balances
+            .get(addr)
+            .cloned()
+            .unwrap_or_else(TokenAmount::zero)
+    }
+
+    fn credit(&mut self, addr: &Address, amount: &TokenAmount) {
+        *self.balances.entry(*addr).or_default() += amount;
+    }
+
+    fn debit(&mut self, addr: &Address, amount: &TokenAmount) -> Result<()> {
+        let balance = self.balance(addr);
+        if balance < *amount {
+            return Err(anyhow!(
+                "insufficient funds: {addr} has {balance} but {amount} is required"
+            ));
+        }
+        self.balances.insert(*addr, balance - amount);
+        Ok(())
+    }
+
+    fn subnet_actor(&self, subnet: &SubnetID) -> Result<&SubnetActorState> {
+        self.subnets
+            .get(subnet)
+            .ok_or_else(|| anyhow!("subnet: {subnet} does not exists"))
+    }
+
+    fn subnet_actor_mut(&mut self, subnet: &SubnetID) -> Result<&mut SubnetActorState> {
+        self.subnets
+            .get_mut(subnet)
+            .ok_or_else(|| anyhow!("subnet: {subnet} does not exists"))
+    }
+
+    /// Looks up the subnet registered in the gateway, i.e. a subnet that has already
+    /// been bootstrapped.
+    fn registered_subnet(&self, subnet: &SubnetID) -> Result<&SubnetActorState> {
+        match self.subnets.get(subnet) {
+            Some(actor) if actor.status != Status::Inactive => Ok(actor),
+            _ => Err(anyhow!("subnet: {subnet} does not exists")),
+        }
+    }
+
+    fn cut_checkpoint(
+        &mut self,
+        height: ChainEpoch,
+        signers: Vec<(Address, TokenAmount)>,
+    ) -> Result<()> {
+        let cross_msgs = std::mem::take(&mut self.pending_bottom_up);
+        let checkpoint = BottomUpCheckpoint {
+            subnet_id: self.id.clone(),
+            block_height: height,
+            block_hash: self.block_hash_at(height)?.to_vec(),
+            next_configuration_number: 0,
+            cross_messages_hash: keccak256(fvm_ipld_encoding::to_vec(&cross_msgs)?).to_vec(),
+        };
+        let checkpoint_hash = checkpoint_hash(&checkpoint)?;
+
+        let (signatories, signatures): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .map(|(addr, _)| {
+                let mut payload = addr.to_bytes();
+                payload.extend_from_slice(&checkpoint_hash);
+                (*addr, keccak256(payload).to_vec())
+            })
+            .unzip();
+
+        self.checkpoints.insert(
+            height,
+            BottomUpCheckpointBundle {
+                checkpoint,
+                signatures,
+                signatories,
+                cross_msgs,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl SubnetActorState {
+    fn new(params: ConstructParams) -> Self {
+        Self {
+            params,
+            status: Status::Inactive,
+            genesis_epoch: 0,
+            circ_supply: TokenAmount::zero(),
+            validators: BTreeMap::new(),
+            genesis_validators: vec![],
+            genesis_balances: BTreeMap::new(),
+            claimable: HashMap::new(),
+            bootstrap_nodes: vec![],
+            configuration_number: 0,
+            changes: BTreeMap::new(),
+            top_down_msgs: BTreeMap::new(),
+            top_down_nonce: 0,
+            last_checkpoint_height: 0,
+            last_checkpoint_submitters: HashSet::new(),
+            applied_bottom_up_nonce: 0,
+        }
+    }
+
+    fn ensure_not_killed(&self) -> Result<()> {
+        if self.status == Status::Killed {
+            return Err(anyhow!("subnet already killed"));
+        }
+        Ok(())
+    }
+
+    fn total_collateral(&self) -> TokenAmount {
+        self.validators.values().map(|v| &v.collateral).sum()
+    }
+
+    /// Validators sorted by collateral, with the address as tie breaker so the order
+    /// is deterministic.
+    fn sorted_validators(&self) -> Vec<(&Address, &MockValidator)> {
+        let mut validators = self.validators.iter().collect::<Vec<_>>();
+        validators.sort_by(|(a1, v1), (a2, v2)| v2.collateral.cmp(&v1.collateral).then(a1.cmp(a2)));
+        validators
+    }
+
+    fn active_validators(&self) -> Vec<(&Address, &MockValidator)> {
+        let mut validators = self.sorted_validators();
+        validators.truncate(self.params.active_validators_limit as usize);
+        validators
+    }
+
+    fn is_active(&self, addr: &Address) -> bool {
+        self.status == Status::Active && self.active_validators().iter().any(|(a, _)| *a == addr)
+    }
+
+    fn is_waiting(&self, addr: &Address) -> bool {
+        self.validators.contains_key(addr) && !self.is_active(addr)
+    }
+
+    /// Records a staking change at `height`. Changes are only emitted once the subnet
+    /// is bootstrapped, before that they are part of the genesis.
+    fn record_change(
+        &mut self,
+        height: ChainEpoch,
+        op: StakingOperation,
+        validator: Address,
+        payload: Vec<u8>,
+    ) {
+        if self.status != Status::Active {
+            return;
+        }
+        self.configuration_number += 1;
+        self.changes
+            .entry(height)
+            .or_default()
+            .push(StakingChangeRequest {
+                configuration_number: self.configuration_number,
+                change: StakingChange {
+                    op,
+                    payload,
+                    validator,
+                },
+            });
+    }
+
+    /// Bootstraps the subnet at `height` if enough validators and collateral were committed.
+    fn try_bootstrap(&mut self, height: ChainEpoch) {
+        if self.status != Status::Inactive
+            || (self.validators.len() as u64) < self.params.min_validators
+            || self.total_collateral() < self.params.min_validator_stake
+        {
+            return;
+        }
+
+        self.status = Status::Active;
+        self.genesis_epoch = height;
+        self.genesis_validators = self
+            .active_validators()
+            .into_iter()
+            .map(|(addr, v)| Validator {
+                addr: *addr,
+                metadata: v.metadata.clone(),
+                weight: v.collateral.clone(),
+            })
+            .collect();
+        self.circ_supply = self.genesis_balances.values().sum();
+    }
+
+    fn info(&self, id: &SubnetID) -> SubnetInfo {
+        SubnetInfo {
+            id: id.clone(),
+            stake: self.total_collateral(),
+            circ_supply: self.circ_supply.clone(),
+            status: self.status,
+            genesis_epoch: self.genesis_epoch,
+        }
+    }
+}
+
+impl MockSubnetManager {
+    fn ensure_same_gateway(&self, gateway: &Address) -> Result<()> {
+        if *gateway != self.gateway_addr {
+            Err(anyhow!("Gateway address not matching with config"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<HierarchyState> {
+        self.hierarchy.lock()
+    }
+
+    /// Applies `f` to the chain state and includes the transaction in a new block.
+    /// The state is only mutated if `f` succeeds.
+    fn transact<T, F>(&self, f: F) -> Result<(T, TxOutcome)>
+    where
+        F: FnOnce(&mut ChainState, ChainEpoch) -> Result<T>,
+    {
+        let mut state = self.lock();
+        let chain = state.chain_mut(&self.chain)?;
+        let height = chain.next_height();
+        let out = f(chain, height)?;
+        let events = chain
+            .subnets
+            .values()
+            .filter_map(|actor| actor.changes.get(&height))
+            .flatten()
+            .cloned()
+            .map(IpcEvent::NewStakingChangeRequest)
+            .collect();
+        state.mine(&self.chain)?;
+
+        // every transaction is mined in its own block, so the height identifies it.
+        let block_hash = state.chain(&self.chain)?.block_hash_at(height)?;
+        let outcome = TxOutcome {
+            tx_hash: mock_tx_hash(&self.chain, height).to_vec(),
+            block_number: height,
+            block_hash: block_hash.to_vec(),
+            gas_used: 0,
+            effective_gas_price: None,
+            events,
+        };
+        Ok((out, outcome))
+    }
+}
+
+#[async_trait]
+impl SubnetManager for MockSubnetManager {
+    async fn create_subnet(
+        &self,
+        _from: Address,
+        params: ConstructParams,
+    ) -> Result<TxResult<(Address, TxOutcome)>> {
+        if params.parent != self.chain {
+            return Err(anyhow!(
+                "parent {} does not match the chain {} of the manager",
+                params.parent,
+                self.chain
+            ));
+        }
+        self.ensure_same_gateway(&params.ipc_gateway_addr)?;
+        if params.bottomup_check_period <= 0 {
+            return Err(anyhow!("invalid bottom up checkpoint period"));
+        }
+
+        self.transact(|chain, _| {
+            chain.deployed_actors += 1;
+            let seed = format!("{}/{}", chain.id, chain.deployed_actors);
+            let addr = Address::new_delegated(EAM_NAMESPACE, &keccak256(seed)[12..])?;
+
+            let subnet = SubnetID::new_from_parent(&chain.id, addr);
+            chain.subnets.insert(subnet, SubnetActorState::new(params));
+            Ok(addr)
+        })
+        .map(TxResult::Sent)
+    }
+
+    async fn join_subnet(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+        metadata: Vec<u8>,
+    ) -> Result<TxResult> {
+        if collateral.is_zero() {
+            return Err(anyhow!("collateral is zero"));
+        }
+
+        let (_, outcome) = self.transact(|chain, height| {
+            let actor = chain.subnet_actor(&subnet)?;
+            actor.ensure_not_killed()?;
+            if actor.validators.contains_key(&from) {
+                return Err(anyhow!("validator {from} has already joined"));
+            }
+            chain.debit(&from, &collateral)?;
+
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            actor.validators.insert(
+                from,
+                MockValidator {
+                    collateral: collateral.clone(),
+                    metadata: metadata.clone(),
+                },
+            );
+            actor.record_change(height, StakingOperation::SetMetadata, from, metadata);
+            actor.record_change(
+                height,
+                StakingOperation::Deposit,
+                from,
+                collateral_payload(&collateral)?,
+            );
+            actor.try_bootstrap(height);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn pre_fund(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        balance: TokenAmount,
+    ) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, _| {
+            if chain.subnet_actor(&subnet)?.status != Status::Inactive {
+                return Err(anyhow!("subnet already bootstrapped"));
+            }
+            chain.debit(&from, &balance)?;
+
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            *actor.genesis_balances.entry(from).or_default() += balance;
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn pre_release(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, _| {
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            if actor.status != Status::Inactive {
+                return Err(anyhow!("subnet already bootstrapped"));
+            }
+            let balance = actor
+                .genesis_balances
+                .get_mut(&from)
+                .ok_or_else(|| anyhow!("{from} has no genesis balance"))?;
+            if *balance < amount {
+                return Err(anyhow!("not enough genesis balance to release"));
+            }
+            *balance -= &amount;
+
+            chain.credit(&from, &amount);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn stake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<TxResult> {
+        if collateral.is_zero() {
+            return Err(anyhow!("collateral is zero"));
+        }
+
+        let (_, outcome) = self.transact(|chain, height| {
+            let actor = chain.subnet_actor(&subnet)?;
+            actor.ensure_not_killed()?;
+            if !actor.validators.contains_key(&from) {
+                return Err(anyhow!("{from} has not staked before"));
+            }
+            chain.debit(&from, &collateral)?;
+
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            if let Some(v) = actor.validators.get_mut(&from) {
+                v.collateral += &collateral;
+            }
+            actor.record_change(
+                height,
+                StakingOperation::Deposit,
+                from,
+                collateral_payload(&collateral)?,
+            );
+            actor.try_bootstrap(height);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn unstake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<TxResult> {
+        if collateral.is_zero() {
+            return Err(anyhow!("collateral is zero"));
+        }
+
+        let (_, outcome) = self.transact(|chain, height| {
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            actor.ensure_not_killed()?;
+            let validator = actor
+                .validators
+                .get_mut(&from)
+                .ok_or_else(|| anyhow!("{from} is not a validator"))?;
+            if validator.collateral <= collateral {
+                return Err(anyhow!(
+                    "cannot withdraw all the collateral, leave the subnet instead"
+                ));
+            }
+            validator.collateral -= &collateral;
+
+            *actor.claimable.entry(from).or_default() += &collateral;
+            actor.record_change(
+                height,
+                StakingOperation::Withdraw,
+                from,
+                collateral_payload(&collateral)?,
+            );
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, height| {
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            actor.ensure_not_killed()?;
+            let validator = actor
+                .validators
+                .remove(&from)
+                .ok_or_else(|| anyhow!("{from} is not a validator"))?;
+
+            actor.record_change(
+                height,
+                StakingOperation::Withdraw,
+                from,
+                collateral_payload(&validator.collateral)?,
+            );
+            *actor.claimable.entry(from).or_default() += validator.collateral;
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn kill_subnet(&self, subnet: SubnetID, _from: Address) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, _| {
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            actor.ensure_not_killed()?;
+            if !actor.validators.is_empty() {
+                return Err(anyhow!("not all validators have left the subnet"));
+            }
+            actor.status = Status::Killed;
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn list_child_subnets(
+        &self,
+        gateway_addr: Address,
+    ) -> Result<HashMap<SubnetID, SubnetInfo>> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+        Ok(chain
+            .subnets
+            .iter()
+            .filter(|(_, actor)| actor.status != Status::Inactive)
+            .map(|(id, actor)| (id.clone(), actor.info(id)))
+            .collect())
+    }
+
+    async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, _| {
+            let amount = chain
+                .subnet_actor_mut(&subnet)?
+                .claimable
+                .remove(&from)
+                .ok_or_else(|| anyhow!("no collateral to withdraw"))?;
+            chain.credit(&from, &amount);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn claim_relayer_reward(&self, subnet: SubnetID, _from: Address) -> Result<TxResult> {
+        // relayer rewards are not accounted in the mock, claiming them is a no-op.
+        let (_, outcome) = self.transact(|chain, _| chain.subnet_actor(&subnet).map(|_| ()))?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn fund(
+        &self,
+        subnet: SubnetID,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult> {
+        self.ensure_same_gateway(&gateway_addr)?;
+        if amount.is_zero() {
+            return Err(anyhow!("invalid value to fund"));
+        }
+
+        let (_, outcome) = self.transact(|chain, height| {
+            let actor = chain.registered_subnet(&subnet)?;
+            if actor.status != Status::Active {
+                return Err(anyhow!("subnet {subnet} not active"));
+            }
+            chain.debit(&from, &amount)?;
+
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            let mut msg = StorableMsg::new_fund_msg(&subnet, &from, &to, amount.clone())?;
+            msg.nonce = actor.top_down_nonce;
+            actor.top_down_nonce += 1;
+            actor.circ_supply += &amount;
+            actor
+                .top_down_msgs
+                .entry(height)
+                .or_default()
+                .push(CrossMsg {
+                    msg,
+                    wrapped: false,
+                });
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn release(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+        fee: Option<TokenAmount>,
+    ) -> Result<TxResult> {
+        self.ensure_same_gateway(&gateway_addr)?;
+        if amount.is_zero() {
+            return Err(anyhow!("cannot release zero"));
+        }
+        let fee = fee.unwrap_or_else(TokenAmount::zero);
+
+        let (_, outcome) = self.transact(|chain, height| {
+            chain.debit(&from, &(&amount + &fee))?;
+
+            let mut msg = StorableMsg::new_release_msg(&chain.id, &from, &to, amount, fee)?;
+            msg.nonce = chain.bottom_up_nonce;
+            chain.bottom_up_nonce += 1;
+            chain.released_nonces.insert(height, msg.nonce);
+            chain.pending_bottom_up.push(CrossMsg {
+                msg,
+                wrapped: false,
+            });
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn propagate(
+        &self,
+        _subnet: SubnetID,
+        _gateway_addr: Address,
+        _from: Address,
+        _postbox_msg_key: Vec<u8>,
+    ) -> Result<TxResult> {
+        Err(anyhow!(
+            "postbox propagation not supported by the mock manager"
+        ))
+    }
+
+    async fn send_cross_message(
+        &self,
+        gateway_addr: Address,
+        from: Address,
+        mut cross_msg: CrossMsg,
+    ) -> Result<TxResult> {
+        self.ensure_same_gateway(&gateway_addr)?;
+
+        let (_, outcome) = self.transact(|chain, height| {
+            let value = &cross_msg.msg.value + &cross_msg.msg.fee;
+            match cross_msg.msg.apply_type(&chain.id)? {
+                IPCMsgType::BottomUp => {
+                    chain.debit(&from, &value)?;
+                    cross_msg.msg.nonce = chain.bottom_up_nonce;
+                    chain.bottom_up_nonce += 1;
+                    chain.pending_bottom_up.push(cross_msg);
+                }
+                IPCMsgType::TopDown => {
+                    let to = cross_msg.msg.to.subnet()?;
+                    let subnet = to
+                        .down(&chain.id)
+                        .ok_or_else(|| anyhow!("invalid cross msg destination subnet"))?;
+                    chain.registered_subnet(&subnet)?;
+                    chain.debit(&from, &value)?;
+
+                    let actor = chain.subnet_actor_mut(&subnet)?;
+                    cross_msg.msg.nonce = actor.top_down_nonce;
+                    actor.top_down_nonce += 1;
+                    actor.circ_supply += &cross_msg.msg.value;
+                    actor
+                        .top_down_msgs
+                        .entry(height)
+                        .or_default()
+                        .push(cross_msg);
+                }
+            }
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn send_value(
+        &self,
+        from: Address,
+        to: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, _| {
+            chain.debit(&from, &amount)?;
+            chain.credit(&to, &amount);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount> {
+        Ok(self.lock().chain(&self.chain)?.balance(address))
+    }
+
+    async fn get_chain_id(&self) -> Result<String> {
+        Ok(self.chain.chain_id().to_string())
+    }
+
+    async fn get_genesis_info(&self, subnet: &SubnetID) -> Result<SubnetGenesisInfo> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet)?;
+        Ok(SubnetGenesisInfo {
+            bottom_up_checkpoint_period: actor.params.bottomup_check_period as u64,
+            msg_fee: actor.params.min_cross_msg_fee.clone(),
+            majority_percentage: SUBNET_MAJORITY_PERCENTAGE,
+            active_validators_limit: actor.params.active_validators_limit,
+            min_collateral: actor.params.min_validator_stake.clone(),
+            genesis_epoch: state
+                .chain(&self.chain)?
+                .registered_subnet(subnet)?
+                .genesis_epoch,
+            validators: actor.genesis_validators.clone(),
+            genesis_balances: actor.genesis_balances.clone(),
+        })
+    }
+
+    async fn add_bootstrap(
+        &self,
+        subnet: &SubnetID,
+        from: &Address,
+        endpoint: String,
+    ) -> Result<TxResult> {
+        let (_, outcome) = self.transact(|chain, _| {
+            let actor = chain.subnet_actor_mut(subnet)?;
+            if !actor.validators.contains_key(from) {
+                return Err(anyhow!("{from} is not a validator"));
+            }
+            actor.bootstrap_nodes.push(endpoint);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet)?;
+        Ok(actor.bootstrap_nodes.clone())
+    }
+
+    async fn get_validator_info(
+        &self,
+        subnet: &SubnetID,
+        validator: &Address,
+    ) -> Result<ValidatorInfo> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet)?;
+
+        // unknown validators are reported with empty collateral, as the contract does.
+        let staking = match actor.validators.get(validator) {
+            Some(v) => ValidatorStakingInfo::new(
+                v.collateral.clone(),
+                v.collateral.clone(),
+                v.metadata.clone(),
+            ),
+            None => ValidatorStakingInfo::new(TokenAmount::zero(), TokenAmount::zero(), vec![]),
+        };
+        Ok(ValidatorInfo {
+            staking,
+            is_active: actor.is_active(validator),
+            is_waiting: actor.is_waiting(validator),
+        })
+    }
+
+    async fn get_subnet_info(&self, subnet: &SubnetID) -> Result<SubnetDetails> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet)?;
+        let info = actor.info(subnet);
+        let active_validators = actor
+            .validators
+            .keys()
+            .filter(|a| actor.is_active(a))
+            .count() as u16;
+        Ok(SubnetDetails {
+            id: info.id,
+            status: info.status,
+            stake: info.stake,
+            circ_supply: info.circ_supply,
+            genesis_epoch: info.genesis_epoch,
+            bottom_up_checkpoint_period: actor.params.bottomup_check_period as u64,
+            majority_percentage: SUBNET_MAJORITY_PERCENTAGE,
+            min_collateral: actor.params.min_validator_stake.clone(),
+            min_cross_msg_fee: actor.params.min_cross_msg_fee.clone(),
+            active_validators,
+            waiting_validators: actor.validators.len() as u16 - active_validators,
+            bootstrap_nodes: actor.bootstrap_nodes.clone(),
+        })
+    }
+
+    async fn list_validators(&self, subnet: &SubnetID) -> Result<Vec<SubnetValidator>> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet)?;
+        let validators = actor
+            .validators
+            .iter()
+            .map(|(addr, v)| {
+                let info = ValidatorInfo {
+                    staking: ValidatorStakingInfo::new(
+                        v.collateral.clone(),
+                        v.collateral.clone(),
+                        v.metadata.clone(),
+                    ),
+                    is_active: actor.is_active(addr),
+                    is_waiting: actor.is_waiting(addr),
+                };
+                (*addr, info)
+            })
+            .collect();
+        Ok(SubnetValidator::ranked(validators))
+    }
+
+    async fn get_fund_msg(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+    ) -> Result<Option<CommittedTopDownMsg>> {
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+        let actor = chain.subnet_actor(subnet)?;
+
+        let Some(height) = (1..=chain.head()).find(|h| mock_tx_hash(&self.chain, *h) == tx_hash)
+        else {
+            return Ok(None);
+        };
+        // every transaction is mined in its own block, so it committed the only message.
+        let msg = actor
+            .top_down_msgs
+            .get(&height)
+            .and_then(|msgs| msgs.first())
+            .ok_or_else(|| anyhow!("transaction at {height} did not fund {subnet}"))?;
+        Ok(Some(CommittedTopDownMsg {
+            height,
+            block_hash: chain.block_hash_at(height)?.to_vec(),
+            msg: msg.clone(),
+        }))
+    }
+
+    async fn get_release_msg(
+        &self,
+        tx_hash: &[u8],
+        checkpoint_period: ChainEpoch,
+    ) -> Result<Option<CheckpointedBottomUpMsg>> {
+        if checkpoint_period <= 0 {
+            return Err(anyhow!("invalid checkpoint period: {checkpoint_period}"));
+        }
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+
+        let Some(height) = (1..=chain.head()).find(|h| mock_tx_hash(&self.chain, *h) == tx_hash)
+        else {
+            return Ok(None);
+        };
+        let nonce = *chain
+            .released_nonces
+            .get(&height)
+            .ok_or_else(|| anyhow!("transaction at {height} is not a release"))?;
+
+        // the message waits in the pending messages until the next checkpoint is cut.
+        let checkpoint = chain
+            .checkpoints
+            .range(height..)
+            .flat_map(|(h, bundle)| bundle.cross_msgs.iter().map(move |m| (*h, m)))
+            .find(|(_, m)| m.msg.nonce == nonce);
+        let (checkpoint_height, msg) = match checkpoint {
+            Some((h, msg)) => (h, msg),
+            None => {
+                let msg = chain
+                    .pending_bottom_up
+                    .iter()
+                    .find(|m| m.msg.nonce == nonce)
+                    .ok_or_else(|| anyhow!("bottom-up message {nonce} not found"))?;
+                let next = (height + checkpoint_period - 1) / checkpoint_period;
+                (next * checkpoint_period, msg)
+            }
+        };
+        Ok(Some(CheckpointedBottomUpMsg {
+            height,
+            checkpoint_height,
+            msg: msg.clone(),
+        }))
+    }
+
+    async fn cross_msg_fee(&self) -> Result<TokenAmount> {
+        // the gateway of a child subnet charges the minimum fee of its subnet actor, the
+        // root has no subnet actor and its messages are free.
+        let state = self.lock();
+        let fee = self
+            .chain
+            .parent()
+            .and_then(|parent| state.chains.get(&parent)?.subnets.get(&self.chain))
+            .map(|actor| actor.params.min_cross_msg_fee.clone())
+            .unwrap_or_else(TokenAmount::zero);
+        Ok(fee)
+    }
+}
+
+#[async_trait]
+impl TopDownFinalityQuery for MockSubnetManager {
+    async fn genesis_epoch(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.registered_subnet(subnet_id)?;
+        Ok(actor.genesis_epoch)
+    }
+
+    async fn chain_head_height(&self) -> Result<ChainEpoch> {
+        Ok(self.lock().chain(&self.chain)?.head())
+    }
+
+    async fn get_top_down_msgs(
+        &self,
+        subnet_id: &SubnetID,
+        epoch: ChainEpoch,
+        block_hash: &[u8],
+    ) -> Result<Vec<CrossMsg>> {
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+        if chain.block_hash_at(epoch)?.as_slice() != block_hash {
+            return Err(anyhow!("block hash does not match the block at {epoch}"));
+        }
+
+        let actor = chain.subnet_actor(subnet_id)?;
+        Ok(actor.top_down_msgs.get(&epoch).cloned().unwrap_or_default())
+    }
+
+    async fn get_block_hash(&self, height: ChainEpoch) -> Result<GetBlockHashResult> {
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+        let parent_block_hash = if height == 0 {
+            [0; 32]
+        } else {
+            chain.block_hash_at(height - 1)?
+        };
+        Ok(GetBlockHashResult {
+            parent_block_hash: parent_block_hash.to_vec(),
+            block_hash: chain.block_hash_at(height)?.to_vec(),
+        })
+    }
+
+    async fn get_validator_changeset(
+        &self,
+        subnet_id: &SubnetID,
+        epoch: ChainEpoch,
+    ) -> Result<TopDownQueryPayload<Vec<StakingChangeRequest>>> {
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+        let actor = chain.subnet_actor(subnet_id)?;
+        Ok(TopDownQueryPayload {
+            value: actor.changes.get(&epoch).cloned().unwrap_or_default(),
+            block_hash: chain.block_hash_at(epoch)?.to_vec(),
+        })
+    }
+
+    async fn latest_parent_finality(&self) -> Result<ChainEpoch> {
+        Ok(self.lock().chain(&self.chain)?.parent_finality)
+    }
+
+    async fn applied_top_down_nonce(&self) -> Result<u64> {
+        Ok(self.lock().chain(&self.chain)?.applied_top_down_nonce)
+    }
+}
+
+#[async_trait]
+impl BottomUpCheckpointRelayer for MockSubnetManager {
+    async fn submit_checkpoint(
+        &self,
+        submitter: &Address,
+        bundle: BottomUpCheckpointBundle,
+    ) -> Result<TxResult> {
+        let subnet = bundle.checkpoint.subnet_id.clone();
+
+        let (_, outcome) = self.transact(|chain, _| {
+            let actor = chain.registered_subnet(&subnet)?;
+            if let Some(s) = bundle
+                .signatories
+                .iter()
+                .find(|s| !actor.validators.contains_key(s))
+            {
+                return Err(anyhow!("signatory {s} is not a validator"));
+            }
+
+            let checkpoint_height = bundle.checkpoint.block_height;
+            let period = actor.params.bottomup_check_period;
+            if checkpoint_height == actor.last_checkpoint_height {
+                // late submissions of the last checkpoint only record the relayer
+                chain
+                    .subnet_actor_mut(&subnet)?
+                    .last_checkpoint_submitters
+                    .insert(*submitter);
+                return Ok(());
+            }
+            if checkpoint_height != actor.last_checkpoint_height + period {
+                return Err(anyhow!("invalid checkpoint epoch: {checkpoint_height}"));
+            }
+
+            let mut released = TokenAmount::zero();
+            let mut applied_nonce = actor.applied_bottom_up_nonce;
+            for m in bundle.cross_msgs.iter() {
+                chain.credit(&m.msg.to.raw_addr()?, &m.msg.value);
+                released += &m.msg.value;
+                applied_nonce = m.msg.nonce + 1;
+            }
+
+            let actor = chain.subnet_actor_mut(&subnet)?;
+            actor.circ_supply -= released;
+            actor.applied_bottom_up_nonce = applied_nonce;
+            actor.last_checkpoint_height = checkpoint_height;
+            actor.last_checkpoint_submitters = HashSet::from([*submitter]);
+            Ok(())
+        })?;
+        Ok(TxResult::Sent(outcome))
+    }
+
+    async fn last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet_id)?;
+        Ok(actor.last_checkpoint_height)
+    }
+
+    async fn applied_bottom_up_nonce(&self, subnet_id: &SubnetID) -> Result<u64> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet_id)?;
+        Ok(actor.applied_bottom_up_nonce)
+    }
+
+    async fn has_submitted_in_last_checkpoint_height(
+        &self,
+        subnet_id: &SubnetID,
+        submitter: &Address,
+    ) -> Result<bool> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet_id)?;
+        Ok(actor.last_checkpoint_submitters.contains(submitter))
+    }
+
+    async fn checkpoint_period(&self, subnet_id: &SubnetID) -> Result<ChainEpoch> {
+        let state = self.lock();
+        let actor = state.chain(&self.chain)?.subnet_actor(subnet_id)?;
+        Ok(actor.params.bottomup_check_period)
+    }
+
+    async fn checkpoint_bundle_at(&self, height: ChainEpoch) -> Result<BottomUpCheckpointBundle> {
+        let state = self.lock();
+        state
+            .chain(&self.chain)?
+            .checkpoints
+            .get(&height)
+            .cloned()
+            .ok_or_else(|| anyhow!("checkpoint does not exists: {height:}"))
+    }
+
+    async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>> {
+        let state = self.lock();
+        let chain = state.chain(&self.chain)?;
+        let Some(bundle) = chain.checkpoints.get(&height) else {
+            return Ok(vec![]);
+        };
+
+        // every checkpoint in the mock is signed by the full active validator set, so the
+        // quorum is reached in the same block the checkpoint is cut.
+        let committee = state.checkpoint_committee(&self.chain).unwrap_or_default();
+        let quorum_weight = committee
+            .1
+            .iter()
+            .filter(|(addr, _)| bundle.signatories.contains(addr))
+            .map(|(_, weight)| weight)
+            .sum();
+        Ok(vec![QuorumReachedEvent {
+            height,
+            checkpoint: checkpoint_hash(&bundle.checkpoint)?.to_vec(),
+            quorum_weight,
+        }])
+    }
+
+    async fn current_epoch(&self) -> Result<ChainEpoch> {
+        Ok(self.lock().chain(&self.chain)?.head())
+    }
+}
+
+/// Deterministically derives the hash of a block from the chain, its height and
+/// the hash of its parent.
+fn block_hash(chain: &SubnetID, height: ChainEpoch, parent: &[u8; 32]) -> [u8; 32] {
+    let mut payload = chain.to_string().into_bytes();
+    payload.extend_from_slice(&height.to_be_bytes());
+    payload.extend_from_slice(parent);
+    keccak256(payload)
+}
+
+/// Derives the hash of the transaction mined at a height, each block includes one.
+fn mock_tx_hash(chain: &SubnetID, height: ChainEpoch) -> [u8; 32] {
+    keccak256(format!("{chain}/tx/{height}"))
+}
+
+fn checkpoint_hash(checkpoint: &BottomUpCheckpoint) -> Result<[u8; 32]> {
+    Ok(keccak256(fvm_ipld_encoding::to_vec(checkpoint)?))
+}
+
+/// The payload of deposit and withdraw staking changes, i.e. the abi encoded amount.
+fn collateral_payload(amount: &TokenAmount) -> Result<Vec<u8>> {
+    Ok(ethers::abi::encode(&[Token::Uint(fil_to_eth_amount(
+        amount,
+    )?)]))
+}
@@ -0,0 +1,517 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use fvm_shared::address::Address;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::address::IPCAddress;
+use ipc_sdk::cross::{CrossMsgBuilder, IPCMsgType, METHOD_INVOKE_EVM};
+use ipc_sdk::gateway::Status;
+use ipc_sdk::subnet::{ConsensusType, ConstructParams};
+use ipc_sdk::subnet_id::SubnetID;
+use url::Url;
+
+use crate::checkpoint::BottomUpCheckpointManager;
+use crate::config::subnet::{EVMSubnet, SubnetConfig};
+use crate::config::{Config, Subnet};
+use crate::lifecycle::{FundStage, FundTracker, ReleaseStage, ReleaseTracker};
+use crate::manager::mock::{MockHierarchy, MockSubnetManager};
+use crate::manager::{BottomUpCheckpointRelayer, IpcEvent, SubnetManager, TopDownFinalityQuery};
+use crate::IpcProvider;
+
+const ROOT_ID: u64 = 123;
+const CHECKPOINT_PERIOD: i64 = 10;
+
+fn gateway() -> Address {
+    Address::new_id(64)
+}
+
+fn subnet_config(id: SubnetID) -> Subnet {
+    Subnet {
+        id,
+        config: SubnetConfig::Fevm(EVMSubnet {
+            provider_http: Url::from_str("http://127.0.0.1:8545").unwrap(),
+            registry_addr: Address::new_id(65),
+            gateway_addr: gateway(),
+            ..Default::default()
+        }),
+    }
+}
+
+fn construct_params(parent: SubnetID) -> ConstructParams {
+    ConstructParams {
+        parent,
+        ipc_gateway_addr: gateway(),
+        consensus: ConsensusType::Mir,
+        min_validators: 1,
+        min_validator_stake: TokenAmount::from_whole(1),
+        bottomup_check_period: CHECKPOINT_PERIOD,
+        active_validators_limit: 1,
+        min_cross_msg_fee: TokenAmount::from_atto(100),
+    }
+}
+
+/// Deploys a child subnet in the root and bootstraps it with `validator`.
+async fn bootstrapped_subnet(
+    hierarchy: &MockHierarchy,
+    validator: Address,
+) -> (MockSubnetManager, MockSubnetManager, Subnet, Subnet) {
+    let root = subnet_config(SubnetID::new_root(ROOT_ID));
+    let parent = hierarchy.manager(&root);
+    hierarchy
+        .set_balance(&root.id, validator, TokenAmount::from_whole(100))
+        .unwrap();
+
+    let (actor, _) = parent
+        .create_subnet(validator, construct_params(root.id.clone()))
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    let child = subnet_config(SubnetID::new_from_parent(&root.id, actor));
+    parent
+        .join_subnet(
+            child.id.clone(),
+            validator,
+            TokenAmount::from_whole(10),
+            vec![1, 2, 3],
+        )
+        .await
+        .unwrap();
+
+    (parent, hierarchy.manager(&child), root, child)
+}
+
+#[tokio::test]
+async fn test_subnet_lifecycle() {
+    let hierarchy = MockHierarchy::new();
+    let validator = Address::new_id(100);
+    let waiting = Address::new_id(101);
+
+    let (parent, _, root, child) = bootstrapped_subnet(&hierarchy, validator).await;
+    hierarchy
+        .set_balance(&root.id, waiting, TokenAmount::from_whole(10))
+        .unwrap();
+
+    let subnets = parent.list_child_subnets(gateway()).await.unwrap();
+    let info = subnets.get(&child.id).unwrap();
+    assert_eq!(info.status, Status::Active);
+    assert_eq!(info.stake, TokenAmount::from_whole(10));
+
+    let genesis = parent.get_genesis_info(&child.id).await.unwrap();
+    assert_eq!(genesis.validators.len(), 1);
+    assert_eq!(genesis.validators[0].addr, validator);
+    assert_eq!(genesis.genesis_epoch, 2);
+
+    // the active validators limit is one, so a second validator stays waiting
+    let outcome = parent
+        .join_subnet(
+            child.id.clone(),
+            waiting,
+            TokenAmount::from_whole(5),
+            vec![],
+        )
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    assert_eq!(
+        outcome.block_number,
+        parent.chain_head_height().await.unwrap()
+    );
+    // joining emits the metadata and the deposit staking changes
+    assert_eq!(outcome.events.len(), 2);
+    assert!(outcome
+        .events
+        .iter()
+        .all(|e| matches!(e, IpcEvent::NewStakingChangeRequest(_))));
+    let info = parent
+        .get_validator_info(&child.id, &waiting)
+        .await
+        .unwrap();
+    assert!(info.is_waiting);
+    assert!(!info.is_active);
+
+    let details = parent.get_subnet_info(&child.id).await.unwrap();
+    assert_eq!(details.status, Status::Active);
+    assert_eq!(details.stake, TokenAmount::from_whole(15));
+    assert_eq!(details.genesis_epoch, 2);
+    assert_eq!(details.active_validators, 1);
+    assert_eq!(details.waiting_validators, 1);
+
+    let validators = parent.list_validators(&child.id).await.unwrap();
+    assert_eq!(validators.len(), 2);
+    assert_eq!(validators[0].address, validator);
+    assert_eq!(validators[0].rank, 1);
+    assert_eq!(validators[0].power, TokenAmount::from_whole(10));
+    assert_eq!(validators[1].address, waiting);
+    assert!(validators[1].info.is_waiting);
+    assert_eq!(validators[1].power, TokenAmount::from_whole(0));
+
+    let changes = parent
+        .get_validator_changeset(&child.id, parent.chain_head_height().await.unwrap())
+        .await
+        .unwrap();
+    assert_eq!(changes.value.len(), 2);
+    let head = parent.chain_head_height().await.unwrap();
+    let ranges = parent
+        .get_validator_changeset_range(&child.id, 0, head)
+        .await
+        .unwrap();
+    assert_eq!(ranges.len(), 1);
+    assert_eq!(ranges[0].height, head);
+    assert_eq!(ranges[0].block_hash, changes.block_hash);
+    let numbers = ranges[0].configuration_numbers();
+    assert_eq!(numbers[1], numbers[0] + 1);
+
+    parent
+        .leave_subnet(child.id.clone(), waiting)
+        .await
+        .unwrap();
+    parent
+        .claim_collateral(child.id.clone(), waiting)
+        .await
+        .unwrap();
+    assert_eq!(
+        parent.wallet_balance(&waiting).await.unwrap(),
+        TokenAmount::from_whole(10)
+    );
+
+    // cannot kill a subnet with validators
+    assert!(parent
+        .kill_subnet(child.id.clone(), validator)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_deterministic_blocks() {
+    let validator = Address::new_id(100);
+
+    let mut hashes = vec![];
+    for _ in 0..2 {
+        let hierarchy = MockHierarchy::new();
+        let (parent, _, _, _) = bootstrapped_subnet(&hierarchy, validator).await;
+        let head = parent.chain_head_height().await.unwrap();
+        hashes.push(parent.get_block_hash(head).await.unwrap());
+    }
+    assert_eq!(hashes[0].block_hash, hashes[1].block_hash);
+
+    let hierarchy = MockHierarchy::new();
+    let (parent, _, root, _) = bootstrapped_subnet(&hierarchy, validator).await;
+    let head = hierarchy.advance_blocks(&root.id, 5).unwrap();
+    assert_eq!(parent.chain_head_height().await.unwrap(), head);
+
+    let prev = parent.get_block_hash(head - 1).await.unwrap();
+    let curr = parent.get_block_hash(head).await.unwrap();
+    assert_eq!(curr.parent_block_hash, prev.block_hash);
+}
+
+#[tokio::test]
+async fn test_fund_and_commit_finality() {
+    let hierarchy = MockHierarchy::new();
+    let validator = Address::new_id(100);
+    let user = Address::new_id(200);
+
+    let (parent, child_manager, _, child) = bootstrapped_subnet(&hierarchy, validator).await;
+
+    let outcome = parent
+        .fund(
+            child.id.clone(),
+            gateway(),
+            validator,
+            user,
+            TokenAmount::from_whole(3),
+        )
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    let epoch = outcome.block_number;
+
+    let hash = parent.get_block_hash(epoch).await.unwrap().block_hash;
+    assert_eq!(hash, outcome.block_hash);
+    let msgs = parent
+        .get_top_down_msgs(&child.id, epoch, &hash)
+        .await
+        .unwrap();
+    assert_eq!(msgs.len(), 1);
+    assert_eq!(msgs[0].msg.value, TokenAmount::from_whole(3));
+    assert!(parent
+        .get_top_down_msgs(&child.id, epoch, &[0; 32])
+        .await
+        .is_err());
+
+    parent
+        .fund(
+            child.id.clone(),
+            gateway(),
+            validator,
+            user,
+            TokenAmount::from_whole(1),
+        )
+        .await
+        .unwrap();
+    let head = parent.chain_head_height().await.unwrap();
+    let msgs = parent
+        .get_top_down_msgs_range(&child.id, 0, head)
+        .await
+        .unwrap();
+    assert_eq!(msgs.len(), 2);
+    assert_eq!(msgs[0].height, epoch);
+    assert_eq!(msgs[0].block_hash, hash);
+    assert_eq!(msgs[1].height, head);
+    assert_eq!(msgs[1].nonces(), vec![msgs[0].nonces()[0] + 1]);
+    assert!(parent
+        .get_top_down_msgs_range(&child.id, head, epoch)
+        .await
+        .is_err());
+
+    let tracker = FundTracker::new(&child.id, &parent, &child_manager);
+    let status = tracker.status(&outcome.tx_hash).await.unwrap();
+    assert_eq!(status.stage, FundStage::Pending);
+    assert_eq!(status.msg.unwrap().height, epoch);
+    assert_eq!(
+        tracker.status(&[0; 32]).await.unwrap().stage,
+        FundStage::Pending
+    );
+
+    hierarchy.commit_parent_finality(&child.id, epoch).unwrap();
+    assert_eq!(child_manager.latest_parent_finality().await.unwrap(), epoch);
+    let status = tracker
+        .wait(&outcome.tx_hash, Duration::from_millis(10))
+        .await
+        .unwrap();
+    assert_eq!(status.stage, FundStage::ExecutedInChild);
+    assert_eq!(
+        child_manager.wallet_balance(&user).await.unwrap(),
+        TokenAmount::from_whole(3)
+    );
+}
+
+#[tokio::test]
+async fn test_checkpoint_manager_relays_release() {
+    let hierarchy = MockHierarchy::new();
+    let validator = Address::new_id(100);
+    let user = Address::new_id(200);
+
+    let (parent, child_manager, root, child) = bootstrapped_subnet(&hierarchy, validator).await;
+    hierarchy
+        .set_balance(&child.id, user, TokenAmount::from_whole(5))
+        .unwrap();
+    let outcome = child_manager
+        .release(gateway(), user, user, TokenAmount::from_whole(2), None)
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+
+    // the message waits for the next checkpoint to be cut
+    let tracker = ReleaseTracker::new(&child.id, &parent, &child_manager);
+    let status = tracker.status(&outcome.tx_hash).await.unwrap();
+    assert_eq!(status.stage, ReleaseStage::Pending);
+    let msg = status.msg.unwrap();
+    assert_eq!(msg.height, outcome.block_number);
+    assert_eq!(msg.checkpoint_height, CHECKPOINT_PERIOD);
+    assert_eq!(msg.msg.msg.value, TokenAmount::from_whole(2));
+    assert!(tracker.status(&[0; 32]).await.unwrap().msg.is_none());
+
+    hierarchy
+        .advance_blocks(&child.id, CHECKPOINT_PERIOD)
+        .unwrap();
+    assert_eq!(
+        tracker.status(&outcome.tx_hash).await.unwrap().stage,
+        ReleaseStage::QuorumReached
+    );
+
+    let relayer = Address::new_id(300);
+    let manager = BottomUpCheckpointManager::new(
+        root.clone(),
+        child.clone(),
+        hierarchy.manager(&root),
+        hierarchy.manager(&child),
+    )
+    .await
+    .unwrap();
+    assert_eq!(manager.checkpoint_period(), CHECKPOINT_PERIOD);
+    manager.submit_checkpoint(&relayer).await.unwrap();
+
+    assert_eq!(
+        parent
+            .last_bottom_up_checkpoint_height(&child.id)
+            .await
+            .unwrap(),
+        CHECKPOINT_PERIOD
+    );
+    assert!(parent
+        .has_submitted_in_last_checkpoint_height(&child.id, &relayer)
+        .await
+        .unwrap());
+    assert_eq!(
+        parent.wallet_balance(&user).await.unwrap(),
+        TokenAmount::from_whole(2)
+    );
+    let status = tracker
+        .wait(&outcome.tx_hash, Duration::from_millis(10))
+        .await
+        .unwrap();
+    assert_eq!(status.stage, ReleaseStage::ExecutedInParent);
+
+    // submitting again is a no-op until the next checkpoint is cut
+    manager.submit_checkpoint(&relayer).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_track_releases_in_same_period() {
+    let hierarchy = MockHierarchy::new();
+    let validator = Address::new_id(100);
+    let user = Address::new_id(200);
+
+    let (parent, child_manager, _, child) = bootstrapped_subnet(&hierarchy, validator).await;
+    hierarchy
+        .set_balance(&child.id, user, TokenAmount::from_whole(5))
+        .unwrap();
+    let first = child_manager
+        .release(gateway(), user, user, TokenAmount::from_whole(1), None)
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    hierarchy.advance_blocks(&child.id, 1).unwrap();
+    let second = child_manager
+        .release(gateway(), user, user, TokenAmount::from_whole(2), None)
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    assert!(second.block_number < CHECKPOINT_PERIOD);
+
+    // both releases go to the same recipient in the same checkpoint, each tracks its own
+    // message.
+    let tracker = ReleaseTracker::new(&child.id, &parent, &child_manager);
+    let first = tracker.status(&first.tx_hash).await.unwrap().msg.unwrap();
+    let second = tracker.status(&second.tx_hash).await.unwrap().msg.unwrap();
+    assert_eq!(first.checkpoint_height, second.checkpoint_height);
+    assert_eq!(first.msg.msg.value, TokenAmount::from_whole(1));
+    assert_eq!(second.msg.msg.value, TokenAmount::from_whole(2));
+    assert_eq!(second.msg.msg.nonce, first.msg.msg.nonce + 1);
+}
+
+#[tokio::test]
+async fn test_provider_with_mock_hierarchy() {
+    let hierarchy = MockHierarchy::new();
+    let validator = Address::new_id(100);
+
+    let root = subnet_config(SubnetID::new_root(ROOT_ID));
+    let mut config = Config::new();
+    config.add_subnet(root.clone());
+    hierarchy.add_chain(&root.id);
+    hierarchy
+        .set_balance(&root.id, validator, TokenAmount::from_whole(100))
+        .unwrap();
+
+    let mut provider = IpcProvider::new_with_mock_hierarchy(config, hierarchy);
+    provider.with_sender(validator);
+
+    let (actor, _) = provider
+        .create_subnet(
+            None,
+            root.id.clone(),
+            1,
+            TokenAmount::from_whole(1),
+            CHECKPOINT_PERIOD,
+            1,
+            TokenAmount::from_atto(100),
+        )
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    let child = SubnetID::new_from_parent(&root.id, actor);
+
+    provider
+        .join_subnet(child.clone(), None, TokenAmount::from_whole(10), vec![])
+        .await
+        .unwrap();
+    provider
+        .fund(child.clone(), None, None, None, TokenAmount::from_whole(1))
+        .await
+        .unwrap();
+
+    let subnets = provider.list_child_subnets(None, &root.id).await.unwrap();
+    assert_eq!(
+        subnets.get(&child).unwrap().circ_supply,
+        TokenAmount::from_whole(1)
+    );
+    assert_eq!(
+        provider.wallet_balance(&root.id, &validator).await.unwrap(),
+        TokenAmount::from_whole(89)
+    );
+
+    // a call to a contract in the child carrying some value
+    let contract = Address::new_delegated(10, &[1; 20]).unwrap();
+    let msg = CrossMsgBuilder::new(IPCAddress::new(&child, &contract).unwrap())
+        .method(METHOD_INVOKE_EVM)
+        .value(TokenAmount::from_whole(1));
+    let outcome = provider
+        .send_cross_message(root.id.clone(), None, None, msg)
+        .await
+        .unwrap()
+        .sent()
+        .unwrap();
+    let msgs = provider
+        .get_top_down_msgs_range(&child, 1, outcome.block_number)
+        .await
+        .unwrap();
+    let msg = &msgs.last().unwrap().msgs[0];
+    assert_eq!(msg.msg.nonce, 1);
+    assert_eq!(msg.msg.to.raw_addr().unwrap(), contract);
+    assert_eq!(msg.msg.method, METHOD_INVOKE_EVM);
+    assert_eq!(
+        provider.wallet_balance(&root.id, &validator).await.unwrap(),
+        TokenAmount::from_whole(88)
+    );
+}
+
+#[tokio::test]
+async fn test_plan_route_between_siblings() {
+    let hierarchy = MockHierarchy::new();
+    let validator = Address::new_id(100);
+
+    let (_, _, root, first) = bootstrapped_subnet(&hierarchy, validator).await;
+    let (_, _, _, second) = bootstrapped_subnet(&hierarchy, validator).await;
+    let mut config = Config::new();
+    config.add_subnet(root.clone());
+    config.add_subnet(first.clone());
+    config.add_subnet(second.clone());
+    let provider = IpcProvider::new_with_mock_hierarchy(config, hierarchy);
+
+    let from = IPCAddress::new(&first.id, &validator).unwrap();
+    let contract = Address::new_delegated(10, &[1; 20]).unwrap();
+    let to = IPCAddress::new(&second.id, &contract).unwrap();
+    let plan = provider.plan_route(&from, &to).await.unwrap();
+    assert!(plan.is_multi_hop());
+
+    // up to the root with a checkpoint, then down to the sibling with the parent finality
+    let hops = &plan.hops;
+    assert_eq!(hops.len(), 2);
+    assert_eq!((&hops[0].from, &hops[0].to), (&first.id, &root.id));
+    assert_eq!(hops[0].msg_type, IPCMsgType::BottomUp);
+    assert_eq!(hops[0].checkpoint_period, Some(CHECKPOINT_PERIOD));
+    assert_eq!(hops[0].min_fee, TokenAmount::from_atto(100));
+    assert!(!hops[0].propagate);
+    assert_eq!((&hops[1].from, &hops[1].to), (&root.id, &second.id));
+    assert_eq!(hops[1].msg_type, IPCMsgType::TopDown);
+    assert_eq!(hops[1].checkpoint_period, None);
+    assert!(hops[1].propagate);
+
+    assert_eq!(plan.total_fee(), TokenAmount::from_atto(100));
+    assert_eq!(plan.checkpoint_latency(), CHECKPOINT_PERIOD);
+
+    // every subnet along the route must be configured
+    let unknown = SubnetID::new_from_parent(&second.id, Address::new_id(1000));
+    let from = IPCAddress::new(&unknown, &validator).unwrap();
+    assert!(provider.plan_route(&from, &to).await.is_err());
+}
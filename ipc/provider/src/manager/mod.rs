@@ -1,11 +1,21 @@
 // Copyright 2022-2023 Protocol Labs
 // SPDX-License-Identifier: MIT
 pub use crate::lotus::message::ipc::SubnetInfo;
-pub use evm::{EthManager, EthSubnetManager};
+pub use evm::{
+    DryRunReport, EthManager, EthSubnetManager, EventSubscriber, EventSubscription,
+    GasCeilingExceeded, GasFees, GasStrategy, IpcContractError, IpcEventFilter, IpcEventQuery,
+    IpcEventStream, IpcLog, IpcLogStream, NonceManager, SignedTransaction, TransactionSigner,
+    TxRevertedError, TxTimeoutError, UnsignedTransaction, Web3Signer,
+};
+pub use fvm::{LotusSubnetManager, MessageAbortedError};
+pub use mock::{MockHierarchy, MockSubnetManager};
 pub use subnet::{
-    BottomUpCheckpointRelayer, GetBlockHashResult, SubnetGenesisInfo, SubnetManager,
-    TopDownFinalityQuery, TopDownQueryPayload,
+    BottomUpCheckpointRelayer, CheckpointedBottomUpMsg, CommittedTopDownMsg, GetBlockHashResult,
+    IpcEvent, NullRoundError, SubnetDetails, SubnetGenesisInfo, SubnetManager,
+    TopDownFinalityQuery, TopDownMsgs, TopDownQueryPayload, TxOutcome, TxResult, ValidatorChanges,
 };
 
 pub mod evm;
+pub mod fvm;
+pub mod mock;
 mod subnet;
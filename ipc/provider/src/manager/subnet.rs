@@ -2,28 +2,38 @@
 // SPDX-License-Identifier: MIT
 
 use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use fvm_shared::clock::ChainEpoch;
 use fvm_shared::{address::Address, econ::TokenAmount};
 use ipc_sdk::checkpoint::{BottomUpCheckpointBundle, QuorumReachedEvent};
 use ipc_sdk::cross::CrossMsg;
-use ipc_sdk::staking::{StakingChangeRequest, ValidatorInfo};
+use ipc_sdk::gateway::Status;
+use ipc_sdk::staking::{ConfigurationNumber, StakingChangeRequest, SubnetValidator, ValidatorInfo};
 use ipc_sdk::subnet::ConstructParams;
 use ipc_sdk::subnet_id::SubnetID;
 use ipc_sdk::validator::Validator;
 
 use crate::lotus::message::ipc::SubnetInfo;
+use crate::manager::evm::{DryRunReport, UnsignedTransaction};
 
 /// Trait to interact with a subnet and handle its lifecycle.
+///
+/// All the state-changing operations wait for the transaction to be included in a block
+/// and return its [`TxOutcome`], unless the manager only simulates them, see [`TxResult`].
 #[async_trait]
 pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpointRelayer {
     /// Deploys a new subnet actor on the `parent` subnet and with the
     /// configuration passed in `ConstructParams`.
     /// The result of the function is the ID address for the subnet actor from which the final
-    /// subet ID can be inferred.
-    async fn create_subnet(&self, from: Address, params: ConstructParams) -> Result<Address>;
+    /// subet ID can be inferred, and the outcome of the deployment transaction.
+    async fn create_subnet(
+        &self,
+        from: Address,
+        params: ConstructParams,
+    ) -> Result<TxResult<(Address, TxOutcome)>>;
 
     /// Performs the call to join a subnet from a wallet address and staking an amount
     /// of collateral. This function, as well as all of the ones on this trait, can infer
@@ -35,30 +45,48 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         from: Address,
         collateral: TokenAmount,
         metadata: Vec<u8>,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxResult>;
 
     /// Adds some initial balance to an address before a child subnet bootstraps to make
     /// it available in the subnet at genesis.
-    async fn pre_fund(&self, subnet: SubnetID, from: Address, balance: TokenAmount) -> Result<()>;
+    async fn pre_fund(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        balance: TokenAmount,
+    ) -> Result<TxResult>;
 
     /// Releases initial funds from an address for a subnet that has not yet been bootstrapped
-    async fn pre_release(&self, subnet: SubnetID, from: Address, amount: TokenAmount)
-        -> Result<()>;
+    async fn pre_release(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        amount: TokenAmount,
+    ) -> Result<TxResult>;
 
     /// Allows validators that have already joined the subnet to stake more collateral
     /// and increase their power in the subnet.
-    async fn stake(&self, subnet: SubnetID, from: Address, collateral: TokenAmount) -> Result<()>;
+    async fn stake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<TxResult>;
 
     /// Allows validators that have already joined the subnet to unstake collateral
     /// and reduce their power in the subnet.
-    async fn unstake(&self, subnet: SubnetID, from: Address, collateral: TokenAmount)
-        -> Result<()>;
+    async fn unstake(
+        &self,
+        subnet: SubnetID,
+        from: Address,
+        collateral: TokenAmount,
+    ) -> Result<TxResult>;
 
     /// Sends a request to leave a subnet from a wallet address.
-    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<()>;
+    async fn leave_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult>;
 
     /// Sends a signal to kill a subnet
-    async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<()>;
+    async fn kill_subnet(&self, subnet: SubnetID, from: Address) -> Result<TxResult>;
 
     /// Lists all the registered children in a gateway.
     async fn list_child_subnets(
@@ -68,15 +96,16 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
 
     /// Claims any collateral that may be available to claim by validators that
     /// have left the subnet.
-    async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<()>;
+    async fn claim_collateral(&self, subnet: SubnetID, from: Address) -> Result<TxResult>;
 
     /// Claims any reward that may be available for a relayer. Relayer rewards
     /// are obtained by submitting bottom-up checkpoints to the parent of a child
     /// subnet.
-    async fn claim_relayer_reward(&self, subnet: SubnetID, from: Address) -> Result<()>;
+    async fn claim_relayer_reward(&self, subnet: SubnetID, from: Address) -> Result<TxResult>;
 
     /// Fund injects new funds from an account of the parent chain to a subnet.
-    /// Returns the epoch that the fund is executed in the parent.
+    /// The block number of the returned outcome is the epoch that the fund is executed
+    /// in the parent.
     async fn fund(
         &self,
         subnet: SubnetID,
@@ -84,10 +113,11 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         from: Address,
         to: Address,
         amount: TokenAmount,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxResult>;
 
     /// Release creates a new check message to release funds in parent chain
-    /// Returns the epoch that the released is executed in the child.
+    /// The block number of the returned outcome is the epoch that the release is executed
+    /// in the child.
     async fn release(
         &self,
         gateway_addr: Address,
@@ -95,7 +125,7 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         to: Address,
         amount: TokenAmount,
         fee: Option<TokenAmount>,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxResult>;
 
     /// Propagate a cross-net message forward. For `postbox_msg_key`, we are using bytes because different
     /// runtime have different representations. For FVM, it should be `CID` as bytes. For EVM, it is
@@ -106,17 +136,18 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         gateway_addr: Address,
         from: Address,
         postbox_msg_key: Vec<u8>,
-    ) -> Result<()>;
+    ) -> Result<TxResult>;
 
     async fn send_cross_message(
         &self,
         gateway_addr: Address,
         from: Address,
         cross_msg: CrossMsg,
-    ) -> Result<()>;
+    ) -> Result<TxResult>;
 
     /// Send value between two addresses in a subnet
-    async fn send_value(&self, from: Address, to: Address, amount: TokenAmount) -> Result<()>;
+    async fn send_value(&self, from: Address, to: Address, amount: TokenAmount)
+        -> Result<TxResult>;
 
     /// Get the balance of an address
     async fn wallet_balance(&self, address: &Address) -> Result<TokenAmount>;
@@ -135,7 +166,7 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         subnet: &SubnetID,
         from: &Address,
         endpoint: String,
-    ) -> Result<()>;
+    ) -> Result<TxResult>;
 
     /// Lists the bootstrap nodes of a subnet
     async fn list_bootstrap_nodes(&self, subnet: &SubnetID) -> Result<Vec<String>>;
@@ -146,6 +177,34 @@ pub trait SubnetManager: Send + Sync + TopDownFinalityQuery + BottomUpCheckpoint
         subnet: &SubnetID,
         validator: &Address,
     ) -> Result<ValidatorInfo>;
+
+    /// Gets the status and configuration of a child subnet, as registered in the gateway
+    /// and the subnet actor of its parent.
+    async fn get_subnet_info(&self, subnet: &SubnetID) -> Result<SubnetDetails>;
+
+    /// Lists the active and waiting validators of a child subnet, ranked by confirmed
+    /// collateral.
+    async fn list_validators(&self, subnet: &SubnetID) -> Result<Vec<SubnetValidator>>;
+
+    /// Returns the top-down message committed in the gateway by a `fund` transaction to a
+    /// child subnet, or `None` if the transaction is not included in a block yet.
+    async fn get_fund_msg(
+        &self,
+        subnet: &SubnetID,
+        tx_hash: &[u8],
+    ) -> Result<Option<CommittedTopDownMsg>>;
+
+    /// Returns the bottom-up message sent by a `release` transaction in this subnet and the
+    /// height of the checkpoint that carries it, or `None` if the transaction is not included
+    /// in a block yet. `checkpoint_period` is the bottom-up checkpoint period of the subnet.
+    async fn get_release_msg(
+        &self,
+        tx_hash: &[u8],
+        checkpoint_period: ChainEpoch,
+    ) -> Result<Option<CheckpointedBottomUpMsg>>;
+
+    /// The minimum fee charged by the gateway of this subnet for sending a cross-net message.
+    async fn cross_msg_fee(&self) -> Result<TokenAmount>;
 }
 
 #[derive(Debug)]
@@ -160,6 +219,156 @@ pub struct SubnetGenesisInfo {
     pub genesis_balances: BTreeMap<Address, TokenAmount>,
 }
 
+/// The status and configuration of a child subnet, see [`SubnetManager::get_subnet_info`].
+#[derive(Debug, Clone)]
+pub struct SubnetDetails {
+    pub id: SubnetID,
+    /// Subnets that have not been bootstrapped yet are not registered in the gateway and
+    /// are reported as inactive, with no stake nor circulating supply.
+    pub status: Status,
+    pub stake: TokenAmount,
+    pub circ_supply: TokenAmount,
+    pub genesis_epoch: ChainEpoch,
+    pub bottom_up_checkpoint_period: u64,
+    pub majority_percentage: u8,
+    pub min_collateral: TokenAmount,
+    pub min_cross_msg_fee: TokenAmount,
+    /// The number of validators in the active set.
+    pub active_validators: u16,
+    /// The number of validators that joined but are not in the active set.
+    pub waiting_validators: u16,
+    pub bootstrap_nodes: Vec<String>,
+}
+
+impl Display for SubnetDetails {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "subnet: {}", self.id)?;
+        writeln!(f, "  status: {:?}", self.status)?;
+        writeln!(f, "  stake: {}", self.stake)?;
+        writeln!(f, "  circulating supply: {}", self.circ_supply)?;
+        writeln!(f, "  genesis epoch: {}", self.genesis_epoch)?;
+        writeln!(
+            f,
+            "  bottom-up checkpoint period: {}",
+            self.bottom_up_checkpoint_period
+        )?;
+        writeln!(f, "  majority percentage: {}", self.majority_percentage)?;
+        writeln!(f, "  min collateral: {}", self.min_collateral)?;
+        writeln!(f, "  min cross-msg fee: {}", self.min_cross_msg_fee)?;
+        writeln!(f, "  active validators: {}", self.active_validators)?;
+        writeln!(f, "  waiting validators: {}", self.waiting_validators)?;
+        write!(f, "  bootstrap nodes: {}", self.bootstrap_nodes.join(", "))
+    }
+}
+
+/// The outcome of a state-changing transaction once it has been included in a block.
+#[derive(Debug, Clone, Default)]
+pub struct TxOutcome {
+    /// The hash of the transaction, or the CID bytes of the message for native subnets.
+    pub tx_hash: Vec<u8>,
+    /// The height of the block that included the transaction.
+    pub block_number: ChainEpoch,
+    /// The hash of the block that included the transaction.
+    pub block_hash: Vec<u8>,
+    pub gas_used: u64,
+    /// The price per unit of gas actually paid, if reported by the network.
+    pub effective_gas_price: Option<TokenAmount>,
+    /// The IPC events emitted by the transaction.
+    pub events: Vec<IpcEvent>,
+}
+
+impl TxOutcome {
+    /// The address of the subnet actor deployed by the transaction, from its
+    /// `SubnetDeployed` event.
+    pub fn deployed_subnet(&self) -> Result<Address> {
+        self.events
+            .iter()
+            .find_map(|event| match event {
+                IpcEvent::SubnetDeployed { subnet_addr } => Some(*subnet_addr),
+                _ => None,
+            })
+            .ok_or_else(|| anyhow!("no subnet deployed event in receipt"))
+    }
+}
+
+impl Display for TxOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tx 0x{} included at epoch {} (block 0x{}), gas used: {}",
+            hex::encode(&self.tx_hash),
+            self.block_number,
+            hex::encode(&self.block_hash),
+            self.gas_used
+        )
+    }
+}
+
+/// The result of a state-changing operation. Transactions are only sent to the network
+/// outside of the dry-run and offline-signing modes of the manager, see
+/// `EthSubnetManager::with_dry_run` and `EthSubnetManager::with_offline_signing`.
+#[derive(Debug, Clone)]
+pub enum TxResult<T = TxOutcome> {
+    /// The transaction was sent, with the outcome of its execution.
+    Sent(T),
+    /// The transaction was simulated instead of sent.
+    Simulated(DryRunReport),
+    /// The transaction was built to be signed offline instead of sent.
+    Unsigned(UnsignedTransaction),
+}
+
+impl<T> TxResult<T> {
+    /// Converts the outcome of a sent transaction with `f`.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> TxResult<U> {
+        match self {
+            TxResult::Sent(outcome) => TxResult::Sent(f(outcome)),
+            TxResult::Simulated(report) => TxResult::Simulated(report),
+            TxResult::Unsigned(unsigned) => TxResult::Unsigned(unsigned),
+        }
+    }
+
+    /// Same as `map`, with a conversion that can fail.
+    pub fn try_map<U>(self, f: impl FnOnce(T) -> Result<U>) -> Result<TxResult<U>> {
+        Ok(match self {
+            TxResult::Sent(outcome) => TxResult::Sent(f(outcome)?),
+            TxResult::Simulated(report) => TxResult::Simulated(report),
+            TxResult::Unsigned(unsigned) => TxResult::Unsigned(unsigned),
+        })
+    }
+
+    /// The outcome of the transaction, failing if it was not sent.
+    pub fn sent(self) -> Result<T> {
+        match self {
+            TxResult::Sent(outcome) => Ok(outcome),
+            TxResult::Simulated(report) => {
+                Err(anyhow!("`{}` was simulated, not sent", report.method))
+            }
+            TxResult::Unsigned(unsigned) => Err(anyhow!(
+                "`{}` was built to be signed, not sent",
+                unsigned.method
+            )),
+        }
+    }
+}
+
+/// The events emitted by the IPC actors that are relevant to the client.
+#[derive(Debug, Clone)]
+pub enum IpcEvent {
+    /// A new subnet actor was deployed by the registry.
+    SubnetDeployed { subnet_addr: Address },
+    /// A validator staking change that will be propagated to the child subnet.
+    NewStakingChangeRequest(StakingChangeRequest),
+    /// The signatures of a bottom-up checkpoint reached quorum.
+    QuorumReached(QuorumReachedEvent),
+    /// Top-down messages for a child subnet committed in the gateway of its parent.
+    NewTopDownMessages {
+        subnet_id: SubnetID,
+        height: ChainEpoch,
+        block_hash: Vec<u8>,
+        msgs: Vec<CrossMsg>,
+    },
+}
+
 /// The generic payload that returns the block hash of the data returning block with the actual
 /// data payload.
 #[derive(Debug)]
@@ -188,7 +397,7 @@ pub trait TopDownFinalityQuery: Send + Sync {
         epoch: ChainEpoch,
         block_hash: &[u8],
     ) -> Result<Vec<CrossMsg>>;
-    /// Get the block hash
+    /// Get the block hash, fails with a `NullRoundError` if no block was produced at `height`.
     async fn get_block_hash(&self, height: ChainEpoch) -> Result<GetBlockHashResult>;
     /// Get the validator change set from start to end block.
     async fn get_validator_changeset(
@@ -198,6 +407,160 @@ pub trait TopDownFinalityQuery: Send + Sync {
     ) -> Result<TopDownQueryPayload<Vec<StakingChangeRequest>>>;
     /// Returns the latest parent finality committed in a child subnet
     async fn latest_parent_finality(&self) -> Result<ChainEpoch>;
+    /// Returns the nonce of the next top-down message to be executed in a child subnet
+    async fn applied_top_down_nonce(&self) -> Result<u64>;
+
+    /// Returns the top down messages from `from` to `to`, both inclusive, grouped by height.
+    /// Each height is queried at its block hash, and the blocks must chain through their
+    /// parent block hash, otherwise the chain reorganized while it was queried. Null rounds
+    /// and heights without messages are skipped.
+    async fn get_top_down_msgs_range(
+        &self,
+        subnet_id: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<TopDownMsgs>> {
+        if from < 0 || to < from {
+            return Err(anyhow!("invalid height range {from}..={to}"));
+        }
+
+        let mut hashes = vec![];
+        for height in from..=to {
+            hashes.push((height, self.get_block_hash(height).await));
+        }
+
+        let mut result = vec![];
+        for (height, hash) in chained_blocks(hashes)? {
+            let msgs = self
+                .get_top_down_msgs(subnet_id, height, &hash.block_hash)
+                .await?;
+            if !msgs.is_empty() {
+                result.push(TopDownMsgs {
+                    height,
+                    block_hash: hash.block_hash,
+                    msgs,
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the validator changes from `from` to `to`, both inclusive, grouped by height.
+    /// Heights without changes are skipped.
+    async fn get_validator_changeset_range(
+        &self,
+        subnet_id: &SubnetID,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<ValidatorChanges>> {
+        if from < 0 || to < from {
+            return Err(anyhow!("invalid height range {from}..={to}"));
+        }
+
+        let mut result = vec![];
+        for height in from..=to {
+            let payload = match self.get_validator_changeset(subnet_id, height).await {
+                Ok(payload) => payload,
+                Err(e) if e.is::<NullRoundError>() => continue,
+                Err(e) => return Err(e),
+            };
+            if !payload.value.is_empty() {
+                result.push(ValidatorChanges {
+                    height,
+                    block_hash: payload.block_hash,
+                    changes: payload.value,
+                });
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Returned when querying a height that is a null round, i.e. no block was produced at that
+/// height, as happens on Filecoin.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("height {height} is a null round")]
+pub struct NullRoundError {
+    pub height: ChainEpoch,
+}
+
+/// Skips the null rounds among the block hashes of consecutive heights, and checks that the
+/// remaining blocks chain through their parent block hash, otherwise the chain reorganized
+/// while it was queried.
+pub(crate) fn chained_blocks(
+    hashes: Vec<(ChainEpoch, Result<GetBlockHashResult>)>,
+) -> Result<Vec<(ChainEpoch, GetBlockHashResult)>> {
+    let mut blocks: Vec<(ChainEpoch, GetBlockHashResult)> = vec![];
+    for (height, hash) in hashes {
+        let hash = match hash {
+            Ok(hash) => hash,
+            Err(e) if e.is::<NullRoundError>() => continue,
+            Err(e) => return Err(e.context(format!("cannot get block hash at {height}"))),
+        };
+        if let Some((_, prev)) = blocks.last() {
+            if prev.block_hash != hash.parent_block_hash {
+                return Err(anyhow!(
+                    "block at {height} is not a child of the previous block, chain reorg?"
+                ));
+            }
+        }
+        blocks.push((height, hash));
+    }
+    Ok(blocks)
+}
+
+/// The top down messages of a parent block, see
+/// [`TopDownFinalityQuery::get_top_down_msgs_range`].
+#[derive(Debug, Clone)]
+pub struct TopDownMsgs {
+    pub height: ChainEpoch,
+    pub block_hash: Vec<u8>,
+    pub msgs: Vec<CrossMsg>,
+}
+
+impl TopDownMsgs {
+    /// The nonces of the messages, in the order they are executed in the child subnet.
+    pub fn nonces(&self) -> Vec<u64> {
+        self.msgs.iter().map(|m| m.msg.nonce).collect()
+    }
+}
+
+/// A top-down message and the parent block that committed it, see
+/// [`SubnetManager::get_fund_msg`].
+#[derive(Debug, Clone)]
+pub struct CommittedTopDownMsg {
+    pub height: ChainEpoch,
+    pub block_hash: Vec<u8>,
+    pub msg: CrossMsg,
+}
+
+/// A bottom-up message and the checkpoint that carries it, see
+/// [`SubnetManager::get_release_msg`].
+#[derive(Debug, Clone)]
+pub struct CheckpointedBottomUpMsg {
+    /// The height of the block that included the transaction that sent the message.
+    pub height: ChainEpoch,
+    pub checkpoint_height: ChainEpoch,
+    pub msg: CrossMsg,
+}
+
+/// The validator changes of a parent block, see
+/// [`TopDownFinalityQuery::get_validator_changeset_range`].
+#[derive(Debug, Clone)]
+pub struct ValidatorChanges {
+    pub height: ChainEpoch,
+    pub block_hash: Vec<u8>,
+    pub changes: Vec<StakingChangeRequest>,
+}
+
+impl ValidatorChanges {
+    /// The configuration numbers of the changes, in the order they are applied.
+    pub fn configuration_numbers(&self) -> Vec<ConfigurationNumber> {
+        self.changes
+            .iter()
+            .map(|c| c.configuration_number)
+            .collect()
+    }
 }
 
 /// The bottom up checkpoint manager that handles the bottom up relaying from child subnet to the parent
@@ -206,14 +569,16 @@ pub trait TopDownFinalityQuery: Send + Sync {
 pub trait BottomUpCheckpointRelayer: Send + Sync {
     /// Submit a checkpoint for execution.
     /// It triggers the commitment of the checkpoint and the execution of related cross-net messages.
-    /// Returns the epoch that the execution is successful
+    /// The block number of the returned outcome is the epoch that the execution is successful
     async fn submit_checkpoint(
         &self,
         submitter: &Address,
         bundle: BottomUpCheckpointBundle,
-    ) -> Result<ChainEpoch>;
+    ) -> Result<TxResult>;
     /// The last confirmed/submitted checkpoint height.
     async fn last_bottom_up_checkpoint_height(&self, subnet_id: &SubnetID) -> Result<ChainEpoch>;
+    /// The nonce of the next bottom-up message from the child subnet to be executed.
+    async fn applied_bottom_up_nonce(&self, subnet_id: &SubnetID) -> Result<u64>;
     /// Check if the submitter has already submitted in the `last_bottom_up_checkpoint_height`
     async fn has_submitted_in_last_checkpoint_height(
         &self,
@@ -226,6 +591,22 @@ pub trait BottomUpCheckpointRelayer: Send + Sync {
     async fn checkpoint_bundle_at(&self, height: ChainEpoch) -> Result<BottomUpCheckpointBundle>;
     /// Queries the signature quorum reached events at target height.
     async fn quorum_reached_events(&self, height: ChainEpoch) -> Result<Vec<QuorumReachedEvent>>;
+    /// Queries the signature quorum reached events from `from` to `to`, both inclusive.
+    async fn quorum_reached_events_range(
+        &self,
+        from: ChainEpoch,
+        to: ChainEpoch,
+    ) -> Result<Vec<QuorumReachedEvent>> {
+        if from < 0 || to < from {
+            return Err(anyhow!("invalid height range {from}..={to}"));
+        }
+
+        let mut result = vec![];
+        for height in from..=to {
+            result.extend(self.quorum_reached_events(height).await?);
+        }
+        Ok(result)
+    }
     /// Get the current epoch in the current subnet
     async fn current_epoch(&self) -> Result<ChainEpoch>;
 }
@@ -0,0 +1,327 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Retries with exponential backoff and rate limiting of the requests sent to the RPC
+//! endpoints of a subnet.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use ethers::providers::{HttpClientError, JsonRpcError};
+use ipc_sdk::subnet_id::SubnetID;
+use rand::Rng;
+use reqwest::StatusCode;
+use tokio::sync::Mutex;
+
+use crate::config::subnet::RetryConfig;
+use crate::config::Subnet;
+
+/// JSON-RPC error code used by most providers to signal that the rate limit was exceeded.
+const LIMIT_EXCEEDED_CODE: i64 = -32005;
+
+/// Errors that can be classified as transient, i.e. worth retrying.
+pub trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+/// A transient error of an RPC endpoint, e.g. an HTTP response with a 429 or 5xx status.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("transient rpc error: {0}")]
+pub struct TransientError(pub String);
+
+/// Whether an HTTP response with `status` can be retried.
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Whether a JSON-RPC error response signals that the endpoint is rate limiting us. Any
+/// other error response (e.g. a revert) is deterministic and not retried.
+pub fn is_rate_limit_error(e: &JsonRpcError) -> bool {
+    let message = e.message.to_lowercase();
+    e.code == LIMIT_EXCEEDED_CODE
+        || e.code == 429
+        || message.contains("rate limit")
+        || message.contains("too many requests")
+}
+
+fn is_retryable_reqwest_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect() || e.status().map_or(false, is_retryable_status)
+}
+
+impl Retryable for HttpClientError {
+    fn is_retryable(&self) -> bool {
+        match self {
+            HttpClientError::ReqwestError(e) => is_retryable_reqwest_error(e),
+            HttpClientError::JsonRpcError(e) => is_rate_limit_error(e),
+            // the ethers http transport does not check the response status, so rate limited
+            // or unavailable endpoints show up as responses that are not JSON-RPC.
+            HttpClientError::SerdeJson { text, .. } => {
+                let text = text.to_lowercase();
+                text.contains("too many requests")
+                    || text.contains("rate limit")
+                    || text.contains("bad gateway")
+                    || text.contains("service unavailable")
+                    || text.contains("gateway timeout")
+            }
+        }
+    }
+}
+
+impl Retryable for anyhow::Error {
+    fn is_retryable(&self) -> bool {
+        if self.downcast_ref::<TransientError>().is_some() {
+            return true;
+        }
+        if let Some(e) = self.downcast_ref::<reqwest::Error>() {
+            return is_retryable_reqwest_error(e);
+        }
+        if let Some(e) = self.downcast_ref::<HttpClientError>() {
+            return e.is_retryable();
+        }
+        if let Some(e) = self.downcast_ref::<JsonRpcError>() {
+            return is_rate_limit_error(e);
+        }
+        // JSON-RPC errors of the lotus client are only available as text.
+        let message = self.to_string().to_lowercase();
+        message.contains("rate limit") || message.contains("too many requests")
+    }
+}
+
+/// A token bucket limiting the rate of requests to an endpoint. The bucket holds up to
+/// `burst` tokens and is refilled with `requests_per_sec` tokens every second, every
+/// request takes a token or waits until one is available.
+#[derive(Debug)]
+pub struct RateLimiter {
+    requests_per_sec: u32,
+    burst: u32,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_sec: u32, burst: u32) -> Self {
+        let requests_per_sec = requests_per_sec.max(1);
+        let burst = burst.max(1);
+        Self {
+            requests_per_sec,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until a request can be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let refill = now.duration_since(bucket.refilled_at).as_secs_f64()
+                    * self.requests_per_sec as f64;
+                bucket.tokens = (bucket.tokens + refill).min(self.burst as f64);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.requests_per_sec as f64)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// How the requests to the RPC endpoints of a subnet are retried and rate limited.
+///
+/// Transient errors are retried up to `max_retries` times, waiting a random time between
+/// zero and an exponentially growing backoff before each retry ("full jitter"), so that
+/// clients hitting the same endpoint do not retry in lockstep.
+///
+/// The policy is cheap to clone and clones share the same rate limiter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::from_config(&RetryConfig::default())
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_backoff,
+            max_backoff,
+            rate_limiter: None,
+        }
+    }
+
+    /// A policy that sends every request once.
+    pub fn no_retries() -> Self {
+        Self::new(0, Duration::ZERO, Duration::ZERO)
+    }
+
+    pub fn from_config(config: &RetryConfig) -> Self {
+        let policy = Self::new(
+            config.max_retries,
+            Duration::from_millis(config.initial_backoff_ms),
+            Duration::from_millis(config.max_backoff_ms),
+        );
+        match config.requests_per_sec {
+            Some(requests_per_sec) => {
+                policy.with_rate_limit(requests_per_sec, config.burst.unwrap_or(requests_per_sec))
+            }
+            None => policy,
+        }
+    }
+
+    /// Limits the requests sent with this policy to `requests_per_sec`, allowing bursts
+    /// of up to `burst` requests.
+    pub fn with_rate_limit(mut self, requests_per_sec: u32, burst: u32) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(requests_per_sec, burst)));
+        self
+    }
+
+    /// The maximum time to wait before the retry number `attempt` (starting at 1).
+    pub fn max_backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Runs `f` until it succeeds, fails with an error that is not retryable, or the
+    /// retries are exhausted. `what` describes the request in the logs.
+    pub async fn run<T, E, F, Fut>(&self, what: &str, mut f: F) -> Result<T, E>
+    where
+        E: Retryable + std::fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempt = 0;
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire().await;
+            }
+
+            let e = match f().await {
+                Err(e) if e.is_retryable() && attempt < self.max_retries => e,
+                result => return result,
+            };
+
+            attempt += 1;
+            let max_backoff = self.max_backoff(attempt);
+            let backoff = if max_backoff.is_zero() {
+                max_backoff
+            } else {
+                rand::thread_rng().gen_range(Duration::ZERO..=max_backoff)
+            };
+            log::warn!(
+                "{what} failed with a transient error, retry {attempt}/{} in {}ms: {e}",
+                self.max_retries,
+                backoff.as_millis()
+            );
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// Returns the retry policy of `subnet`. The policy is shared by all the clients of the
+/// subnet in the process, so that they are rate limited together.
+pub fn subnet_retry_policy(subnet: &Subnet) -> RetryPolicy {
+    static POLICIES: OnceLock<std::sync::Mutex<HashMap<SubnetID, (RetryConfig, RetryPolicy)>>> =
+        OnceLock::new();
+
+    let config = subnet.retry_config();
+    let mut policies = POLICIES.get_or_init(Default::default).lock().unwrap();
+    match policies.get(&subnet.id) {
+        // the config may have been reloaded with a different policy.
+        Some((c, policy)) if *c == config => policy.clone(),
+        _ => {
+            let policy = RetryPolicy::from_config(&config);
+            policies.insert(subnet.id.clone(), (config, policy.clone()));
+            policy
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{Duration, Instant};
+
+    use super::{RetryPolicy, TransientError};
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(policy.max_backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.max_backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.max_backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.max_backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.max_backoff(100), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_retries_transient_errors_only() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(5));
+
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<u32> = policy
+            .run("test", || async {
+                match calls.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(anyhow::Error::new(TransientError("503".to_string()))),
+                    n => Ok(n),
+                }
+            })
+            .await;
+        assert_eq!(result.unwrap(), 2);
+
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = policy
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(anyhow::anyhow!("reverted"))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let calls = AtomicU32::new(0);
+        let result: anyhow::Result<()> = policy
+            .run("test", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(anyhow::Error::new(TransientError("429".to_string())))
+            })
+            .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let policy = RetryPolicy::no_retries().with_rate_limit(20, 2);
+
+        let start = Instant::now();
+        for _ in 0..4 {
+            let _: anyhow::Result<()> = policy.run("test", || async { Ok(()) }).await;
+        }
+        // the first 2 requests use the burst, the next 2 wait for 50ms each
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
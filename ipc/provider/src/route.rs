@@ -0,0 +1,100 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Planning of the route of a cross-net message through the hierarchy, with the gateways,
+//! fees and checkpoints involved in each hop.
+
+use std::fmt::{Display, Formatter};
+
+use fvm_shared::address::Address;
+use fvm_shared::clock::ChainEpoch;
+use fvm_shared::econ::TokenAmount;
+use ipc_sdk::address::IPCAddress;
+use ipc_sdk::cross::IPCMsgType;
+use ipc_sdk::subnet_id::SubnetID;
+
+/// A hop of a cross-net message between a subnet and its parent or one of its children.
+#[derive(Debug, Clone)]
+pub struct RouteHop {
+    pub from: SubnetID,
+    pub to: SubnetID,
+    pub msg_type: IPCMsgType,
+    /// The gateway in `from` that commits the message for the hop.
+    pub gateway: Address,
+    /// The minimum fee charged by the gateway.
+    pub min_fee: TokenAmount,
+    /// Whether the message must be propagated from the postbox of the gateway, which is the
+    /// case of every hop after the first one.
+    pub propagate: bool,
+    /// The checkpoint period of `from` for bottom-up hops, whose checkpoints must be
+    /// submitted to the parent by a relayer.
+    pub checkpoint_period: Option<ChainEpoch>,
+}
+
+impl Display for RouteHop {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.msg_type {
+            IPCMsgType::BottomUp => write!(f, "{} -> {} (bottom-up)", self.from, self.to)?,
+            IPCMsgType::TopDown => write!(f, "{} -> {} (top-down)", self.from, self.to)?,
+        }
+        write!(f, ", gateway: {}, min fee: {}", self.gateway, self.min_fee)?;
+        if self.propagate {
+            write!(f, ", propagate in {}", self.from)?;
+        }
+        match self.checkpoint_period {
+            Some(period) => write!(
+                f,
+                ", relayer submits checkpoints every {period} blocks to {}",
+                self.to
+            ),
+            None => write!(f, ", committed with the parent finality of {}", self.to),
+        }
+    }
+}
+
+/// The route of a cross-net message from `from` to `to`, see
+/// [`IpcProvider::plan_route`](crate::IpcProvider::plan_route).
+#[derive(Debug, Clone)]
+pub struct RoutePlan {
+    pub from: IPCAddress,
+    pub to: IPCAddress,
+    pub hops: Vec<RouteHop>,
+}
+
+impl RoutePlan {
+    pub fn is_multi_hop(&self) -> bool {
+        self.hops.len() > 1
+    }
+
+    /// The sum of the minimum fees of the gateways along the route.
+    pub fn total_fee(&self) -> TokenAmount {
+        self.hops.iter().map(|h| &h.min_fee).sum()
+    }
+
+    /// The maximum number of blocks the message waits for the checkpoints of the bottom-up
+    /// hops to be cut. Top-down hops also wait for the parent finality to be committed in
+    /// the child, which is not accounted for.
+    pub fn checkpoint_latency(&self) -> ChainEpoch {
+        self.hops.iter().filter_map(|h| h.checkpoint_period).sum()
+    }
+}
+
+impl Display for RoutePlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "route from {} to {}:", self.from, self.to)?;
+        for (i, hop) in self.hops.iter().enumerate() {
+            writeln!(f, "  {}. {hop}", i + 1)?;
+        }
+        writeln!(f, "total min fee: {}", self.total_fee())?;
+
+        let top_down = self
+            .hops
+            .iter()
+            .filter(|h| h.msg_type == IPCMsgType::TopDown)
+            .count();
+        write!(
+            f,
+            "estimated latency: up to {} blocks of checkpoints, plus {top_down} parent finalities",
+            self.checkpoint_latency()
+        )
+    }
+}
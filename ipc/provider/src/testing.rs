@@ -0,0 +1,87 @@
+// Copyright 2022-2023 Protocol Labs
+// SPDX-License-Identifier: MIT
+//! Stand-in rpc endpoints for the tests of the crate.
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use url::Url;
+
+/// Starts a stand-in JSON-RPC endpoint answering every request with the `result` or `error`
+/// returned by `handler` for its method and params. The requests of a batch are answered
+/// in reverse order, so that clients have to match the responses by id.
+pub(crate) async fn rpc_endpoint<F>(handler: F) -> Url
+where
+    F: Fn(&str, &Value) -> Value + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                // keep-alive connections carry several requests.
+                while let Some(request) = read_request(&mut stream).await {
+                    let answer = |request: &Value| {
+                        let method = request["method"].as_str().unwrap_or_default();
+                        let mut response = handler(method, &request["params"]);
+                        response["jsonrpc"] = json!("2.0");
+                        response["id"] = request["id"].clone();
+                        response
+                    };
+                    let response = match &request {
+                        Value::Array(batch) => batch.iter().rev().map(answer).collect(),
+                        request => answer(request),
+                    };
+
+                    let body = response.to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                        body.len()
+                    );
+                    if stream.write_all(response.as_bytes()).await.is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    });
+
+    url
+}
+
+/// Reads the json body of the next http request on `stream`, or `None` once the client
+/// closes the connection.
+async fn read_request(stream: &mut TcpStream) -> Option<Value> {
+    let mut request = vec![];
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        request.extend_from_slice(&buf[..n]);
+
+        let text = String::from_utf8_lossy(&request);
+        let Some((headers, body)) = text.split_once("\r\n\r\n") else {
+            continue;
+        };
+        // read as much of the body as the headers announce.
+        let length = headers
+            .lines()
+            .find_map(|l| {
+                let (name, value) = l.split_once(':')?;
+                name.eq_ignore_ascii_case("content-length")
+                    .then(|| value.trim().parse::<usize>().ok())?
+            })
+            .unwrap_or_default();
+        if body.len() >= length {
+            return Some(serde_json::from_str(&body[..length]).unwrap());
+        }
+    }
+}
@@ -5,6 +5,7 @@
 use crate::address::IPCAddress;
 use crate::subnet_id::SubnetID;
 use anyhow::anyhow;
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
 use fvm_ipld_encoding::RawBytes;
 use fvm_shared::address::Address;
 use fvm_shared::econ::TokenAmount;
@@ -37,7 +38,7 @@ pub struct CrossMsg {
     pub wrapped: bool,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IPCMsgType {
     BottomUp,
     TopDown,
@@ -117,6 +118,133 @@ impl StorableMsg {
     }
 }
 
+/// The method number of `InvokeEVM`, the method of the FEVM actors that executes the
+/// ABI-encoded call in its params.
+pub const METHOD_INVOKE_EVM: MethodNum = 3844450837;
+
+/// Builds cross-net messages calling arbitrary methods of a contract in another subnet, as
+/// opposed to the plain transfers of [`StorableMsg::new_fund_msg`] and
+/// [`StorableMsg::new_release_msg`].
+///
+/// The nonce is set by the gateway when the message is sent, and the fee must be at least
+/// the cross-msg fee of the gateway of the source subnet for the message to be accepted.
+/// An unset fee is zero when built, the provider defaults it to the cross-msg fee instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossMsgBuilder {
+    to: IPCAddress,
+    method: MethodNum,
+    params: RawBytes,
+    value: TokenAmount,
+    fee: Option<TokenAmount>,
+    wrapped: bool,
+}
+
+impl CrossMsgBuilder {
+    /// A message to `to`, which transfers nothing until a method, params or value are set.
+    pub fn new(to: IPCAddress) -> Self {
+        Self {
+            to,
+            method: METHOD_SEND,
+            params: RawBytes::default(),
+            value: TokenAmount::zero(),
+            fee: None,
+            wrapped: false,
+        }
+    }
+
+    pub fn method(mut self, method: MethodNum) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn params(mut self, params: RawBytes) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Calls the Solidity function with `signature`, e.g. `transfer(address,uint256)`,
+    /// with `args` parsed as the types of its parameters, through `InvokeEVM`.
+    pub fn abi_call(self, signature: &str, args: &[String]) -> anyhow::Result<Self> {
+        let function = ethers::abi::AbiParser::default()
+            .parse_function(signature)
+            .map_err(|e| anyhow!("invalid function signature {signature}: {e}"))?;
+        if function.inputs.len() != args.len() {
+            return Err(anyhow!(
+                "{} expects {} arguments, got {}",
+                function.name,
+                function.inputs.len(),
+                args.len()
+            ));
+        }
+
+        let mut tokens = vec![];
+        for (param, arg) in function.inputs.iter().zip(args) {
+            let token = LenientTokenizer::tokenize(&param.kind, arg).map_err(|e| {
+                anyhow!("invalid argument {} of {}: {e}", param.name, function.name)
+            })?;
+            tokens.push(token);
+        }
+        let calldata = function.encode_input(&tokens)?;
+        Ok(self
+            .method(METHOD_INVOKE_EVM)
+            .params(RawBytes::new(calldata)))
+    }
+
+    pub fn value(mut self, value: TokenAmount) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn fee(mut self, fee: TokenAmount) -> Self {
+        self.fee = Some(fee);
+        self
+    }
+
+    pub fn has_fee(&self) -> bool {
+        self.fee.is_some()
+    }
+
+    /// Whether the message is wrapped in the params of the call to the destination, so that
+    /// it learns the original sender of the message.
+    pub fn wrapped(mut self, wrapped: bool) -> Self {
+        self.wrapped = wrapped;
+        self
+    }
+
+    /// Builds the message sent by `from`, checking that it crosses subnets and that the
+    /// destination can execute it.
+    pub fn build(self, from: IPCAddress) -> anyhow::Result<CrossMsg> {
+        if from.subnet()? == self.to.subnet()? {
+            return Err(anyhow!(
+                "source and destination are in the same subnet: {}",
+                self.to.subnet()?
+            ));
+        }
+
+        let to = self.to.raw_addr()?;
+        let is_transfer = self.method == METHOD_SEND && self.params.is_empty();
+        if is_transfer && !IPCAddress::is_valid_account_address(&to) {
+            return Err(anyhow!("invalid destination account address: {to}"));
+        }
+        if !is_transfer && !IPCAddress::is_valid_contract_address(&to) {
+            return Err(anyhow!("invalid destination contract address: {to}"));
+        }
+
+        Ok(CrossMsg {
+            msg: StorableMsg {
+                from,
+                to: self.to,
+                method: self.method,
+                params: self.params,
+                value: self.value,
+                nonce: 0,
+                fee: self.fee.unwrap_or_else(TokenAmount::zero),
+            },
+            wrapped: self.wrapped,
+        })
+    }
+}
+
 pub fn is_bottomup(from: &SubnetID, to: &SubnetID) -> bool {
     let index = match from.common_parent(to) {
         Some((ind, _)) => ind,
@@ -126,6 +254,60 @@ pub fn is_bottomup(from: &SubnetID, to: &SubnetID) -> bool {
     from.children_as_ref().len() > index
 }
 
+/// A hop of a cross-net message between a subnet and its parent or one of its children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossMsgHop {
+    pub from: SubnetID,
+    pub to: SubnetID,
+}
+
+impl CrossMsgHop {
+    pub fn msg_type(&self) -> IPCMsgType {
+        if is_bottomup(&self.from, &self.to) {
+            IPCMsgType::BottomUp
+        } else {
+            IPCMsgType::TopDown
+        }
+    }
+}
+
+/// Returns the hops of a cross-net message from `from` to `to` through the hierarchy,
+/// going up to their common parent and then down to the destination.
+pub fn cross_msg_route(from: &SubnetID, to: &SubnetID) -> anyhow::Result<Vec<CrossMsgHop>> {
+    let (_, common) = from
+        .common_parent(to)
+        .ok_or_else(|| anyhow!("{from} and {to} are not in the same hierarchy"))?;
+    if from == to {
+        return Err(anyhow!(
+            "source and destination are in the same subnet: {to}"
+        ));
+    }
+
+    let mut hops = vec![];
+    let mut current = from.clone();
+    while current != common {
+        let parent = current
+            .parent()
+            .ok_or_else(|| anyhow!("{current} has no parent"))?;
+        hops.push(CrossMsgHop {
+            from: current,
+            to: parent.clone(),
+        });
+        current = parent;
+    }
+    while &current != to {
+        let child = to
+            .down(&current)
+            .ok_or_else(|| anyhow!("cannot move down from {current} to {to}"))?;
+        hops.push(CrossMsgHop {
+            from: current,
+            to: child.clone(),
+        });
+        current = child;
+    }
+    Ok(hops)
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, Default, Serialize_tuple, Deserialize_tuple)]
 pub struct CrossMsgs {
     // FIXME: Consider to make this an AMT if we expect
@@ -186,6 +368,110 @@ mod tests {
         bottom_up("/r123/f01/f02", "/r123/f01/f02/f03", false);
     }
 
+    #[test]
+    fn test_cross_msg_builder() {
+        let from = IPCAddress::from_str("/r123/f01:f0100").unwrap();
+        let contract = Address::new_delegated(10, &[1; 20]).unwrap();
+        let to = IPCAddress::new(&SubnetID::from_str("/r123").unwrap(), &contract).unwrap();
+
+        let msg = CrossMsgBuilder::new(to.clone())
+            .abi_call(
+                "transfer(address,uint256)",
+                &[
+                    "0x0101010101010101010101010101010101010101".to_string(),
+                    "1000".to_string(),
+                ],
+            )
+            .unwrap()
+            .value(TokenAmount::from_whole(1))
+            .wrapped(true)
+            .build(from.clone())
+            .unwrap();
+        assert_eq!(msg.msg.method, METHOD_INVOKE_EVM);
+        // the 4-byte selector followed by the two 32-byte words of the arguments
+        assert_eq!(msg.msg.params.len(), 4 + 2 * 32);
+        assert_eq!(&msg.msg.params[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(msg.msg.value, TokenAmount::from_whole(1));
+        assert!(msg.wrapped);
+
+        // wrong number or type of arguments
+        assert!(CrossMsgBuilder::new(to.clone())
+            .abi_call("transfer(address,uint256)", &["1000".to_string()])
+            .is_err());
+        assert!(CrossMsgBuilder::new(to.clone())
+            .abi_call("transfer(address)", &["1000".to_string()])
+            .is_err());
+
+        // calls must target a contract, and messages must cross subnets
+        let account = IPCAddress::from_str("/r123:f0200").unwrap();
+        assert!(CrossMsgBuilder::new(account.clone())
+            .method(METHOD_INVOKE_EVM)
+            .build(from.clone())
+            .is_err());
+        assert!(CrossMsgBuilder::new(account)
+            .value(TokenAmount::from_whole(1))
+            .build(from.clone())
+            .is_ok());
+        assert!(CrossMsgBuilder::new(to.clone())
+            .method(METHOD_INVOKE_EVM)
+            .build(IPCAddress::new(&to.subnet().unwrap(), &Address::new_id(100)).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn test_cross_msg_route() {
+        let route = |a: &str, b: &str| {
+            cross_msg_route(
+                &SubnetID::from_str(a).unwrap(),
+                &SubnetID::from_str(b).unwrap(),
+            )
+            .map(|hops| {
+                hops.into_iter()
+                    .map(|h| (h.from.to_string(), h.to.to_string(), h.msg_type()))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            route("/r123/f01/f02", "/r123/f03").unwrap(),
+            vec![
+                (
+                    "/r123/f01/f02".to_string(),
+                    "/r123/f01".to_string(),
+                    IPCMsgType::BottomUp
+                ),
+                (
+                    "/r123/f01".to_string(),
+                    "/r123".to_string(),
+                    IPCMsgType::BottomUp
+                ),
+                (
+                    "/r123".to_string(),
+                    "/r123/f03".to_string(),
+                    IPCMsgType::TopDown
+                ),
+            ]
+        );
+        assert_eq!(
+            route("/r123", "/r123/f01/f02").unwrap(),
+            vec![
+                (
+                    "/r123".to_string(),
+                    "/r123/f01".to_string(),
+                    IPCMsgType::TopDown
+                ),
+                (
+                    "/r123/f01".to_string(),
+                    "/r123/f01/f02".to_string(),
+                    IPCMsgType::TopDown
+                ),
+            ]
+        );
+        assert_eq!(route("/r123/f01", "/r123").unwrap().len(), 1);
+        assert!(route("/r123/f01", "/r123/f01").is_err());
+        assert!(route("/r123/f01", "/r124/f01").is_err());
+    }
+
     fn bottom_up(a: &str, b: &str, res: bool) {
         assert_eq!(
             is_bottomup(
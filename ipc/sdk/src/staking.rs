@@ -68,6 +68,32 @@ pub struct ValidatorStakingInfo {
     metadata: Vec<u8>,
 }
 
+impl ValidatorStakingInfo {
+    pub fn new(
+        confirmed_collateral: TokenAmount,
+        total_collateral: TokenAmount,
+        metadata: Vec<u8>,
+    ) -> Self {
+        Self {
+            confirmed_collateral,
+            total_collateral,
+            metadata,
+        }
+    }
+
+    pub fn confirmed_collateral(&self) -> &TokenAmount {
+        &self.confirmed_collateral
+    }
+
+    pub fn total_collateral(&self) -> &TokenAmount {
+        &self.total_collateral
+    }
+
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+}
+
 impl Display for ValidatorStakingInfo {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -111,3 +137,101 @@ impl Display for ValidatorInfo {
         )
     }
 }
+
+/// A validator of a subnet with its position in the validator set.
+#[derive(Clone, Debug)]
+pub struct SubnetValidator {
+    pub address: Address,
+    pub info: ValidatorInfo,
+    /// The weight of the validator in the checkpoint quorums, its confirmed collateral if
+    /// it is active and zero if it is waiting.
+    pub power: TokenAmount,
+    /// The position of the validator, starting at 1, when ranked by confirmed collateral.
+    pub rank: usize,
+}
+
+impl SubnetValidator {
+    /// Ranks `validators` by confirmed collateral, from the highest, as the subnet actor
+    /// does to select the active validators. Ties are broken by address.
+    pub fn ranked(validators: Vec<(Address, ValidatorInfo)>) -> Vec<SubnetValidator> {
+        let mut validators = validators;
+        validators.sort_by(|(a1, v1), (a2, v2)| {
+            v2.staking
+                .confirmed_collateral
+                .cmp(&v1.staking.confirmed_collateral)
+                .then(a1.cmp(a2))
+        });
+        validators
+            .into_iter()
+            .enumerate()
+            .map(|(i, (address, info))| SubnetValidator {
+                address,
+                power: if info.is_active {
+                    info.staking.confirmed_collateral.clone()
+                } else {
+                    TokenAmount::default()
+                },
+                info,
+                rank: i + 1,
+            })
+            .collect()
+    }
+}
+
+impl Display for SubnetValidator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let status = if self.info.is_active {
+            "active"
+        } else if self.info.is_waiting {
+            "waiting"
+        } else {
+            "inactive"
+        };
+        write!(
+            f,
+            "#{} {} ({status}): confirmed collateral: {}, total collateral: {}, power: {}, metadata: 0x{}",
+            self.rank,
+            self.address,
+            self.info.staking.confirmed_collateral,
+            self.info.staking.total_collateral,
+            self.power,
+            hex::encode(&self.info.staking.metadata)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+    use fvm_shared::econ::TokenAmount;
+
+    use super::{SubnetValidator, ValidatorInfo, ValidatorStakingInfo};
+
+    fn validator(collateral: u64, is_active: bool) -> ValidatorInfo {
+        let collateral = TokenAmount::from_whole(collateral);
+        ValidatorInfo {
+            staking: ValidatorStakingInfo::new(collateral.clone(), collateral, vec![]),
+            is_active,
+            is_waiting: !is_active,
+        }
+    }
+
+    #[test]
+    fn test_ranked_validators() {
+        let validators = SubnetValidator::ranked(vec![
+            (Address::new_id(3), validator(5, false)),
+            (Address::new_id(2), validator(10, true)),
+            (Address::new_id(1), validator(10, true)),
+        ]);
+
+        let addresses = validators.iter().map(|v| v.address).collect::<Vec<_>>();
+        assert_eq!(
+            addresses,
+            vec![Address::new_id(1), Address::new_id(2), Address::new_id(3)]
+        );
+        let ranks = validators.iter().map(|v| v.rank).collect::<Vec<_>>();
+        assert_eq!(ranks, vec![1, 2, 3]);
+        assert_eq!(validators[0].power, TokenAmount::from_whole(10));
+        assert_eq!(validators[2].power, TokenAmount::default());
+    }
+}